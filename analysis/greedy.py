@@ -3,14 +3,13 @@ def main():
     file = open("../scenario.txt", "r")
 
     header = file.readline().split(",")
-    header = map(int, header)
-    (number_of_objects, max_weight, max_size) = tuple(header)
+    (number_of_objects, max_weight, max_size) = (int(header[0]), float(header[1]), float(header[2]))
 
     items = []
 
     for item in file.readlines():
         item = item.split(",")
-        item = map(int, item)
+        item = map(float, item)
 
         (weight, size, cost) = tuple(item)
 
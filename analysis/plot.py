@@ -8,16 +8,17 @@ def read_file(filename):
         .read() \
         .split("\n")
 
-    results = map(lambda x: int(x), results)
+    # lines hold the evaluation count and the best score
+    results = map(lambda x: float(x.split(",")[-1]), results)
     results = list(results)
-    return np.asarray(results, dtype=int)
+    return np.asarray(results, dtype=float)
 
 
 def average_results(results_list):
     generations = results_list[0].size
     universes_number = len(results_list)
 
-    acc = np.zeros(generations, dtype=int)
+    acc = np.zeros(generations, dtype=float)
 
     for results in results_list:
         acc = acc + results
@@ -0,0 +1,59 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use genet::population::random_vector::random_vector;
+use genet::population::tournament::tournament;
+use nalgebra::DVector;
+use rand::distributions::{Distribution, Uniform};
+use rand::thread_rng;
+
+/// Previous implementation of the tournament kept as a baseline.
+///
+/// It draws a {0, 1} mask as long as the whole population and multiplies it by scores.
+fn masked_tournament(scores: &DVector<i32>, tournament_size: usize) -> usize {
+    let mut selector = random_vector(tournament_size, scores.nrows());
+    selector.component_mul_assign(scores);
+    let (best_idx, _) = selector.argmax();
+    best_idx
+}
+
+fn random_scores(population_size: usize) -> DVector<i32> {
+    let mut rng = thread_rng();
+    let scores = Uniform::from(0..1000);
+    DVector::from_fn(population_size, |_, _| scores.sample(&mut rng))
+}
+
+fn bench_tournament(c: &mut Criterion) {
+    let mut group = c.benchmark_group("tournament");
+    let mut rng = thread_rng();
+
+    for population_size in [100, 1_000, 10_000].iter().cloned() {
+        let scores = random_scores(population_size);
+
+        // a small tournament and the same 20% ratio as used in analysis/run.sh
+        for tournament_size in [4, population_size * 20 / 100].iter().cloned() {
+            let parameter = format!("{}/{}", population_size, tournament_size);
+
+            group.bench_with_input(
+                BenchmarkId::new("masked", &parameter),
+                &scores,
+                |b, scores| b.iter(|| masked_tournament(scores, tournament_size)),
+            );
+
+            group.bench_with_input(
+                BenchmarkId::new("without_replacement", &parameter),
+                &scores,
+                |b, scores| b.iter(|| tournament(scores, tournament_size, false, &mut rng)),
+            );
+
+            group.bench_with_input(
+                BenchmarkId::new("with_replacement", &parameter),
+                &scores,
+                |b, scores| b.iter(|| tournament(scores, tournament_size, true, &mut rng)),
+            );
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_tournament);
+criterion_main!(benches);
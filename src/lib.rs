@@ -0,0 +1,11 @@
+extern crate crossbeam;
+extern crate crossbeam_utils;
+extern crate nalgebra as na;
+extern crate rand;
+extern crate rayon;
+
+pub mod consts;
+pub mod number;
+pub mod population;
+pub mod scenario;
+pub mod train;
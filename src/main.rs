@@ -1,20 +1,32 @@
-extern crate crossbeam;
-extern crate crossbeam_utils;
-extern crate nalgebra as na;
-extern crate rand;
-extern crate rayon;
-
-mod consts;
-mod population;
-mod scenario;
-mod train;
-
-use crate::consts::{DynResult, Number};
-use crate::population::config::Config as PopulationConfig;
-use crate::scenario::Scenario;
-use crate::Opt::{Generate, PrintScenario, Train};
+use crate::Opt::{
+    Analyze, Convert, Export, Generate, Import, Island, PrintScenario, Train, Verify,
+};
+use genet::consts::DynResult;
+use genet::number::{Number, NumberType};
+use genet::population::cellular::{CellularConfig, Grid, Neighbourhood, UpdateMode};
+use genet::population::config::Config as PopulationConfig;
+use genet::population::control::{ControlConfig, ControlMethod};
+use genet::population::diversity::{DiversityConfig, DuplicatePolicy};
+use genet::population::island::{EmigrantPolicy, MigrationConfig, ReplacementPolicy, Topology};
+use genet::population::network::NetworkConfig;
+use genet::population::niching::{NichingConfig, NichingMethod};
+use genet::population::operators::{Operator, OperatorConfig, SelectionStrategy};
+use genet::population::steady_state::{Replacement, SteadyStateConfig};
+use genet::population::survivors::{SurvivorConfig, SurvivorSelection};
+use genet::scenario::analysis::Analysis;
+use genet::scenario::generator::{Capacity, GeneratorConfig, InstanceClass};
+use genet::scenario::import::{Filter, ImportConfig};
+use genet::scenario::solution::{load_solution, load_solver_solution, SolutionFormat};
+use genet::scenario::solver::SolverFormat;
+use genet::scenario::{LoadOptions, Scenario, ScenarioFormat};
+use genet::train::{train, TrainConfig};
+use genet::with_number_type;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::process;
+use std::time::{Duration, Instant};
 use structopt::StructOpt;
-use train::train;
 
 #[derive(Debug, StructOpt)]
 #[structopt(
@@ -28,106 +40,1438 @@ enum Opt {
     Generate {
         output_file: String,
 
-        #[structopt(short, long, help = "Output file")]
-        number_of_objects: Number,
+        #[structopt(short, long, help = "Number of objects [usize]")]
+        number_of_objects: usize,
 
-        #[structopt(short = "w", long, help = "Max weight [Number]")]
-        max_weight: Number,
+        #[structopt(
+            short = "w",
+            long,
+            required_unless = "weight-tightness",
+            help = "Max weight [Number]"
+        )]
+        max_weight: Option<String>,
 
-        #[structopt(short = "s", long, help = "Max size [Number]")]
-        max_size: Number,
+        #[structopt(
+            long,
+            use_delimiter = true,
+            conflicts_with = "max-weight",
+            help = "Max weight as a fraction of the total weight of all objects [float64]. Has to be in range (0, 1], the literature uses 0.25, 0.5 and 0.75. Several comma separated levels generate one scenario each"
+        )]
+        weight_tightness: Vec<f64>,
+
+        #[structopt(
+            short = "s",
+            long,
+            required_unless = "size-tightness",
+            help = "Max size [Number]"
+        )]
+        max_size: Option<String>,
+
+        #[structopt(
+            long,
+            use_delimiter = true,
+            conflicts_with = "max-size",
+            help = "Max size as a fraction of the total size of all objects [float64]. Has to be in range (0, 1], the literature uses 0.25, 0.5 and 0.75. Several comma separated levels generate one scenario each"
+        )]
+        size_tightness: Vec<f64>,
+
+        #[structopt(
+            long,
+            default_value = "uncorrelated",
+            possible_values = &InstanceClass::VARIANTS,
+            help = "Instance class, see D. Pisinger \"Where are the hard knapsack problems?\""
+        )]
+        class: InstanceClass,
+
+        #[structopt(
+            short,
+            long,
+            help = "Data range R [u64]. Weights and sizes are drawn from [1, R]. Defaults to 10 * max(max_weight, max_size) / number_of_objects or 1000 if both capacities are given as tightness"
+        )]
+        range: Option<u64>,
+
+        #[structopt(
+            long,
+            default_value = "1",
+            help = "Share of the weight in the value costs are correlated with [float64]"
+        )]
+        weight_correlation: f64,
+
+        #[structopt(
+            long,
+            default_value = "1",
+            help = "Share of the size in the value costs are correlated with [float64]"
+        )]
+        size_correlation: f64,
+
+        #[structopt(
+            long,
+            default_value = "3",
+            help = "Step d of the profit-ceiling class [u64]"
+        )]
+        ceiling_step: u64,
+
+        #[structopt(long, help = "Factor d of the circle class [float64]. Defaults to 2/3")]
+        circle_factor: Option<f64>,
+
+        #[structopt(
+            long,
+            default_value = "2",
+            help = "Number of objects in the spanner set of the spanner class [usize]"
+        )]
+        spanner_size: usize,
+
+        #[structopt(
+            long,
+            default_value = "10",
+            help = "The largest multiplier of the spanner class [u64]"
+        )]
+        spanner_multiplier: u64,
+
+        #[structopt(flatten)]
+        number_type: NumberTypeOpt,
     },
 
     #[structopt(about = "Generates new population and trains it for given scenario")]
     Train {
+        #[structopt(flatten)]
+        train: TrainOpt,
+    },
+
+    #[structopt(
+        about = "Trains like train and exchanges migrants with other processes over TCP. Peers may start later or stop earlier"
+    )]
+    Island {
+        #[structopt(flatten)]
+        train: TrainOpt,
+
+        #[structopt(
+            long,
+            help = "Address migrants are received on, for example 127.0.0.1:7001"
+        )]
+        listen: String,
+
+        #[structopt(
+            long = "peer",
+            number_of_values = 1,
+            help = "Address of a process migrants are sent to. May be given several times"
+        )]
+        peers: Vec<String>,
+
+        #[structopt(
+            long,
+            default_value = "1000",
+            help = "Limit of connecting and sending to a peer in milliseconds [u64]"
+        )]
+        peer_timeout: u64,
+    },
+
+    #[structopt(about = "Reports statistics and bounds of given scenario")]
+    Analyze {
         #[structopt(help = "Input file")]
         input_file: String,
 
+        #[structopt(long, help = "Prints the report as JSON")]
+        json: bool,
+
+        #[structopt(flatten)]
+        load: LoadOpt,
+
+        #[structopt(flatten)]
+        number_type: NumberTypeOpt,
+    },
+
+    #[structopt(about = "Builds scenario out of a CSV table with arbitrary columns")]
+    Import {
+        #[structopt(help = "Input CSV file with a header row")]
+        input_file: String,
+
         #[structopt(
-            help = "File to which best individuals from each generation will be saved. If no such file is provided result will be printed out"
+            help = "Output file. Use the .json extension to keep ids and names of the objects"
         )]
-        result_file: Option<String>,
+        output_file: String,
 
-        #[structopt(short = "l", long, help = "Generation Limit [usize]")]
-        generation_limit: usize,
+        #[structopt(long, help = "Column holding weights of the objects")]
+        weight: String,
 
-        #[structopt(short, long, help = "Population Size [usize]")]
-        population_size: usize,
+        #[structopt(long, help = "Column holding sizes of the objects")]
+        size: String,
+
+        #[structopt(long, help = "Column holding costs of the objects")]
+        cost: String,
 
         #[structopt(
-            short,
             long,
-            help = "Tournament Size [usize]. Has to be in range [1, population_size]"
+            help = "Column holding ids of the objects. Defaults to 1-based numbers of the data rows, skipped rows included"
         )]
-        tournament_size: usize,
+        id: Option<String>,
+
+        #[structopt(long, help = "Column holding names of the objects")]
+        name: Option<String>,
+
+        #[structopt(short = "w", long, help = "Max weight [Number]")]
+        max_weight: String,
+
+        #[structopt(short = "s", long, help = "Max size [Number]")]
+        max_size: String,
+
+        #[structopt(
+            long = "filter",
+            number_of_values = 1,
+            help = "Imports only rows matching `column<operator>value`, e.g. `status!=discontinued` or `kg<=30`. Operators are =, !=, <, <=, >, >=. May be given multiple times"
+        )]
+        filters: Vec<Filter>,
+
+        #[structopt(long, default_value = ",", help = "Field delimiter [ASCII char]")]
+        delimiter: char,
+
+        #[structopt(flatten)]
+        number_type: NumberTypeOpt,
+    },
+
+    #[structopt(about = "Checks whether solution fits into given scenario")]
+    Verify {
+        #[structopt(help = "Scenario file")]
+        input_file: String,
+
+        #[structopt(
+            help = "Solution file listing chosen objects separated by commas or whitespace, such as the `Best objects` line printed by train"
+        )]
+        solution_file: String,
 
         #[structopt(
-            short,
             long,
-            help = "Crossover Probability [float64]. Has to be in range [0, 1]"
+            possible_values = &SolutionFormat::VARIANTS,
+            help = "How chosen objects are listed: 0-based indices, ids, a 0/1 value per object or a solution file of an external solver. Detected if not given"
         )]
-        crossover_probability: f64,
+        solution_format: Option<SolutionFormat>,
 
         #[structopt(
-            short,
             long,
-            help = "Mutation Probability [float64]. Has to be in range [0, 1]"
+            help = "Solution file of an external solver for the scenario exported with export. Its optimum is verified as well and the gap to it is reported"
         )]
-        mutation_probability: f64,
+        optimum: Option<String>,
+
+        #[structopt(
+            long,
+            default_value = "0",
+            help = "Tolerance [Number]. Total weight and size may exceed their limits by this much and still be feasible"
+        )]
+        tolerance: String,
+
+        #[structopt(flatten)]
+        load: LoadOpt,
+
+        #[structopt(flatten)]
+        number_type: NumberTypeOpt,
+    },
+
+    #[structopt(about = "Exports scenario as a 0-1 program for external MIP solvers")]
+    Export {
+        #[structopt(help = "Input file")]
+        input_file: String,
+
+        #[structopt(
+            help = "Output file. Objects become binary variables x0, x1, ... named after their indices"
+        )]
+        output_file: String,
 
         #[structopt(
-            short,
             long,
-            help = "Epsilon [float64]. Stops training when changes between generation are smaller then epsilon"
+            possible_values = &SolverFormat::VARIANTS,
+            help = "CPLEX LP or free MPS. Detected from the extension of the output file if not given"
         )]
-        epsilon: Option<f64>,
+        format: Option<SolverFormat>,
+
+        #[structopt(flatten)]
+        load: LoadOpt,
+
+        #[structopt(flatten)]
+        number_type: NumberTypeOpt,
+    },
+
+    #[structopt(about = "Converts scenario between formats")]
+    Convert {
+        #[structopt(help = "Input file in any format")]
+        input_file: String,
+
+        #[structopt(
+            help = "Output file. The format is chosen by the extension: .bin for binary, .json for JSON and positional otherwise"
+        )]
+        output_file: String,
+
+        #[structopt(flatten)]
+        load: LoadOpt,
+
+        #[structopt(flatten)]
+        number_type: NumberTypeOpt,
     },
 
     #[structopt(about = "Loads and prints given scenario")]
     PrintScenario {
         #[structopt(help = "Input file")]
         input_file: String,
+
+        #[structopt(flatten)]
+        load: LoadOpt,
+
+        #[structopt(flatten)]
+        number_type: NumberTypeOpt,
     },
 }
 
-fn main() -> DynResult<()> {
-    match Opt::from_args() {
-        Generate {
-            number_of_objects,
-            max_weight,
-            max_size,
-            output_file,
-        } => Scenario::generate(number_of_objects, max_weight, max_size, output_file)?,
+#[derive(Debug, StructOpt)]
+struct TrainOpt {
+    #[structopt(
+        help = "Input file. Binary scenarios are memory mapped and trained on without copying their columns"
+    )]
+    input_file: String,
+
+    #[structopt(
+        help = "File to which the evaluation count and the best score of each generation will be saved. If no such file is provided result will be printed out"
+    )]
+    result_file: Option<String>,
+
+    #[structopt(short = "l", long, help = "Generation Limit [usize]")]
+    generation_limit: usize,
+
+    #[structopt(
+        short,
+        long,
+        require_delimiter = true,
+        help = "Population Size [usize]. Several comma separated values give each island its own"
+    )]
+    population_size: Vec<usize>,
+
+    #[structopt(
+        short,
+        long,
+        require_delimiter = true,
+        help = "Tournament Size [usize]. Has to be in range [1, population_size]. Several comma separated values give each island its own"
+    )]
+    tournament_size: Vec<usize>,
+
+    #[structopt(
+        long,
+        help = "Draws tournament contestants with replacement. Allows tournament size greater then population size"
+    )]
+    tournament_with_replacement: bool,
+
+    #[structopt(
+        short,
+        long,
+        require_delimiter = true,
+        help = "Crossover Probability [float64]. Has to be in range [0, 1]. Several comma separated values give each island its own"
+    )]
+    crossover_probability: Vec<f64>,
+
+    #[structopt(
+        short,
+        long,
+        require_delimiter = true,
+        help = "Mutation Probability [float64]. Has to be in range [0, 1]. Several comma separated values give each island its own"
+    )]
+    mutation_probability: Vec<f64>,
+
+    #[structopt(
+        short,
+        long,
+        help = "Epsilon [float64]. Stops training when changes between generation are smaller then epsilon"
+    )]
+    epsilon: Option<f64>,
+
+    #[structopt(
+        long,
+        default_value = "0",
+        help = "Tolerance [Number]. Total weight and size may exceed their limits by this much and still be feasible"
+    )]
+    tolerance: String,
+
+    #[structopt(
+        long,
+        help = "Decides objects which never fit, are dominated or are fixed by LP reduced costs before training and trains on the remaining ones"
+    )]
+    reduce: bool,
+
+    #[structopt(flatten)]
+    islands: IslandOpt,
+
+    #[structopt(flatten)]
+    cellular: CellularOpt,
+
+    #[structopt(flatten)]
+    steady_state: SteadyStateOpt,
+
+    #[structopt(flatten)]
+    survivors: SurvivorOpt,
+
+    #[structopt(flatten)]
+    diversity: DiversityOpt,
 
-        Train {
+    #[structopt(flatten)]
+    niching: NichingOpt,
+
+    #[structopt(flatten)]
+    control: ControlOpt,
+
+    #[structopt(flatten)]
+    operators: OperatorOpt,
+
+    #[structopt(
+        long,
+        help = "Stops training once this many individuals were scored on all islands together [usize]"
+    )]
+    evaluation_limit: Option<usize>,
+
+    #[structopt(
+        long,
+        help = "CSV file to which best, worst and mean score and its standard deviation of each island and of all of them are written every generation"
+    )]
+    stats_file: Option<String>,
+
+    #[structopt(
+        long,
+        help = "Keeps this many of the best distinct feasible solutions of the whole run and writes them at the end [usize]"
+    )]
+    hall_of_fame: Option<usize>,
+
+    #[structopt(
+        long,
+        default_value = "1",
+        help = "Number of objects in which solutions of the hall of fame differ at least [usize]"
+    )]
+    hall_of_fame_distance: usize,
+
+    #[structopt(
+        long,
+        requires = "hall-of-fame",
+        help = "CSV file to which cost, weight, size and objects of each solution of the hall of fame are written. If no such file is provided they are printed out"
+    )]
+    hall_of_fame_file: Option<String>,
+
+    #[structopt(flatten)]
+    load: LoadOpt,
+
+    #[structopt(flatten)]
+    number_type: NumberTypeOpt,
+}
+
+impl TrainOpt {
+    fn run(self, network: Option<NetworkConfig>) -> DynResult<()> {
+        let TrainOpt {
             input_file,
             result_file,
             generation_limit,
             population_size,
             tournament_size,
+            tournament_with_replacement,
             crossover_probability,
             mutation_probability,
             epsilon,
-        } => {
-            let population_config = PopulationConfig {
-                population_size,
-                tournament_size,
-                crossover_probability,
-                mutation_probability,
-            };
-            train(
+            tolerance,
+            reduce,
+            islands,
+            cellular,
+            steady_state,
+            survivors,
+            diversity,
+            niching,
+            control,
+            operators,
+            evaluation_limit,
+            stats_file,
+            hall_of_fame,
+            hall_of_fame_distance,
+            hall_of_fame_file,
+            load,
+            number_type,
+        } = self;
+
+        let n = islands.islands;
+        if n == 0 {
+            Err("There has to be at least one island")?
+        }
+
+        let population_size = per_island("Population size", &population_size, n)?;
+        let tournament_size = per_island("Tournament size", &tournament_size, n)?;
+        let crossover_probability = per_island("Crossover probability", &crossover_probability, n)?;
+        let mutation_probability = per_island("Mutation probability", &mutation_probability, n)?;
+        let cellular_config = cellular.config()?;
+        let steady_state_config = steady_state.config();
+        let diversity_config = diversity.config()?;
+        let niching_config = niching.config();
+        let control_config = control.config(generation_limit);
+        let operator_config = operators.config()?;
+        let survivor_configs = population_size
+            .iter()
+            .map(|size| survivors.config(*size))
+            .collect::<DynResult<Vec<_>>>()?;
+
+        let island_configs = (0..n)
+            .map(|i| PopulationConfig {
+                population_size: population_size[i],
+                tournament_size: tournament_size[i],
+                tournament_with_replacement,
+                crossover_probability: crossover_probability[i],
+                mutation_probability: mutation_probability[i],
+                cellular: cellular_config,
+                steady_state: steady_state_config,
+                survivors: survivor_configs[i],
+                diversity: diversity_config,
+                niching: niching_config,
+                control: control_config,
+                operators: operator_config.clone(),
+            })
+            .collect::<Vec<_>>();
+        for config in island_configs.iter() {
+            config.validate()?;
+        }
+        let train_config = TrainConfig {
+            generation_limit,
+            evaluation_limit,
+            epsilon,
+            reduce,
+            niches: niching.niches,
+            migration: islands.migration()?,
+            stats_file,
+            neighbourhood_stats_file: cellular.neighbourhood_stats_file,
+            operator_stats_file: operators.operator_stats_file,
+            network,
+            hall_of_fame,
+            hall_of_fame_distance,
+            hall_of_fame_file,
+        };
+        with_number_type!(
+            number_type.value,
+            run_train(
                 input_file,
                 result_file,
-                population_config,
-                generation_limit,
-                epsilon,
+                island_configs,
+                &train_config,
+                &tolerance,
+                &load.options(),
+            )
+        )
+    }
+}
+
+#[derive(Debug, StructOpt)]
+struct LoadOpt {
+    #[structopt(
+        long,
+        help = "Reports all errors found in the scenario instead of stopping at the first one"
+    )]
+    all_errors: bool,
+}
+
+impl LoadOpt {
+    fn options(&self) -> LoadOptions {
+        LoadOptions {
+            collect_errors: self.all_errors,
+        }
+    }
+}
+
+#[derive(Debug, StructOpt)]
+struct IslandOpt {
+    #[structopt(
+        long,
+        default_value = "1",
+        help = "Number of populations evolving in parallel [usize]"
+    )]
+    islands: usize,
+
+    #[structopt(
+        long,
+        default_value = "10",
+        help = "Generations between migrations [usize]. 0 disables migration"
+    )]
+    migration_interval: usize,
+
+    #[structopt(
+        long,
+        default_value = "0.05",
+        help = "Share of the population of an island which emigrates to each of its neighbours [float64]"
+    )]
+    migration_rate: f64,
+
+    #[structopt(
+        long,
+        default_value = "ring",
+        possible_values = Topology::VARIANTS,
+        help = "Islands emigrants go to: the next one, all other ones or a random one"
+    )]
+    topology: Topology,
+
+    #[structopt(
+        long,
+        default_value = "best",
+        possible_values = EmigrantPolicy::VARIANTS,
+        help = "Individuals which emigrate"
+    )]
+    emigrants: EmigrantPolicy,
+
+    #[structopt(
+        long,
+        default_value = "worst",
+        possible_values = ReplacementPolicy::VARIANTS,
+        help = "Individuals which are replaced by immigrants"
+    )]
+    replacement: ReplacementPolicy,
+}
+
+impl IslandOpt {
+    fn migration(&self) -> DynResult<MigrationConfig> {
+        if !(0.0..=1.0).contains(&self.migration_rate) {
+            Err("Migration rate has to be in range [0, 1]")?
+        }
+
+        Ok(MigrationConfig {
+            interval: self.migration_interval,
+            rate: self.migration_rate,
+            topology: self.topology,
+            emigrants: self.emigrants,
+            replacement: self.replacement,
+        })
+    }
+}
+
+#[derive(Debug, StructOpt)]
+struct CellularOpt {
+    #[structopt(
+        long,
+        help = "Places individuals on a toroidal grid like 10x20 and breeds each of them only with its neighbours. Needs as many cells as individuals"
+    )]
+    grid: Option<Grid>,
+
+    #[structopt(
+        long,
+        default_value = "von-neumann",
+        possible_values = Neighbourhood::VARIANTS,
+        help = "Cells within the radius of a cell: counted in horizontal and vertical steps or in a square"
+    )]
+    neighbourhood: Neighbourhood,
+
+    #[structopt(
+        long,
+        default_value = "1",
+        help = "Radius of the neighbourhood [usize]"
+    )]
+    radius: usize,
+
+    #[structopt(
+        long,
+        default_value = "sync",
+        possible_values = UpdateMode::VARIANTS,
+        help = "Replaces all cells at once or one by one in random order"
+    )]
+    update: UpdateMode,
+
+    #[structopt(
+        long,
+        help = "CSV file to which the mean best and mean score of neighbourhoods, the distance between neighbours and the share of cells holding the best score are written every generation"
+    )]
+    neighbourhood_stats_file: Option<String>,
+}
+
+impl CellularOpt {
+    fn config(&self) -> DynResult<Option<CellularConfig>> {
+        let grid = match self.grid {
+            Some(grid) => grid,
+            None if self.neighbourhood_stats_file.is_some() => {
+                Err("Neighbourhood statistics need a grid")?
+            }
+            None => return Ok(None),
+        };
+
+        Ok(Some(CellularConfig {
+            grid,
+            neighbourhood: self.neighbourhood,
+            radius: self.radius,
+            update: self.update,
+        }))
+    }
+}
+
+#[derive(Debug, StructOpt)]
+struct SteadyStateOpt {
+    #[structopt(
+        long,
+        help = "Replaces a few individuals at a time with children scored right away instead of breeding whole generations. A generation still scores as many children as there are individuals"
+    )]
+    steady_state: bool,
+
+    #[structopt(
+        long,
+        default_value = "2",
+        help = "Children bred in each steady-state step [usize]"
+    )]
+    children: usize,
+
+    #[structopt(
+        long,
+        default_value = "worst",
+        possible_values = Replacement::VARIANTS,
+        help = "Individuals replaced by children in the steady-state mode"
+    )]
+    steady_state_replacement: Replacement,
+}
+
+impl SteadyStateOpt {
+    fn config(&self) -> Option<SteadyStateConfig> {
+        if !self.steady_state {
+            return None;
+        }
+
+        Some(SteadyStateConfig {
+            children: self.children,
+            replacement: self.steady_state_replacement,
+        })
+    }
+}
+
+#[derive(Debug, StructOpt)]
+struct SurvivorOpt {
+    #[structopt(
+        long,
+        help = "Number of children λ bred every generation [usize]. Defaults to the population size μ"
+    )]
+    offspring: Option<usize>,
+
+    #[structopt(
+        long,
+        possible_values = SurvivorSelection::VARIANTS,
+        help = "Survivors are the best of parents and children (plus) or of children only (comma). Defaults to comma when offspring or elitism is given and to replacing the whole population otherwise"
+    )]
+    survivors: Option<SurvivorSelection>,
+
+    #[structopt(
+        long,
+        default_value = "0",
+        help = "Number of the best parents surviving comma-selection [usize]"
+    )]
+    elitism: usize,
+}
+
+impl SurvivorOpt {
+    fn config(&self, population_size: usize) -> DynResult<Option<SurvivorConfig>> {
+        if self.offspring.is_none() && self.survivors.is_none() && self.elitism == 0 {
+            return Ok(None);
+        }
+
+        let selection = self.survivors.unwrap_or(SurvivorSelection::Comma);
+        if selection == SurvivorSelection::Plus && self.elitism > 0 {
+            Err("Plus-selection always keeps the best parents, elitism works only with comma-selection")?
+        }
+
+        Ok(Some(SurvivorConfig {
+            offspring: self.offspring.unwrap_or(population_size),
+            selection,
+            elitism: self.elitism,
+        }))
+    }
+}
+
+#[derive(Debug, StructOpt)]
+struct DiversityOpt {
+    #[structopt(
+        long,
+        possible_values = DuplicatePolicy::VARIANTS,
+        help = "Replaces all but one copy of each genotype after every generation with a mutated copy or a random individual"
+    )]
+    remove_duplicates: Option<DuplicatePolicy>,
+
+    #[structopt(
+        long,
+        default_value = "0",
+        help = "Share of the population replaced by random individuals after every generation [float64]. Has to be in range [0, 1]. The worst individuals are replaced"
+    )]
+    immigrant_rate: f64,
+}
+
+impl DiversityOpt {
+    fn config(&self) -> DynResult<Option<DiversityConfig>> {
+        if !(0.0..=1.0).contains(&self.immigrant_rate) {
+            Err("Immigrant rate has to be in range [0, 1]")?
+        }
+        if self.remove_duplicates.is_none() && self.immigrant_rate == 0.0 {
+            return Ok(None);
+        }
+
+        Ok(Some(DiversityConfig {
+            duplicates: self.remove_duplicates,
+            immigrant_rate: self.immigrant_rate,
+        }))
+    }
+}
+
+#[derive(Debug, StructOpt)]
+struct NichingOpt {
+    #[structopt(
+        long,
+        possible_values = NichingMethod::VARIANTS,
+        help = "Keeps several niches of good individuals: fitness sharing, deterministic crowding or restricted tournament selection"
+    )]
+    niching: Option<NichingMethod>,
+
+    #[structopt(
+        long,
+        default_value = "10",
+        help = "Number of differing objects within which individuals share their score and belong to the same niche [usize]"
+    )]
+    niche_radius: usize,
+
+    #[structopt(
+        long,
+        default_value = "1",
+        help = "Shape α of the sharing function 1 - (distance / radius)^α [float64]"
+    )]
+    sharing_alpha: f64,
+
+    #[structopt(
+        long,
+        default_value = "10",
+        help = "Number of random individuals a child of restricted tournament selection competes with [usize]"
+    )]
+    rts_window: usize,
+
+    #[structopt(
+        long,
+        default_value = "10",
+        help = "Number of niches whose best individuals are printed at the end [usize]"
+    )]
+    niches: usize,
+}
+
+impl NichingOpt {
+    fn config(&self) -> Option<NichingConfig> {
+        self.niching.map(|method| NichingConfig {
+            method,
+            radius: self.niche_radius,
+            alpha: self.sharing_alpha,
+            window: self.rts_window,
+        })
+    }
+}
+
+#[derive(Debug, StructOpt)]
+struct ControlOpt {
+    #[structopt(
+        long,
+        possible_values = ControlMethod::VARIANTS,
+        help = "Changes mutation and crossover probabilities during the run: linear, exponential or step schedules, the 1/5th success rule, diversity-driven or self-adaptive mutation"
+    )]
+    rate_control: Option<ControlMethod>,
+
+    #[structopt(
+        long,
+        help = "Mutation probability schedules end with [float64]. Defaults to the initial one"
+    )]
+    final_mutation_probability: Option<f64>,
+
+    #[structopt(
+        long,
+        help = "Crossover probability schedules end with [float64]. Defaults to the initial one"
+    )]
+    final_crossover_probability: Option<f64>,
+
+    #[structopt(
+        long,
+        help = "Number of generations schedules take to reach the final probabilities [usize]. Defaults to the generation limit"
+    )]
+    schedule_generations: Option<usize>,
+
+    #[structopt(
+        long,
+        default_value = "4",
+        help = "Number of jumps of the step schedule [usize]"
+    )]
+    schedule_steps: usize,
+
+    #[structopt(
+        long,
+        default_value = "1.2",
+        help = "Factor the 1/5th success rule and diversity-driven control multiply or divide mutation probability by every generation [float64]"
+    )]
+    control_factor: f64,
+
+    #[structopt(
+        long,
+        default_value = "0.5",
+        help = "Share of the diversity of the initial population below which diversity-driven control raises mutation probability [float64]"
+    )]
+    target_diversity: f64,
+
+    #[structopt(
+        long,
+        default_value = "0.2",
+        help = "Learning rate of self-adaptive mutation probabilities [float64]"
+    )]
+    self_adaptation_rate: f64,
+}
+
+impl ControlOpt {
+    fn config(&self, generation_limit: usize) -> Option<ControlConfig> {
+        self.rate_control.map(|method| ControlConfig {
+            method,
+            final_mutation_probability: self.final_mutation_probability,
+            final_crossover_probability: self.final_crossover_probability,
+            generations: self.schedule_generations.unwrap_or(generation_limit),
+            steps: self.schedule_steps,
+            factor: self.control_factor,
+            target_diversity: self.target_diversity,
+            learning_rate: self.self_adaptation_rate,
+        })
+    }
+}
+
+#[derive(Debug, StructOpt)]
+struct OperatorOpt {
+    #[structopt(
+        long,
+        possible_values = SelectionStrategy::VARIANTS,
+        help = "Chooses among several operators by the improvements of their children over their parents"
+    )]
+    operator_selection: Option<SelectionStrategy>,
+
+    #[structopt(
+        long,
+        require_delimiter = true,
+        possible_values = Operator::VARIANTS,
+        default_value = "one-point,two-point,uniform,swap",
+        help = "Operators adaptive operator selection chooses among"
+    )]
+    operators: Vec<Operator>,
+
+    #[structopt(
+        long,
+        default_value = "0.05",
+        help = "Smallest probability of choosing each operator by probability matching and adaptive pursuit [float64]"
+    )]
+    min_operator_probability: f64,
+
+    #[structopt(
+        long,
+        default_value = "0.3",
+        help = "Share of the reward of the last generation in the quality of an operator [float64]"
+    )]
+    operator_adaptation_rate: f64,
+
+    #[structopt(
+        long,
+        default_value = "0.3",
+        help = "Learning rate of adaptive pursuit [float64]"
+    )]
+    pursuit_rate: f64,
+
+    #[structopt(
+        long,
+        default_value = "0.5",
+        help = "Weight of the exploration bonus of UCB [float64]"
+    )]
+    ucb_exploration: f64,
+
+    #[structopt(
+        long,
+        help = "CSV file to which usage, credit, quality and probability of each operator of each island are written every generation"
+    )]
+    operator_stats_file: Option<String>,
+}
+
+impl OperatorOpt {
+    fn config(&self) -> DynResult<Option<OperatorConfig>> {
+        let strategy = match self.operator_selection {
+            Some(strategy) => strategy,
+            None if self.operator_stats_file.is_some() => {
+                Err("Operator statistics are written only with --operator-selection")?
+            }
+            None => return Ok(None),
+        };
+
+        Ok(Some(OperatorConfig {
+            operators: self.operators.clone(),
+            strategy,
+            min_probability: self.min_operator_probability,
+            adaptation_rate: self.operator_adaptation_rate,
+            pursuit_rate: self.pursuit_rate,
+            exploration: self.ucb_exploration,
+        }))
+    }
+}
+
+#[derive(Debug, StructOpt)]
+struct NumberTypeOpt {
+    #[structopt(
+        long = "number-type",
+        default_value = "i64",
+        possible_values = &NumberType::VARIANTS,
+        help = "Type used for weights, sizes, costs and scores. Sums which do not fit into it are rejected"
+    )]
+    value: NumberType,
+}
+
+/// Generator options which do not depend on the number type
+struct GenerateOpt {
+    number_of_objects: usize,
+    class: InstanceClass,
+    range: Option<u64>,
+    weight_correlation: f64,
+    size_correlation: f64,
+    ceiling_step: u64,
+    circle_factor: Option<f64>,
+    spanner_size: usize,
+    spanner_multiplier: u64,
+    weight_tightness: Vec<f64>,
+    size_tightness: Vec<f64>,
+}
+
+fn generate<N: Number>(
+    opt: GenerateOpt,
+    max_weight: Option<String>,
+    max_size: Option<String>,
+    output_file: String,
+) -> DynResult<()> {
+    let max_weight = max_weight.as_deref().map(N::parse).transpose()?;
+    let max_size = max_size.as_deref().map(N::parse).transpose()?;
+
+    let weights = capacities(max_weight, &opt.weight_tightness);
+    let sizes = capacities(max_size, &opt.size_tightness);
+    if weights.len() > 1 && sizes.len() > 1 && weights.len() != sizes.len() {
+        Err("weight and size tightness have to have the same number of levels")?
+    }
+
+    // a single level is used with every level of the other resource
+    let levels = (0..weights.len().max(sizes.len()))
+        .map(|i| (weights[i % weights.len()], sizes[i % sizes.len()]))
+        .collect::<Vec<_>>();
+
+    let max_capacity = max_weight
+        .into_iter()
+        .chain(max_size)
+        .map(N::to_f64)
+        .fold(None, |acc: Option<f64>, x| {
+            Some(acc.map_or(x, |acc| acc.max(x)))
+        });
+
+    let config = GeneratorConfig {
+        number_of_objects: opt.number_of_objects,
+        class: opt.class,
+        range: opt.range.unwrap_or_else(|| match max_capacity {
+            Some(max_capacity) => {
+                GeneratorConfig::default_range(opt.number_of_objects, max_capacity)
+            }
+            None => GeneratorConfig::DEFAULT_RANGE,
+        }),
+        weight_correlation: opt.weight_correlation,
+        size_correlation: opt.size_correlation,
+        ceiling_step: opt.ceiling_step,
+        circle_factor: opt.circle_factor.unwrap_or(2.0 / 3.0),
+        spanner_size: opt.spanner_size,
+        spanner_multiplier: opt.spanner_multiplier,
+    };
+
+    let scenarios = Scenario::<N>::generate_levels(&config, &levels)?;
+
+    for (scenario, (weight, size)) in scenarios.iter().zip(levels.iter()) {
+        let output_file = if levels.len() > 1 {
+            level_file(&output_file, *weight, *size)
+        } else {
+            output_file.clone()
+        };
+        scenario.save(&output_file)?;
+
+        println!(
+            "{}: Weight sum: {} Size sum: {} Max weight: {} Max size: {}",
+            output_file,
+            scenario.weights.iter().fold(N::zero(), |acc, x| acc + *x),
+            scenario.sizes.iter().fold(N::zero(), |acc, x| acc + *x),
+            scenario.max_weight,
+            scenario.max_size
+        );
+    }
+    Ok(())
+}
+
+fn capacities<N: Number>(absolute: Option<N>, tightness: &[f64]) -> Vec<Capacity<N>> {
+    match absolute {
+        Some(absolute) => vec![Capacity::Absolute(absolute)],
+        None => tightness.iter().map(|x| Capacity::Tightness(*x)).collect(),
+    }
+}
+
+/// Inserts tightness levels before the extension, `out.txt` becomes `out_w0.25_s0.5.txt`
+fn level_file<N>(output_file: &str, weight: Capacity<N>, size: Capacity<N>) -> String {
+    let path = Path::new(output_file);
+    let mut name = path
+        .file_stem()
+        .map(|x| x.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    for (prefix, capacity) in [("w", weight), ("s", size)].iter() {
+        if let Capacity::Tightness(tightness) = capacity {
+            name.push_str(&format!("_{}{}", prefix, tightness));
+        }
+    }
+    if let Some(extension) = path.extension() {
+        name.push_str(&format!(".{}", extension.to_string_lossy()));
+    }
+
+    path.with_file_name(name).to_string_lossy().into_owned()
+}
+
+/// Gives a value to each island, a single value is shared by all of them
+fn per_island<T: Copy>(name: &str, values: &[T], islands: usize) -> DynResult<Vec<T>> {
+    match values.len() {
+        1 => Ok(vec![values[0]; islands]),
+        n if n == islands => Ok(values.to_vec()),
+        n => Err(format!(
+            "{} has {} values but there are {} islands, give one value or one for each island",
+            name, n, islands
+        ))?,
+    }
+}
+
+fn run_train<N: Number>(
+    input_file: String,
+    result_file: Option<String>,
+    island_configs: Vec<PopulationConfig>,
+    train_config: &TrainConfig,
+    tolerance: &str,
+    load_options: &LoadOptions,
+) -> DynResult<()> {
+    let tolerance = N::parse(tolerance)?;
+    if tolerance < N::zero() {
+        Err("Tolerance has to be non negative")?
+    }
+
+    train(
+        input_file,
+        result_file,
+        island_configs,
+        train_config,
+        tolerance,
+        load_options,
+    )
+}
+
+fn analyze<N: Number>(input_file: String, json: bool, load_options: &LoadOptions) -> DynResult<()> {
+    let scenario = Scenario::<N>::load_with(input_file, load_options)?;
+    let analysis = Analysis::new(&scenario);
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&analysis)?);
+    } else {
+        println!("{}", analysis);
+    }
+    Ok(())
+}
+
+/// Import options which do not depend on the number type
+struct ImportOpt {
+    weight: String,
+    size: String,
+    cost: String,
+    id: Option<String>,
+    name: Option<String>,
+    filters: Vec<Filter>,
+    delimiter: char,
+}
+
+fn import<N: Number>(
+    opt: ImportOpt,
+    max_weight: String,
+    max_size: String,
+    input_file: String,
+    output_file: String,
+) -> DynResult<()> {
+    if !opt.delimiter.is_ascii() {
+        Err("Delimiter has to be an ASCII character")?
+    }
+
+    let config = ImportConfig {
+        weight_column: opt.weight,
+        size_column: opt.size,
+        cost_column: opt.cost,
+        id_column: opt.id,
+        name_column: opt.name,
+        max_weight: N::parse(&max_weight)?,
+        max_size: N::parse(&max_size)?,
+        filters: opt.filters,
+        delimiter: opt.delimiter as u8,
+    };
+
+    let import = Scenario::<N>::import(&input_file, &config)?;
+    import.scenario.save(&output_file)?;
+
+    println!(
+        "{}: Imported {} objects, skipped {} rows",
+        output_file,
+        import.scenario.number_of_objects,
+        import.skipped.len()
+    );
+    for row in import.skipped.iter() {
+        println!("  {}", row);
+    }
+    Ok(())
+}
+
+fn verify<N: Number>(
+    input_file: String,
+    solution_file: String,
+    optimum_file: Option<String>,
+    solution_format: Option<SolutionFormat>,
+    tolerance: &str,
+    load_options: &LoadOptions,
+) -> DynResult<()> {
+    let tolerance = N::parse(tolerance)?;
+    if tolerance < N::zero() {
+        Err("Tolerance has to be non negative")?
+    }
+
+    let scenario = Scenario::<N>::load_with(input_file, load_options)?.with_tolerance(tolerance)?;
+    let solution = load_solution(&solution_file, &scenario, solution_format)?;
+    let verification = scenario.verify(&solution);
+
+    println!("{}", verification);
+
+    if let Some(optimum_file) = optimum_file {
+        let optimum = load_solver_solution(&optimum_file, &scenario)?;
+        let optimum_verification = scenario.verify(&optimum.objects);
+
+        println!("\nOptimum {}", optimum_file);
+        println!("{}", optimum_verification);
+        if let Some(objective) = optimum.objective {
+            let cost = optimum_verification.cost.to_f64();
+            if (objective - cost).abs() > 1e-6 * cost.abs().max(1.0) {
+                println!(
+                    "Solver declares objective {} but the chosen objects cost {}",
+                    objective, cost
+                );
+            }
+        }
+
+        // computed in f64 because unsigned types cannot represent negative difference
+        let (optimum, cost) = (
+            optimum_verification.cost.to_f64(),
+            verification.cost.to_f64(),
+        );
+        let gap = if optimum == 0.0 {
+            0.0
+        } else {
+            (optimum - cost) / optimum.abs()
+        };
+        println!("Gap {} ({:.4}%)", optimum - cost, gap * 100.0);
+    }
+
+    if !verification.is_feasible() {
+        Err(format!(
+            "solution violates {} of 2 constraints",
+            verification.violations.len()
+        ))?
+    }
+    Ok(())
+}
+
+fn export<N: Number>(
+    input_file: String,
+    output_file: String,
+    format: Option<SolverFormat>,
+    load_options: &LoadOptions,
+) -> DynResult<()> {
+    let format = format
+        .or_else(|| SolverFormat::detect(&output_file))
+        .ok_or("Unknown output format, use the .lp or .mps extension or --format")?;
+
+    let scenario = Scenario::<N>::load_with(input_file, load_options)?;
+    let mut file = BufWriter::new(File::create(&output_file)?);
+    scenario.write_solver(format, &mut file)?;
+    file.flush()?;
+
+    println!(
+        "{}: {} binary variables, 2 constraints",
+        output_file, scenario.number_of_objects
+    );
+    Ok(())
+}
+
+fn convert<N: Number>(
+    input_file: String,
+    output_file: String,
+    load_options: &LoadOptions,
+) -> DynResult<()> {
+    let input_format = ScenarioFormat::of_file(&input_file)?;
+    let loading = Instant::now();
+    let scenario = Scenario::<N>::load_with(input_file, load_options)?;
+    let loading = loading.elapsed();
+
+    let saving = Instant::now();
+    scenario.save(&output_file)?;
+    let saving = saving.elapsed();
+
+    println!(
+        "{}: {} objects, {} loading {:?}, {} saving {:?}",
+        output_file,
+        scenario.number_of_objects,
+        input_format.name(),
+        loading,
+        ScenarioFormat::detect(&output_file, "").name(),
+        saving
+    );
+    Ok(())
+}
+
+fn print_scenario<N: Number>(input_file: String, load_options: &LoadOptions) -> DynResult<()> {
+    let scenario = Scenario::<N>::load_with(input_file, load_options)?;
+    println!("{:?}", scenario);
+    Ok(())
+}
+
+fn main() {
+    if let Err(err) = run(Opt::from_args()) {
+        eprintln!("Error: {}", err);
+        process::exit(1);
+    }
+}
+
+fn run(opt: Opt) -> DynResult<()> {
+    match opt {
+        Generate {
+            number_of_objects,
+            max_weight,
+            max_size,
+            output_file,
+            class,
+            range,
+            weight_correlation,
+            size_correlation,
+            ceiling_step,
+            circle_factor,
+            spanner_size,
+            spanner_multiplier,
+            weight_tightness,
+            size_tightness,
+            number_type,
+        } => {
+            let opt = GenerateOpt {
+                number_of_objects,
+                class,
+                range,
+                weight_correlation,
+                size_correlation,
+                ceiling_step,
+                circle_factor,
+                spanner_size,
+                spanner_multiplier,
+                weight_tightness,
+                size_tightness,
+            };
+            with_number_type!(
+                number_type.value,
+                generate(opt, max_weight, max_size, output_file)
             )?
         }
 
-        PrintScenario { input_file } => {
-            let scenario = Scenario::load(input_file)?;
-            println!("{:?}", scenario);
+        Train { train } => train.run(None)?,
+
+        Island {
+            train,
+            listen,
+            peers,
+            peer_timeout,
+        } => train.run(Some(NetworkConfig {
+            listen,
+            peers,
+            timeout: Duration::from_millis(peer_timeout),
+        }))?,
+
+        Analyze {
+            input_file,
+            json,
+            load,
+            number_type,
+        } => with_number_type!(
+            number_type.value,
+            analyze(input_file, json, &load.options())
+        )?,
+
+        Import {
+            input_file,
+            output_file,
+            weight,
+            size,
+            cost,
+            id,
+            name,
+            max_weight,
+            max_size,
+            filters,
+            delimiter,
+            number_type,
+        } => {
+            let opt = ImportOpt {
+                weight,
+                size,
+                cost,
+                id,
+                name,
+                filters,
+                delimiter,
+            };
+            with_number_type!(
+                number_type.value,
+                import(opt, max_weight, max_size, input_file, output_file)
+            )?
         }
+
+        Verify {
+            input_file,
+            solution_file,
+            optimum,
+            solution_format,
+            tolerance,
+            load,
+            number_type,
+        } => with_number_type!(
+            number_type.value,
+            verify(
+                input_file,
+                solution_file,
+                optimum,
+                solution_format,
+                &tolerance,
+                &load.options(),
+            )
+        )?,
+
+        Export {
+            input_file,
+            output_file,
+            format,
+            load,
+            number_type,
+        } => with_number_type!(
+            number_type.value,
+            export(input_file, output_file, format, &load.options())
+        )?,
+
+        Convert {
+            input_file,
+            output_file,
+            load,
+            number_type,
+        } => with_number_type!(
+            number_type.value,
+            convert(input_file, output_file, &load.options())
+        )?,
+
+        PrintScenario {
+            input_file,
+            load,
+            number_type,
+        } => with_number_type!(
+            number_type.value,
+            print_scenario(input_file, &load.options())
+        )?,
     }
 
     Ok(())
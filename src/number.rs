@@ -0,0 +1,231 @@
+use alga::general::{ClosedAdd, ClosedDiv, ClosedMul, ClosedSub};
+use bytemuck::Pod;
+use na::Scalar;
+use num_traits::{One, Zero};
+use rand::distributions::uniform::SampleUniform;
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::str::FromStr;
+
+/// Numeric type used for scenario values, genes and fitness.
+///
+/// Genes are stored with the same type as the scenario so the population
+/// can be evaluated with plain matrix multiplication.
+pub trait Number:
+    Scalar
+    + Copy
+    + PartialOrd
+    + Zero
+    + One
+    + ClosedAdd
+    + ClosedSub
+    + ClosedMul
+    + ClosedDiv
+    + SampleUniform
+    + Display
+    + Send
+    + Sync
+    + Pod
+{
+    /// Type name used on the command line
+    const NAME: &'static str;
+
+    const TYPE: NumberType;
+
+    /// Parses number from its textual representation
+    fn parse(s: &str) -> Result<Self, Box<dyn Error>>;
+
+    /// Returns `None` if the result is not representable
+    fn checked_add(self, rhs: Self) -> Option<Self>;
+
+    /// Returns `None` if the result is not representable
+    fn checked_mul(self, rhs: Self) -> Option<Self>;
+
+    /// Returns `None` if `n` is not representable
+    fn from_usize(n: usize) -> Option<Self>;
+
+    /// Returns `None` if `x` is not representable. Integers round `x` down.
+    fn from_f64(x: f64) -> Option<Self>;
+
+    fn to_f64(self) -> f64;
+}
+
+macro_rules! impl_integer {
+    ($t:ty, $number_type:expr) => {
+        impl Number for $t {
+            const NAME: &'static str = stringify!($t);
+            const TYPE: NumberType = $number_type;
+
+            fn parse(s: &str) -> Result<Self, Box<dyn Error>> {
+                Ok(s.parse::<$t>()?)
+            }
+
+            fn checked_add(self, rhs: Self) -> Option<Self> {
+                <$t>::checked_add(self, rhs)
+            }
+
+            fn checked_mul(self, rhs: Self) -> Option<Self> {
+                <$t>::checked_mul(self, rhs)
+            }
+
+            fn from_usize(n: usize) -> Option<Self> {
+                <$t>::try_from(n).ok()
+            }
+
+            fn from_f64(x: f64) -> Option<Self> {
+                let x = x.floor();
+                if x >= <$t>::MIN as f64 && x <= <$t>::MAX as f64 {
+                    Some(x as $t)
+                } else {
+                    None
+                }
+            }
+
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+        }
+    };
+}
+
+impl_integer!(i32, NumberType::I32);
+impl_integer!(i64, NumberType::I64);
+impl_integer!(u64, NumberType::U64);
+
+impl Number for f64 {
+    const NAME: &'static str = "f64";
+    const TYPE: NumberType = NumberType::F64;
+
+    fn parse(s: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(s.parse::<f64>()?)
+    }
+
+    // Floating point numbers do not wrap, they lose precision and eventually
+    // become infinite. Only the latter is treated as an overflow.
+    fn checked_add(self, rhs: Self) -> Option<Self> {
+        Some(self + rhs).filter(|x| x.is_finite())
+    }
+
+    fn checked_mul(self, rhs: Self) -> Option<Self> {
+        Some(self * rhs).filter(|x| x.is_finite())
+    }
+
+    fn from_usize(n: usize) -> Option<Self> {
+        Some(n as f64)
+    }
+
+    fn from_f64(x: f64) -> Option<Self> {
+        Some(x).filter(|x| x.is_finite())
+    }
+
+    fn to_f64(self) -> f64 {
+        self
+    }
+}
+
+/// Runtime selector of the `Number` implementation
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum NumberType {
+    I32,
+    #[default]
+    I64,
+    U64,
+    F64,
+}
+
+impl NumberType {
+    pub const VARIANTS: [&'static str; 4] = [i32::NAME, i64::NAME, u64::NAME, f64::NAME];
+    pub const ALL: [NumberType; 4] = [
+        NumberType::I32,
+        NumberType::I64,
+        NumberType::U64,
+        NumberType::F64,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            NumberType::I32 => i32::NAME,
+            NumberType::I64 => i64::NAME,
+            NumberType::U64 => u64::NAME,
+            NumberType::F64 => f64::NAME,
+        }
+    }
+}
+
+impl FromStr for NumberType {
+    type Err = UnknownNumberType;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            _ if s == i32::NAME => Ok(NumberType::I32),
+            _ if s == i64::NAME => Ok(NumberType::I64),
+            _ if s == u64::NAME => Ok(NumberType::U64),
+            _ if s == f64::NAME => Ok(NumberType::F64),
+            _ => Err(UnknownNumberType(s.to_string())),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct UnknownNumberType(String);
+
+impl Error for UnknownNumberType {}
+
+impl Display for UnknownNumberType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "unknown number type {}, expected one of {}",
+            self.0,
+            NumberType::VARIANTS.join(", ")
+        )
+    }
+}
+
+/// Calls generic function `$f` with `Number` implementation selected by `$number_type`
+#[macro_export]
+macro_rules! with_number_type {
+    ($number_type:expr, $f:ident($($arg:expr),* $(,)?)) => {
+        match $number_type {
+            $crate::number::NumberType::I32 => $f::<i32>($($arg),*),
+            $crate::number::NumberType::I64 => $f::<i64>($($arg),*),
+            $crate::number::NumberType::U64 => $f::<u64>($($arg),*),
+            $crate::number::NumberType::F64 => $f::<f64>($($arg),*),
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn integer_overflow_is_detected() {
+        assert_eq!(Number::checked_add(i32::MAX, 1), None);
+        assert_eq!(Number::checked_mul(u64::MAX, 2), None);
+        assert_eq!(Number::checked_add(i64::MAX - 1, 1), Some(i64::MAX));
+    }
+
+    #[test]
+    fn float_overflow_is_detected() {
+        assert_eq!(Number::checked_mul(f64::MAX, 2.0), None);
+        assert_eq!(Number::checked_add(1.5, 2.0), Some(3.5));
+    }
+
+    #[test]
+    fn integers_are_rounded_down_from_floats() {
+        assert_eq!(<i32 as Number>::from_f64(7.9), Some(7));
+        assert_eq!(<u64 as Number>::from_f64(-1.0), None);
+        assert_eq!(<i32 as Number>::from_f64(f64::NAN), None);
+        assert_eq!(<f64 as Number>::from_f64(7.9), Some(7.9));
+    }
+
+    #[test]
+    fn number_type_is_parsed_from_its_name() {
+        for name in NumberType::VARIANTS.iter() {
+            assert!(name.parse::<NumberType>().is_ok());
+        }
+        assert!("i8".parse::<NumberType>().is_err());
+    }
+}
@@ -0,0 +1,384 @@
+use crate::number::Number;
+use crate::population::config::Config;
+use crate::population::individual::new_individual;
+use crate::population::tournament::tournament;
+use crate::population::{evaluate_population, Population};
+use na::{DMatrix, DVector};
+use rand::seq::SliceRandom;
+use rand::{thread_rng, Rng};
+use rayon::prelude::*;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+/// Toroidal grid individuals of a cellular population live on
+///
+/// Individual `i` is the row `i` of the population matrix and lives in
+/// the cell `(i % width, i / width)`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Grid {
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Grid {
+    pub fn cells(self) -> usize {
+        self.width * self.height
+    }
+
+    /// Cells each cell may choose its parents from, including the cell itself
+    pub fn neighbourhoods(self, neighbourhood: Neighbourhood, radius: usize) -> Vec<Vec<usize>> {
+        let (width, height) = (self.width as isize, self.height as isize);
+        let radius = radius as isize;
+
+        (0..self.cells())
+            .map(|cell| {
+                let (x, y) = ((cell % self.width) as isize, (cell / self.width) as isize);
+                let mut cells = Vec::new();
+
+                for dy in -radius..=radius {
+                    for dx in -radius..=radius {
+                        let inside = match neighbourhood {
+                            Neighbourhood::VonNeumann => dx.abs() + dy.abs() <= radius,
+                            Neighbourhood::Moore => true,
+                        };
+                        if inside {
+                            let nx = (x + dx).rem_euclid(width);
+                            let ny = (y + dy).rem_euclid(height);
+                            cells.push((ny * width + nx) as usize);
+                        }
+                    }
+                }
+
+                // a radius wider than the grid wraps around onto the same cells
+                cells.sort_unstable();
+                cells.dedup();
+                cells
+            })
+            .collect()
+    }
+}
+
+impl FromStr for Grid {
+    type Err = InvalidGrid;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (width, height) = s
+            .split_once('x')
+            .ok_or_else(|| InvalidGrid(s.to_string()))?;
+        let parse = |x: &str| x.trim().parse::<usize>().ok().filter(|x| *x > 0);
+
+        match (parse(width), parse(height)) {
+            (Some(width), Some(height)) => Ok(Grid { width, height }),
+            _ => Err(InvalidGrid(s.to_string())),
+        }
+    }
+}
+
+impl Display for Grid {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{}", self.width, self.height)
+    }
+}
+
+#[derive(Debug)]
+pub struct InvalidGrid(String);
+
+impl Error for InvalidGrid {}
+
+impl Display for InvalidGrid {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid grid {}, expected positive width and height like 10x20",
+            self.0
+        )
+    }
+}
+
+/// Cells within the radius of a cell
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Neighbourhood {
+    /// Cells at most `radius` steps away moving horizontally and vertically
+    VonNeumann,
+    /// Cells in the square of side `2 * radius + 1` around the cell
+    Moore,
+}
+
+/// Order in which cells are replaced by their offspring
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UpdateMode {
+    /// All cells breed from the previous generation and are replaced at once
+    Synchronous,
+    /// Cells are replaced one by one in a new random order every generation,
+    /// so later cells may choose offspring of earlier ones as parents
+    Asynchronous,
+}
+
+impl_policy!(Neighbourhood, UnknownNeighbourhood, "neighbourhood", [
+    "von-neumann" => VonNeumann,
+    "moore" => Moore
+]);
+impl_policy!(UpdateMode, UnknownUpdateMode, "update mode", [
+    "sync" => Synchronous,
+    "async" => Asynchronous
+]);
+
+/// Options of a cellular population
+#[derive(Debug, Clone, Copy)]
+pub struct CellularConfig {
+    pub grid: Grid,
+    pub neighbourhood: Neighbourhood,
+    pub radius: usize,
+    pub update: UpdateMode,
+}
+
+/// Summary of the neighbourhoods of a cellular population
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NeighbourhoodStats {
+    /// Mean of the best scores of all neighbourhoods
+    pub local_best: f64,
+    /// Mean of the mean scores of all neighbourhoods
+    pub local_mean: f64,
+    /// Mean share of genes in which a cell differs from its neighbours
+    pub distance: f64,
+    /// Share of cells holding the best score of the population
+    pub takeover: f64,
+}
+
+impl NeighbourhoodStats {
+    /// Statistics of the scores increased by `offset`
+    pub fn with_offset(self, offset: f64) -> NeighbourhoodStats {
+        NeighbourhoodStats {
+            local_best: self.local_best + offset,
+            local_mean: self.local_mean + offset,
+            ..self
+        }
+    }
+
+    /// Header of the CSV columns written by `to_csv`
+    pub const CSV_HEADER: &'static str = "local_best,local_mean,distance,takeover";
+
+    pub fn to_csv(&self) -> String {
+        format!(
+            "{},{},{},{}",
+            self.local_best, self.local_mean, self.distance, self.takeover
+        )
+    }
+}
+
+/// Picks a parent for a cell by a tournament among its neighbourhood
+///
+/// Tournaments without replacement are limited to the size of the neighbourhood.
+fn select_neighbour<N: Number, R: Rng + ?Sized>(
+    neighbourhood: &[usize],
+    scores: &DVector<N>,
+    config: &Config,
+    rng: &mut R,
+) -> usize {
+    let local = DVector::from_iterator(
+        neighbourhood.len(),
+        neighbourhood.iter().map(|i| scores[*i]),
+    );
+    let tournament_size = if config.tournament_with_replacement {
+        config.tournament_size
+    } else {
+        config.tournament_size.min(neighbourhood.len())
+    };
+
+    neighbourhood[tournament(
+        &local,
+        tournament_size,
+        config.tournament_with_replacement,
+        rng,
+    )]
+}
+
+impl<N: Number> Population<N> {
+    /// Breeds a child for every cell from parents of its neighbourhood
+    ///
+    /// The child replaces the individual of the cell unless it scores worse.
+    pub(super) fn evolve_cellular(&mut self, cellular: CellularConfig) {
+        match cellular.update {
+            UpdateMode::Synchronous => self.evolve_synchronous(),
+            UpdateMode::Asynchronous => self.evolve_asynchronous(),
+        }
+    }
+
+    fn evolve_synchronous(&mut self) {
+        let population = &self.population;
+        let scores = &self.scores;
+        let neighbourhoods = &self.neighbourhoods;
+        let config = &self.config;
+
+        let parents = self
+            .next_population
+            .row_iter_mut()
+            .collect::<Vec<_>>()
+            .par_iter_mut()
+            .enumerate()
+            .map_init(thread_rng, |rng, (i, child)| {
+                let parent1 = select_neighbour(&neighbourhoods[i], scores, config, rng);
+                let parent2 = select_neighbour(&neighbourhoods[i], scores, config, rng);
+
+                new_individual(
+                    child,
+                    &population.row(parent1),
+                    &population.row(parent2),
+                    rng,
+                    config,
+                );
+                [parent1, parent2]
+            })
+            .collect::<Vec<_>>();
+
+        let offspring_scores = evaluate_population(&self.next_population, &self.scenario);
+        self.record_successes(&parents, &offspring_scores);
+        for (i, score) in offspring_scores.iter().enumerate() {
+            if *score >= self.scores[i] {
+                self.population
+                    .row_mut(i)
+                    .copy_from(&self.next_population.row(i));
+                self.scores[i] = *score;
+            }
+        }
+    }
+
+    /// Each offspring is evaluated on its own so it can be chosen as a parent
+    /// right away, which makes this mode considerably slower than the synchronous one.
+    fn evolve_asynchronous(&mut self) {
+        let mut rng = thread_rng();
+        let mut child = DMatrix::<N>::zeros(1, self.scenario.number_of_objects);
+
+        let mut order = (0..self.config.population_size).collect::<Vec<_>>();
+        order.shuffle(&mut rng);
+
+        for i in order {
+            let neighbourhood = &self.neighbourhoods[i];
+            let parent1 = select_neighbour(neighbourhood, &self.scores, &self.config, &mut rng);
+            let parent2 = select_neighbour(neighbourhood, &self.scores, &self.config, &mut rng);
+
+            new_individual(
+                &mut child.row_mut(0),
+                &self.population.row(parent1),
+                &self.population.row(parent2),
+                &mut rng,
+                &self.config,
+            );
+
+            let score = evaluate_population(&child, &self.scenario)[0];
+            self.record_success([parent1, parent2], score);
+            if score >= self.scores[i] {
+                self.population.row_mut(i).copy_from(&child.row(0));
+                self.scores[i] = score;
+            }
+        }
+    }
+
+    /// Statistics of the neighbourhoods, `None` unless the population is cellular
+    pub fn neighbourhood_stats(&self) -> Option<NeighbourhoodStats> {
+        self.config.cellular?;
+
+        let cells = self.neighbourhoods.len() as f64;
+        let number_of_objects = self.scenario.number_of_objects.max(1) as f64;
+        let (_, best) = super::best_individual(&self.scores);
+
+        let mut stats = NeighbourhoodStats {
+            local_best: 0.0,
+            local_mean: 0.0,
+            distance: 0.0,
+            takeover: 0.0,
+        };
+
+        for (i, neighbourhood) in self.neighbourhoods.iter().enumerate() {
+            let scores = neighbourhood.iter().map(|j| self.scores[*j].to_f64());
+            stats.local_best += scores.clone().fold(f64::MIN, f64::max);
+            stats.local_mean += scores.sum::<f64>() / neighbourhood.len() as f64;
+
+            let row = self.population.row(i);
+            let neighbours = neighbourhood.iter().filter(|j| **j != i);
+            let count = neighbours.clone().count().max(1) as f64;
+            let differences = neighbours
+                .map(|j| {
+                    let other = self.population.row(*j);
+                    row.iter().zip(other.iter()).filter(|(a, b)| a != b).count()
+                })
+                .sum::<usize>();
+            stats.distance += differences as f64 / count / number_of_objects;
+
+            if self.scores[i] == best {
+                stats.takeover += 1.0;
+            }
+        }
+
+        stats.local_best /= cells;
+        stats.local_mean /= cells;
+        stats.distance /= cells;
+        stats.takeover /= cells;
+
+        Some(stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::population::{test_config, test_scenario};
+    use std::sync::Arc;
+
+    #[test]
+    fn neighbourhoods_wrap_around_the_grid() {
+        let grid = "4x3".parse::<Grid>().unwrap();
+
+        // cell 0 is the top left corner, its neighbours wrap to the right
+        // column and to the bottom row
+        let von_neumann = grid.neighbourhoods(Neighbourhood::VonNeumann, 1);
+        assert_eq!(von_neumann[0], vec![0, 1, 3, 4, 8]);
+
+        let moore = grid.neighbourhoods(Neighbourhood::Moore, 1);
+        assert_eq!(moore[0], vec![0, 1, 3, 4, 5, 7, 8, 9, 11]);
+
+        // a radius wider than the grid covers every cell once
+        let whole = grid.neighbourhoods(Neighbourhood::Moore, 5);
+        assert_eq!(whole[6], (0..12).collect::<Vec<_>>());
+
+        assert!("4x0".parse::<Grid>().is_err());
+        assert!("4".parse::<Grid>().is_err());
+    }
+
+    #[test]
+    fn cellular_populations_never_get_worse() {
+        let scenario = Arc::new(test_scenario(12, 20, 5));
+
+        for update in [UpdateMode::Synchronous, UpdateMode::Asynchronous].iter() {
+            let config = Config {
+                cellular: Some(CellularConfig {
+                    grid: Grid {
+                        width: 4,
+                        height: 4,
+                    },
+                    neighbourhood: Neighbourhood::Moore,
+                    radius: 1,
+                    update: *update,
+                }),
+                ..test_config(16)
+            };
+            let mut population = Population::new(Arc::clone(&scenario), config).unwrap();
+
+            for _ in 0..20 {
+                let previous = population.scores().clone();
+                population.evolve();
+                // every cell keeps its individual unless the offspring is at least as good
+                assert!(population
+                    .scores()
+                    .iter()
+                    .zip(previous.iter())
+                    .all(|(a, b)| a >= b));
+            }
+
+            let stats = population.neighbourhood_stats().unwrap();
+            assert!(stats.takeover > 0.0 && stats.takeover <= 1.0);
+            assert!((0.0..=1.0).contains(&stats.distance));
+        }
+    }
+}
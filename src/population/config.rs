@@ -1,7 +1,346 @@
-#[derive(Debug)]
+use crate::population::cellular::CellularConfig;
+use crate::population::control::{ControlConfig, ControlMethod};
+use crate::population::diversity::DiversityConfig;
+use crate::population::niching::{NichingConfig, NichingMethod};
+use crate::population::operators::OperatorConfig;
+use crate::population::steady_state::SteadyStateConfig;
+use crate::population::survivors::SurvivorConfig;
+
+#[derive(Debug, Clone)]
 pub struct Config {
     pub mutation_probability: f64,
     pub crossover_probability: f64,
     pub population_size: usize,
     pub tournament_size: usize,
+    pub tournament_with_replacement: bool,
+    /// Places individuals on a grid and breeds them only with their neighbours
+    pub cellular: Option<CellularConfig>,
+    /// Replaces a few individuals at a time instead of the whole population
+    pub steady_state: Option<SteadyStateConfig>,
+    /// Breeds a separate number of children and selects survivors among them
+    pub survivors: Option<SurvivorConfig>,
+    /// Replaces duplicates and injects random individuals after every generation
+    pub diversity: Option<DiversityConfig>,
+    /// Keeps several niches of good individuals instead of converging to one
+    pub niching: Option<NichingConfig>,
+    /// Changes mutation and crossover probabilities during the run
+    pub control: Option<ControlConfig>,
+    /// Chooses among several operators by the improvements of their children
+    pub operators: Option<OperatorConfig>,
+}
+
+impl Config {
+    /// Checks the options and that they can be combined
+    pub fn validate(&self) -> Result<(), String> {
+        if self.tournament_size == 0 {
+            return Err("Tournament size has to be at least 1".to_string());
+        }
+        if !self.tournament_with_replacement && self.tournament_size > self.population_size {
+            return Err(format!(
+                "Tournament size {} without replacement is larger then population size {}",
+                self.tournament_size, self.population_size
+            ));
+        }
+        if let Some(cellular) = self.cellular {
+            if cellular.grid.cells() != self.population_size {
+                return Err(format!(
+                    "Grid {} has {} cells but population size is {}",
+                    cellular.grid,
+                    cellular.grid.cells(),
+                    self.population_size
+                ));
+            }
+        }
+        if let Some(steady_state) = self.steady_state {
+            if steady_state.children == 0 {
+                return Err("Steady-state steps have to breed at least one child".to_string());
+            }
+        }
+        if let Some(survivors) = self.survivors {
+            survivors.validate(self.population_size)?;
+        }
+        if let Some(control) = self.control {
+            control.validate(self.mutation_probability, self.crossover_probability)?;
+        }
+        if let Some(operators) = &self.operators {
+            operators.validate()?;
+        }
+
+        // cellular and steady-state populations breed one neighbourhood or
+        // a few children at a time, the other modes need whole generations
+        let generational = self.cellular.is_none() && self.steady_state.is_none();
+        let self_adaptive = self
+            .control
+            .is_some_and(|control| control.method == ControlMethod::SelfAdaptive);
+
+        if self.cellular.is_some() && self.steady_state.is_some() {
+            return Err("Cellular populations cannot evolve in the steady-state mode".to_string());
+        }
+        if self.survivors.is_some() && !generational {
+            return Err("Survivor selection works only with whole generations".to_string());
+        }
+        if let Some(niching) = self.niching {
+            if !generational {
+                return Err("Niching works only with whole generations".to_string());
+            }
+            if niching.method != NichingMethod::Sharing && self.survivors.is_some() {
+                return Err(
+                    "Crowding and restricted tournaments select survivors on their own".to_string(),
+                );
+            }
+        }
+        if self_adaptive && (!generational || self.survivors.is_some() || self.niching.is_some()) {
+            return Err(
+                "Self-adaptive mutation works only with plain generational breeding".to_string(),
+            );
+        }
+        if self.operators.is_some() {
+            if !generational || self.niching.is_some() {
+                return Err(
+                    "Adaptive operator selection works only with whole generations without niching"
+                        .to_string(),
+                );
+            }
+            if self_adaptive {
+                return Err(
+                    "Adaptive operator selection cannot be combined with self-adaptive mutation"
+                        .to_string(),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Number of children bred every generation
+    pub fn offspring_size(&self) -> usize {
+        self.survivors
+            .map_or(self.population_size, |survivors| survivors.offspring)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::population::cellular::{Grid, Neighbourhood, UpdateMode};
+    use crate::population::operators::{Operator, SelectionStrategy};
+    use crate::population::steady_state::Replacement;
+    use crate::population::survivors::SurvivorSelection;
+    use crate::population::test_config;
+
+    #[test]
+    fn tournament_size_is_in_range() {
+        assert!(Config {
+            tournament_size: 0,
+            ..test_config(16)
+        }
+        .validate()
+        .is_err());
+        assert!(Config {
+            tournament_size: 0,
+            tournament_with_replacement: true,
+            ..test_config(16)
+        }
+        .validate()
+        .is_err());
+        assert!(Config {
+            tournament_size: 17,
+            ..test_config(16)
+        }
+        .validate()
+        .is_err());
+        assert!(Config {
+            tournament_size: 17,
+            tournament_with_replacement: true,
+            ..test_config(16)
+        }
+        .validate()
+        .is_ok());
+    }
+
+    #[test]
+    fn grid_has_a_cell_for_each_individual() {
+        let cellular = |width, height| CellularConfig {
+            grid: Grid { width, height },
+            neighbourhood: Neighbourhood::VonNeumann,
+            radius: 1,
+            update: UpdateMode::Synchronous,
+        };
+
+        assert!(Config {
+            cellular: Some(cellular(4, 4)),
+            ..test_config(16)
+        }
+        .validate()
+        .is_ok());
+        assert!(Config {
+            cellular: Some(cellular(4, 5)),
+            ..test_config(16)
+        }
+        .validate()
+        .is_err());
+    }
+
+    #[test]
+    fn conflicting_modes_are_rejected() {
+        let cellular = CellularConfig {
+            grid: Grid {
+                width: 4,
+                height: 4,
+            },
+            neighbourhood: Neighbourhood::VonNeumann,
+            radius: 1,
+            update: UpdateMode::Synchronous,
+        };
+        let steady_state = SteadyStateConfig {
+            children: 2,
+            replacement: Replacement::Worst,
+        };
+        let survivors = SurvivorConfig {
+            offspring: 32,
+            selection: SurvivorSelection::Plus,
+            elitism: 0,
+        };
+        let crowding = NichingConfig {
+            method: NichingMethod::Crowding,
+            radius: 2,
+            alpha: 1.0,
+            window: 4,
+        };
+
+        assert!(Config {
+            steady_state: Some(SteadyStateConfig {
+                children: 0,
+                ..steady_state
+            }),
+            ..test_config(16)
+        }
+        .validate()
+        .is_err());
+        assert!(Config {
+            cellular: Some(cellular),
+            steady_state: Some(steady_state),
+            ..test_config(16)
+        }
+        .validate()
+        .is_err());
+        assert!(Config {
+            survivors: Some(survivors),
+            ..test_config(16)
+        }
+        .validate()
+        .is_ok());
+        assert!(Config {
+            steady_state: Some(steady_state),
+            survivors: Some(survivors),
+            ..test_config(16)
+        }
+        .validate()
+        .is_err());
+        assert!(Config {
+            steady_state: Some(steady_state),
+            niching: Some(crowding),
+            ..test_config(16)
+        }
+        .validate()
+        .is_err());
+        assert!(Config {
+            survivors: Some(survivors),
+            niching: Some(crowding),
+            ..test_config(16)
+        }
+        .validate()
+        .is_err());
+        assert!(Config {
+            survivors: Some(survivors),
+            niching: Some(NichingConfig {
+                method: NichingMethod::Sharing,
+                ..crowding
+            }),
+            ..test_config(16)
+        }
+        .validate()
+        .is_ok());
+    }
+
+    #[test]
+    fn self_adaptive_mutation_needs_plain_generations() {
+        let control = ControlConfig {
+            method: ControlMethod::SelfAdaptive,
+            final_mutation_probability: None,
+            final_crossover_probability: None,
+            generations: 10,
+            steps: 1,
+            factor: 1.5,
+            target_diversity: 0.5,
+            learning_rate: 0.2,
+        };
+        let survivors = SurvivorConfig {
+            offspring: 32,
+            selection: SurvivorSelection::Plus,
+            elitism: 0,
+        };
+
+        assert!(Config {
+            control: Some(control),
+            ..test_config(16)
+        }
+        .validate()
+        .is_ok());
+        assert!(Config {
+            control: Some(control),
+            survivors: Some(survivors),
+            ..test_config(16)
+        }
+        .validate()
+        .is_err());
+        assert!(Config {
+            control: Some(ControlConfig {
+                method: ControlMethod::Linear,
+                final_mutation_probability: Some(1.5),
+                ..control
+            }),
+            ..test_config(16)
+        }
+        .validate()
+        .is_err());
+    }
+
+    #[test]
+    fn operator_selection_needs_whole_generations() {
+        let operators = OperatorConfig {
+            operators: vec![Operator::OnePoint, Operator::Swap],
+            strategy: SelectionStrategy::AdaptivePursuit,
+            min_probability: 0.1,
+            adaptation_rate: 0.5,
+            pursuit_rate: 0.5,
+            exploration: 0.1,
+        };
+        let steady_state = SteadyStateConfig {
+            children: 2,
+            replacement: Replacement::Worst,
+        };
+
+        assert!(Config {
+            operators: Some(operators.clone()),
+            ..test_config(16)
+        }
+        .validate()
+        .is_ok());
+        assert!(Config {
+            operators: Some(OperatorConfig {
+                operators: Vec::new(),
+                ..operators.clone()
+            }),
+            ..test_config(16)
+        }
+        .validate()
+        .is_err());
+        assert!(Config {
+            operators: Some(operators),
+            steady_state: Some(steady_state),
+            ..test_config(16)
+        }
+        .validate()
+        .is_err());
+    }
 }
@@ -0,0 +1,501 @@
+use crate::number::Number;
+use crate::population::individual::new_individual_with_rate;
+use crate::population::tournament::tournament;
+use crate::population::Population;
+use na::DVector;
+use rand::{thread_rng, Rng};
+use rand_distr::StandardNormal;
+use rayon::prelude::*;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::mem::swap;
+use std::str::FromStr;
+
+/// Ways of changing mutation and crossover probabilities during the run
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ControlMethod {
+    /// Probabilities move to their final values by the same amount every generation
+    Linear,
+    /// Probabilities move to their final values by the same factor every generation
+    Exponential,
+    /// Probabilities move linearly to their final values in a few equal jumps
+    Step,
+    /// Mutation probability grows while more than a fifth of children beat
+    /// both of their parents and shrinks while fewer do
+    OneFifth,
+    /// Mutation probability grows while diversity is below the target and shrinks otherwise
+    Diversity,
+    /// Each individual carries its own mutation probability, which its
+    /// children inherit from their first parent and perturb
+    SelfAdaptive,
+}
+
+impl_policy!(ControlMethod, UnknownControlMethod, "rate control", [
+    "linear" => Linear,
+    "exponential" => Exponential,
+    "step" => Step,
+    "one-fifth" => OneFifth,
+    "diversity" => Diversity,
+    "self-adaptive" => SelfAdaptive
+]);
+
+impl ControlMethod {
+    /// Whether probabilities follow a fixed schedule over generations
+    pub fn is_schedule(self) -> bool {
+        matches!(
+            self,
+            ControlMethod::Linear | ControlMethod::Exponential | ControlMethod::Step
+        )
+    }
+}
+
+/// Options of parameter control
+#[derive(Debug, Clone, Copy)]
+pub struct ControlConfig {
+    pub method: ControlMethod,
+    /// Probabilities schedules end with, `None` keeps the initial probability
+    pub final_mutation_probability: Option<f64>,
+    pub final_crossover_probability: Option<f64>,
+    /// Number of generations schedules span, the last of them is bred with
+    /// the final probabilities
+    pub generations: usize,
+    /// Number of jumps of the step schedule
+    pub steps: usize,
+    /// Factor the 1/5th success rule and diversity-driven control multiply
+    /// or divide mutation probability by
+    pub factor: f64,
+    /// Share of the diversity of the initial population below which
+    /// diversity-driven control raises mutation probability
+    pub target_diversity: f64,
+    /// Learning rate τ of self-adaptive mutation probabilities
+    pub learning_rate: f64,
+}
+
+impl ControlConfig {
+    /// Checks that probabilities can be controlled starting from the given ones
+    pub fn validate(
+        &self,
+        mutation_probability: f64,
+        crossover_probability: f64,
+    ) -> Result<(), String> {
+        let finals = [
+            self.final_mutation_probability
+                .unwrap_or(mutation_probability),
+            self.final_crossover_probability
+                .unwrap_or(crossover_probability),
+        ];
+        if finals.iter().any(|p| !(0.0..=1.0).contains(p)) {
+            return Err("Final probabilities have to be in range [0, 1]".to_string());
+        }
+        if self.method == ControlMethod::Exponential
+            && [mutation_probability, crossover_probability]
+                .iter()
+                .chain(finals.iter())
+                .any(|p| *p <= 0.0)
+        {
+            return Err(
+                "Exponential schedule needs positive initial and final probabilities".to_string(),
+            );
+        }
+        if self.method.is_schedule() && self.generations == 0 {
+            return Err("Schedules have to last at least one generation".to_string());
+        }
+        if self.method == ControlMethod::Step && self.steps == 0 {
+            return Err("Step schedule needs at least one step".to_string());
+        }
+        if self.factor < 1.0 {
+            return Err(format!("Control factor {} is smaller then 1", self.factor));
+        }
+        if !(0.0..=1.0).contains(&self.target_diversity) {
+            return Err("Target diversity has to be in range [0, 1]".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Mutation and crossover probabilities a generation was bred with
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Rates {
+    /// Mean probability of the individuals when it is self-adaptive
+    pub mutation: f64,
+    pub crossover: f64,
+}
+
+impl Rates {
+    /// Header of the CSV columns written by `to_csv`
+    pub const CSV_HEADER: &'static str = "mutation_probability,crossover_probability";
+
+    pub fn to_csv(&self) -> String {
+        format!("{},{}", self.mutation, self.crossover)
+    }
+}
+
+/// Bounds of adapted mutation probabilities, mutating more than half of the
+/// genes only makes children look less like their parents than random ones
+const MIN_MUTATION_PROBABILITY: f64 = 1e-6;
+const MAX_MUTATION_PROBABILITY: f64 = 0.5;
+
+/// Share of children beating their parents the 1/5th success rule aims at
+const TARGET_SUCCESS_RATE: f64 = 0.2;
+
+/// State of the parameter control of a population
+#[derive(Debug)]
+pub(super) struct Control {
+    config: ControlConfig,
+    /// Probabilities given by the population config
+    initial: Rates,
+    /// Number of generations bred so far
+    generation: usize,
+    /// Children bred since the last adaptation which beat both of their parents
+    successes: usize,
+    children: usize,
+    /// Mean distance of the initial individuals, see `Population::gene_diversity`
+    initial_diversity: f64,
+    /// Mutation probabilities of the offspring when they are self-adaptive,
+    /// see `Population::mutation_rates`
+    next_rates: DVector<f64>,
+}
+
+impl Control {
+    pub(super) fn new<N: Number>(config: ControlConfig, population: &Population<N>) -> Control {
+        let initial = Rates {
+            mutation: population.config.mutation_probability,
+            crossover: population.config.crossover_probability,
+        };
+        Control {
+            config,
+            initial,
+            generation: 0,
+            successes: 0,
+            children: 0,
+            initial_diversity: population.gene_diversity(),
+            next_rates: DVector::zeros(0),
+        }
+    }
+
+    /// Probabilities of generation `generation` of the schedule
+    fn scheduled(&self, generation: usize) -> Rates {
+        let config = &self.config;
+        let last = config.generations.saturating_sub(1).max(1);
+        let mut progress = (generation as f64 / last as f64).min(1.0);
+        if config.method == ControlMethod::Step {
+            progress = (progress * config.steps as f64).floor() / config.steps as f64;
+        }
+
+        let schedule = |start: f64, end: Option<f64>| {
+            let end = end.unwrap_or(start);
+            match config.method {
+                ControlMethod::Exponential => start * (end / start).powf(progress),
+                _ => start + (end - start) * progress,
+            }
+        };
+        Rates {
+            mutation: schedule(self.initial.mutation, config.final_mutation_probability),
+            crossover: schedule(self.initial.crossover, config.final_crossover_probability),
+        }
+    }
+}
+
+impl<N: Number> Population<N> {
+    /// Sets probabilities the next generation is bred with
+    pub(super) fn control_rates(&mut self) {
+        let method = match &self.control {
+            Some(control) => control.config.method,
+            None => return,
+        };
+        let diversity = if method == ControlMethod::Diversity {
+            self.gene_diversity()
+        } else {
+            0.0
+        };
+        let control = self.control.as_mut().unwrap();
+        let factor = control.config.factor;
+
+        let grow = match method {
+            ControlMethod::OneFifth if control.children > 0 => {
+                let success_rate = control.successes as f64 / control.children as f64;
+                if success_rate > TARGET_SUCCESS_RATE {
+                    Some(true)
+                } else if success_rate < TARGET_SUCCESS_RATE {
+                    Some(false)
+                } else {
+                    None
+                }
+            }
+            ControlMethod::Diversity => {
+                Some(diversity < control.config.target_diversity * control.initial_diversity)
+            }
+            _ => None,
+        };
+        match grow {
+            Some(true) => self.config.mutation_probability *= factor,
+            Some(false) => self.config.mutation_probability /= factor,
+            None => (),
+        }
+        if grow.is_some() {
+            self.config.mutation_probability = self
+                .config
+                .mutation_probability
+                .clamp(MIN_MUTATION_PROBABILITY, MAX_MUTATION_PROBABILITY);
+        }
+
+        if method.is_schedule() {
+            let rates = control.scheduled(control.generation);
+            self.config.mutation_probability = rates.mutation;
+            self.config.crossover_probability = rates.crossover;
+        }
+
+        control.generation += 1;
+        control.successes = 0;
+        control.children = 0;
+    }
+
+    /// Probabilities the current generation was bred with
+    pub fn rates(&self) -> Rates {
+        let mutation = match &self.mutation_rates {
+            Some(rates) => rates.mean(),
+            None => self.config.mutation_probability,
+        };
+        Rates {
+            mutation,
+            crossover: self.config.crossover_probability,
+        }
+    }
+
+    /// Mean number of genes in which two individuals differ divided by the
+    /// number of genes, 0 when all individuals are the same
+    pub fn gene_diversity(&self) -> f64 {
+        let size = self.population.nrows() as f64;
+        let number_of_objects = self.population.ncols();
+        if size < 2.0 || number_of_objects == 0 {
+            return 0.0;
+        }
+
+        // a gene chosen by `c` individuals differs in `c * (size - c)` ordered pairs
+        let differences = self
+            .population
+            .column_iter()
+            .map(|genes| {
+                let chosen = genes.iter().filter(|gene| **gene == N::one()).count() as f64;
+                2.0 * chosen * (size - chosen)
+            })
+            .sum::<f64>();
+        differences / (size * (size - 1.0) * number_of_objects as f64)
+    }
+
+    /// Counts a child which scored `score` towards the 1/5th success rule
+    pub(super) fn record_success(&mut self, parents: [usize; 2], score: N) {
+        if let Some(control) = &mut self.control {
+            control.children += 1;
+            if score > self.scores[parents[0]] && score > self.scores[parents[1]] {
+                control.successes += 1;
+            }
+        }
+    }
+
+    /// Counts children of `parents` which scored `scores`, before they replace anyone
+    pub(super) fn record_successes(&mut self, parents: &[[usize; 2]], scores: &DVector<N>) {
+        if self.control.is_some() {
+            for (parents, score) in parents.iter().zip(scores.iter()) {
+                self.record_success(*parents, *score);
+            }
+        }
+    }
+
+    pub(super) fn is_self_adaptive(&self) -> bool {
+        self.mutation_rates.is_some()
+    }
+
+    /// Mutation probability individual `i` carries when it is self-adaptive
+    pub(super) fn mutation_rate(&self, i: usize) -> Option<f64> {
+        self.mutation_rates.as_ref().map(|rates| rates[i])
+    }
+
+    /// Gives individual `i` the probability `rate` it brought along when it is self-adaptive
+    ///
+    /// Individuals which did not carry any start with the initial probability.
+    pub(super) fn set_mutation_rate(&mut self, i: usize, rate: Option<f64>) {
+        let initial = self.config.mutation_probability;
+        if let Some(rates) = &mut self.mutation_rates {
+            // rates of other processes are not trusted, like their scores
+            let rate = rate.filter(|rate| rate.is_finite()).unwrap_or(initial);
+            rates[i] = rate.clamp(MIN_MUTATION_PROBABILITY, MAX_MUTATION_PROBABILITY);
+        }
+    }
+
+    /// Breeds offspring mutated with the probability `p` of their first parent
+    /// perturbed into `1 / (1 + (1 - p) / p * exp(-τ N(0, 1)))`
+    ///
+    /// Returns parents of each child.
+    pub(super) fn breed_self_adaptive(&mut self) -> Vec<[usize; 2]> {
+        let control = self.control.as_mut().unwrap();
+        let rates = self.mutation_rates.as_ref().unwrap();
+        let learning_rate = control.config.learning_rate;
+        let (population, scores, config) = (&self.population, &self.scores, &self.config);
+
+        let (parents, next_rates): (Vec<_>, Vec<_>) = self
+            .next_population
+            .row_iter_mut()
+            .collect::<Vec<_>>()
+            .par_iter_mut()
+            .map_init(thread_rng, |rng, child| {
+                let tournament_size = config.tournament_size;
+                let with_replacement = config.tournament_with_replacement;
+
+                let parent1 = tournament(scores, tournament_size, with_replacement, rng);
+                let parent2 = tournament(scores, tournament_size, with_replacement, rng);
+
+                let rate = rates[parent1];
+                let noise: f64 = rng.sample(StandardNormal);
+                let rate = 1.0 / (1.0 + (1.0 - rate) / rate * (-learning_rate * noise).exp());
+                let rate = rate.clamp(MIN_MUTATION_PROBABILITY, MAX_MUTATION_PROBABILITY);
+
+                new_individual_with_rate(
+                    child,
+                    &population.row(parent1),
+                    &population.row(parent2),
+                    rng,
+                    config,
+                    rate,
+                );
+                ([parent1, parent2], rate)
+            })
+            .unzip();
+
+        control.next_rates = DVector::from_vec(next_rates);
+        parents
+    }
+
+    /// Gives the offspring which replaced the population its mutation probabilities
+    pub(super) fn inherit_rates(&mut self) {
+        if let (Some(control), Some(rates)) = (&mut self.control, &mut self.mutation_rates) {
+            swap(rates, &mut control.next_rates);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::population::config::Config;
+    use crate::population::island::{EmigrantPolicy, Migrant, ReplacementPolicy};
+    use crate::population::{test_config, test_scenario};
+    use std::sync::Arc;
+
+    fn control(method: ControlMethod) -> ControlConfig {
+        ControlConfig {
+            method,
+            final_mutation_probability: Some(0.01),
+            final_crossover_probability: None,
+            generations: 11,
+            steps: 2,
+            factor: 1.5,
+            target_diversity: 0.5,
+            learning_rate: 0.2,
+        }
+    }
+
+    fn population(control: ControlConfig) -> Population<i64> {
+        let config = Config {
+            control: Some(control),
+            ..test_config(10)
+        };
+        Population::new(Arc::new(test_scenario(20, 40, 6)), config).unwrap()
+    }
+
+    #[test]
+    fn schedules_reach_final_probabilities() {
+        let mutation = |method, generation| {
+            let population = population(control(method));
+            population
+                .control
+                .as_ref()
+                .unwrap()
+                .scheduled(generation)
+                .mutation
+        };
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-12;
+
+        assert!(close(mutation(ControlMethod::Linear, 5), 0.055));
+        assert!(close(
+            mutation(ControlMethod::Exponential, 5),
+            0.1 * 0.1f64.sqrt()
+        ));
+        assert!(close(mutation(ControlMethod::Step, 4), 0.1));
+        assert!(close(mutation(ControlMethod::Step, 5), 0.055));
+        for method in [
+            ControlMethod::Linear,
+            ControlMethod::Exponential,
+            ControlMethod::Step,
+        ]
+        .iter()
+        {
+            assert!(close(mutation(*method, 20), 0.01), "{}", method);
+        }
+
+        let mut population = population(control(ControlMethod::Linear));
+        for _ in 0..12 {
+            population.evolve();
+        }
+        assert!(close(population.rates().mutation, 0.01));
+        assert_eq!(population.rates().crossover, 0.8);
+    }
+
+    #[test]
+    fn one_fifth_rule_follows_the_success_rate() {
+        let mut population = population(control(ControlMethod::OneFifth));
+
+        population.control.as_mut().unwrap().children = 10;
+        population.control.as_mut().unwrap().successes = 3;
+        population.control_rates();
+        assert!((population.rates().mutation - 0.15).abs() < 1e-12);
+
+        population.control.as_mut().unwrap().children = 10;
+        population.control.as_mut().unwrap().successes = 1;
+        population.control_rates();
+        assert!((population.rates().mutation - 0.1).abs() < 1e-12);
+    }
+
+    #[test]
+    fn self_adaptive_rates_follow_the_offspring() {
+        let mut population = population(control(ControlMethod::SelfAdaptive));
+
+        for _ in 0..10 {
+            population.evolve();
+            let rates = population.mutation_rates.as_ref().unwrap();
+            assert_eq!(rates.len(), 10);
+            assert!(rates
+                .iter()
+                .all(|rate| (MIN_MUTATION_PROBABILITY..=MAX_MUTATION_PROBABILITY).contains(rate)));
+        }
+        // perturbed probabilities are almost surely all different from the initial one
+        assert!(population.rates().mutation != 0.1);
+    }
+
+    #[test]
+    fn self_adaptive_rates_move_with_their_individuals() {
+        let mut source = population(control(ControlMethod::SelfAdaptive));
+        let mut target = population(control(ControlMethod::SelfAdaptive));
+        source.mutation_rates = Some(DVector::from_fn(10, |i, _| 0.2 + 0.01 * i as f64));
+        target.mutation_rates = Some(DVector::from_element(10, 0.3));
+        let mut rng = thread_rng();
+
+        let emigrants = source.emigrants(4, EmigrantPolicy::Random, &mut rng);
+        target.immigrate(emigrants.clone(), ReplacementPolicy::Worst, &mut rng);
+        let rates = target.mutation_rates.as_ref().unwrap();
+        for emigrant in emigrants.iter() {
+            assert!((0..10).any(|i| target.population.row(i) == emigrant.genes
+                && Some(rates[i]) == emigrant.mutation_rate));
+        }
+        assert_eq!(rates.iter().filter(|rate| **rate == 0.3).count(), 6);
+
+        // individuals of populations which do not adapt the rate start with the initial one
+        let stranger = Migrant {
+            mutation_rate: None,
+            ..emigrants[0].clone()
+        };
+        target.immigrate(vec![stranger], ReplacementPolicy::Worst, &mut rng);
+        let rates = target.mutation_rates.as_ref().unwrap();
+        assert_eq!(rates.iter().filter(|rate| **rate == 0.1).count(), 1);
+    }
+}
@@ -0,0 +1,254 @@
+use crate::number::Number;
+use crate::population::individual::mutate_with_rate;
+use crate::population::{evaluate_population, generate_random_population, Population};
+use na::DMatrix;
+use rand::prelude::ThreadRng;
+use rand::Rng;
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+/// Individuals duplicates are replaced with
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DuplicatePolicy {
+    /// The duplicate with at least one gene flipped and mutated as usual
+    Mutate,
+    /// A new random individual
+    Random,
+}
+
+impl_policy!(DuplicatePolicy, UnknownDuplicatePolicy, "duplicate policy", [
+    "mutate" => Mutate,
+    "random" => Random
+]);
+
+/// Options of keeping the population diverse
+#[derive(Debug, Clone, Copy)]
+pub struct DiversityConfig {
+    /// Replaces all but the first copy of each genotype, keeps duplicates if `None`
+    pub duplicates: Option<DuplicatePolicy>,
+    /// Share of the population replaced by random individuals every generation,
+    /// the worst individuals are replaced
+    pub immigrant_rate: f64,
+}
+
+/// Numbers of replaced individuals
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DiversityCounts {
+    pub duplicates: usize,
+    pub immigrants: usize,
+}
+
+impl DiversityCounts {
+    /// Header of the CSV columns written by `to_csv`
+    pub const CSV_HEADER: &'static str = "duplicates,immigrants";
+
+    pub fn to_csv(&self) -> String {
+        format!("{},{}", self.duplicates, self.immigrants)
+    }
+}
+
+impl std::ops::Add for DiversityCounts {
+    type Output = DiversityCounts;
+
+    fn add(self, other: DiversityCounts) -> DiversityCounts {
+        DiversityCounts {
+            duplicates: self.duplicates + other.duplicates,
+            immigrants: self.immigrants + other.immigrants,
+        }
+    }
+}
+
+/// Individuals replaced in the last generation and since the start
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DiversityReport {
+    pub last: DiversityCounts,
+    pub total: DiversityCounts,
+}
+
+/// Attempts to make a unique individual out of a duplicate by mutation,
+/// a random individual replaces it when all of them fail
+const MUTATION_ATTEMPTS: usize = 10;
+
+/// Genes packed 8 per byte, equal genotypes give equal keys
+pub(super) fn genotype<'a, N: Number + 'a, I: Iterator<Item = &'a N>>(genes: I) -> Vec<u8> {
+    let mut key = Vec::new();
+    for (i, gene) in genes.enumerate() {
+        if i % 8 == 0 {
+            key.push(0);
+        }
+        if *gene == N::one() {
+            *key.last_mut().unwrap() |= 1 << (i % 8);
+        }
+    }
+    key
+}
+
+impl<N: Number> Population<N> {
+    /// Replaces duplicates and the worst individuals by new ones and scores them
+    pub(super) fn maintain_diversity(&mut self, config: DiversityConfig, rng: &mut ThreadRng) {
+        let size = self.config.population_size;
+        let mut replaced = Vec::new();
+
+        if let Some(policy) = config.duplicates {
+            let mut seen = HashSet::with_capacity(size);
+            for i in 0..size {
+                if !seen.insert(genotype(self.population.row(i).iter())) {
+                    self.replace_duplicate(i, policy, &mut seen, rng);
+                    replaced.push(i);
+                }
+            }
+        }
+        let duplicates = replaced.len();
+
+        let immigrants = ((config.immigrant_rate * size as f64).round() as usize).min(size);
+        if immigrants > 0 {
+            // scores of replaced duplicates are stale, they are not replaced again
+            let worst = self
+                .ranking()
+                .into_iter()
+                .rev()
+                .filter(|i| !replaced.contains(i))
+                .take(immigrants)
+                .collect::<Vec<_>>();
+            let newcomers =
+                generate_random_population::<N>(worst.len(), self.scenario.number_of_objects);
+            for (j, i) in worst.iter().enumerate() {
+                self.population.row_mut(*i).copy_from(&newcomers.row(j));
+                self.set_mutation_rate(*i, None);
+            }
+            replaced.extend(worst);
+        }
+
+        if !replaced.is_empty() {
+            let rows = replaced
+                .iter()
+                .map(|i| self.population.row(*i))
+                .collect::<Vec<_>>();
+            let scores = evaluate_population(&DMatrix::from_rows(&rows), &self.scenario);
+            for (i, score) in replaced.iter().zip(scores.iter()) {
+                self.scores[*i] = *score;
+            }
+            self.evaluations += replaced.len();
+            self.replaced(&replaced);
+        }
+
+        let counts = DiversityCounts {
+            duplicates,
+            immigrants: replaced.len() - duplicates,
+        };
+        self.diversity = DiversityReport {
+            last: counts,
+            total: self.diversity.total + counts,
+        };
+    }
+
+    /// Replaces individual `i` with one whose genotype is not in `seen` yet
+    fn replace_duplicate(
+        &mut self,
+        i: usize,
+        policy: DuplicatePolicy,
+        seen: &mut HashSet<Vec<u8>>,
+        rng: &mut ThreadRng,
+    ) {
+        let number_of_objects = self.scenario.number_of_objects;
+
+        if policy == DuplicatePolicy::Mutate && number_of_objects > 0 {
+            // the mutated duplicate stays the same individual with its own rate
+            let mutation_probability = self
+                .mutation_rate(i)
+                .unwrap_or(self.config.mutation_probability);
+            for _ in 0..MUTATION_ATTEMPTS {
+                let mut row = self.population.row_mut(i);
+                let gene = rng.gen_range(0, number_of_objects);
+                row[gene] = N::one() - row[gene];
+                mutate_with_rate(&mut row, rng, mutation_probability);
+
+                if seen.insert(genotype(self.population.row(i).iter())) {
+                    return;
+                }
+            }
+        }
+
+        // a random individual may repeat as well, on tiny scenarios all of them may
+        // be taken already so the last one is kept anyway
+        self.set_mutation_rate(i, None);
+        for _ in 0..MUTATION_ATTEMPTS {
+            let genes = generate_random_population::<N>(1, number_of_objects);
+            self.population.row_mut(i).copy_from(&genes.row(0));
+            if seen.insert(genotype(genes.iter())) {
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::population::config::Config;
+    use crate::population::{test_config, test_scenario};
+    use rand::thread_rng;
+    use std::sync::Arc;
+
+    fn population(diversity: DiversityConfig) -> Population<i64> {
+        let config = Config {
+            mutation_probability: 0.0,
+            crossover_probability: 0.0,
+            tournament_size: 20,
+            diversity: Some(diversity),
+            ..test_config(20)
+        };
+        Population::new(Arc::new(test_scenario(40, 100, 10)), config).unwrap()
+    }
+
+    fn distinct(population: &Population<i64>) -> usize {
+        (0..20)
+            .map(|i| genotype(population.population.row(i).iter()))
+            .collect::<HashSet<_>>()
+            .len()
+    }
+
+    #[test]
+    fn duplicates_are_replaced_by_unique_individuals() {
+        for policy in DuplicatePolicy::ALL.iter() {
+            let config = DiversityConfig {
+                duplicates: Some(*policy),
+                immigrant_rate: 0.0,
+            };
+            let mut population = population(config);
+
+            let first = population.population.row(0).clone_owned();
+            for i in 1..20 {
+                population.population.row_mut(i).copy_from(&first);
+            }
+            population.maintain_diversity(config, &mut thread_rng());
+
+            assert_eq!(distinct(&population), 20, "{}", policy);
+            assert_eq!(population.diversity().last.duplicates, 19);
+            assert_eq!(population.diversity().last.immigrants, 0);
+
+            let scores = evaluate_population(&population.population, &population.scenario);
+            assert_eq!(&scores, population.scores());
+        }
+    }
+
+    #[test]
+    fn random_immigrants_replace_the_worst() {
+        let mut population = population(DiversityConfig {
+            duplicates: None,
+            immigrant_rate: 0.25,
+        });
+
+        population.evolve();
+        population.evolve();
+
+        let report = population.diversity();
+        assert_eq!(report.last.immigrants, 5);
+        assert_eq!(report.total.immigrants, 10);
+        assert_eq!(report.total.duplicates, 0);
+        // 20 initial individuals, 20 children and 5 immigrants in each generation
+        assert_eq!(population.evaluations(), 70);
+    }
+}
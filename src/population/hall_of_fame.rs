@@ -0,0 +1,150 @@
+use crate::number::Number;
+use crate::population::niching::objects_distance;
+use crate::population::Population;
+
+/// Feasible solution kept by the hall of fame
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry<N> {
+    pub score: N,
+    /// Indices of chosen objects, in increasing order
+    pub objects: Vec<usize>,
+}
+
+/// The best distinct feasible solutions seen during the whole run
+///
+/// Solutions closer to each other than `min_distance` are not distinct and
+/// only the better of them is kept.
+#[derive(Debug, Clone)]
+pub struct HallOfFame<N> {
+    capacity: usize,
+    min_distance: usize,
+    /// Entries from the best to the worst one
+    entries: Vec<Entry<N>>,
+}
+
+impl<N: Number> HallOfFame<N> {
+    /// Distances below 1 are raised to 1 so equal solutions are never kept twice
+    pub fn new(capacity: usize, min_distance: usize) -> HallOfFame<N> {
+        HallOfFame {
+            capacity,
+            min_distance: min_distance.max(1),
+            entries: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Whether a solution scoring `score` could enter the hall of fame
+    pub fn admits(&self, score: N) -> bool {
+        score > N::zero()
+            && self.capacity > 0
+            && (self.entries.len() < self.capacity || score > self.entries.last().unwrap().score)
+    }
+
+    /// Adds a solution unless it is infeasible, worse than all entries of a
+    /// full hall of fame, or close to an entry which is at least as good
+    ///
+    /// Entries close to an added solution are worse than it and are removed.
+    /// Returns whether the solution was added.
+    pub fn offer(&mut self, score: N, objects: Vec<usize>) -> bool {
+        if !self.admits(score) {
+            return false;
+        }
+
+        let min_distance = self.min_distance;
+        let close = |entry: &Entry<N>| objects_distance(&entry.objects, &objects) < min_distance;
+        if self
+            .entries
+            .iter()
+            .any(|entry| close(entry) && entry.score >= score)
+        {
+            return false;
+        }
+        self.entries.retain(|entry| !close(entry));
+
+        let position = self
+            .entries
+            .iter()
+            .position(|entry| entry.score < score)
+            .unwrap_or(self.entries.len());
+        self.entries.insert(position, Entry { score, objects });
+        self.entries.truncate(self.capacity);
+        true
+    }
+
+    /// Entries from the best to the worst one
+    pub fn entries(&self) -> &[Entry<N>] {
+        &self.entries
+    }
+}
+
+impl<N: Number> Population<N> {
+    /// Offers the current individuals to `hall_of_fame`, the best ones first
+    pub fn update_hall_of_fame(&self, hall_of_fame: &mut HallOfFame<N>) {
+        for i in self.ranking() {
+            let score = self.scores[i];
+            // individuals come from the best one so no other one is admitted either
+            if !hall_of_fame.admits(score) {
+                break;
+            }
+
+            let objects = self
+                .population
+                .row(i)
+                .iter()
+                .enumerate()
+                .filter(|(_, gene)| **gene == N::one())
+                .map(|(i, _)| i)
+                .collect();
+            hall_of_fame.offer(score, objects);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn close_solutions_keep_the_better_one() {
+        let mut hall_of_fame = HallOfFame::new(3, 2);
+
+        assert!(hall_of_fame.offer(5, vec![0, 1]));
+        // 1 object away from the first entry and worse
+        assert!(!hall_of_fame.offer(4, vec![0, 1, 2]));
+        assert!(!hall_of_fame.offer(5, vec![0, 1]));
+        // better, replaces the first entry
+        assert!(hall_of_fame.offer(6, vec![0]));
+        assert!(hall_of_fame.offer(3, vec![4, 5]));
+
+        let scores = hall_of_fame
+            .entries()
+            .iter()
+            .map(|entry| entry.score)
+            .collect::<Vec<_>>();
+        assert_eq!(scores, vec![6, 3]);
+    }
+
+    #[test]
+    fn only_the_best_feasible_solutions_are_kept() {
+        let mut hall_of_fame = HallOfFame::new(2, 1);
+
+        assert!(!hall_of_fame.offer(0, vec![7]));
+        assert!(hall_of_fame.offer(2, vec![1]));
+        assert!(hall_of_fame.offer(9, vec![2]));
+        assert!(hall_of_fame.offer(4, vec![3]));
+        assert!(!hall_of_fame.offer(4, vec![4]));
+
+        assert_eq!(
+            hall_of_fame.entries(),
+            &[
+                Entry {
+                    score: 9,
+                    objects: vec![2]
+                },
+                Entry {
+                    score: 4,
+                    objects: vec![3]
+                }
+            ]
+        );
+    }
+}
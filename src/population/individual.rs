@@ -1,27 +1,28 @@
-use crate::consts::Number;
+use crate::number::Number;
 use crate::population::config::Config;
+use crate::population::operators::Operator;
 use rand::prelude::ThreadRng;
 use rand::Rng;
 
-type MutIndividual<'a> = na::Matrix<
-    Number,
+type MutIndividual<'a, N> = na::Matrix<
+    N,
     na::U1,
     na::Dynamic,
-    na::SliceStorageMut<'a, Number, na::U1, na::Dynamic, na::U1, na::Dynamic>,
+    na::SliceStorageMut<'a, N, na::U1, na::Dynamic, na::U1, na::Dynamic>,
 >;
 
-type Individual<'a> = na::Matrix<
-    Number,
+type Individual<'a, N> = na::Matrix<
+    N,
     na::U1,
     na::Dynamic,
-    na::SliceStorage<'a, Number, na::U1, na::Dynamic, na::U1, na::Dynamic>,
+    na::SliceStorage<'a, N, na::U1, na::Dynamic, na::U1, na::Dynamic>,
 >;
 
 /// Creates new individual and stores it into `child` parameter
-pub fn new_individual(
-    child: &mut MutIndividual,
-    parent1: &Individual,
-    parent2: &Individual,
+pub fn new_individual<N: Number>(
+    child: &mut MutIndividual<N>,
+    parent1: &Individual<N>,
+    parent2: &Individual<N>,
     rng: &mut ThreadRng,
     config: &Config,
 ) {
@@ -29,6 +30,42 @@ pub fn new_individual(
     mutate(child, rng, config);
 }
 
+/// Creates new individual like `new_individual` but mutates it with its own
+/// `mutation_probability` instead of the one of `config`
+pub fn new_individual_with_rate<N: Number>(
+    child: &mut MutIndividual<N>,
+    parent1: &Individual<N>,
+    parent2: &Individual<N>,
+    rng: &mut ThreadRng,
+    config: &Config,
+    mutation_probability: f64,
+) {
+    crossover(child, parent1, parent2, rng, config);
+    mutate_with_rate(child, rng, mutation_probability);
+}
+
+/// Creates new individual with `operator` and stores it into `child` parameter
+pub fn new_individual_with_operator<N: Number>(
+    child: &mut MutIndividual<N>,
+    parent1: &Individual<N>,
+    parent2: &Individual<N>,
+    rng: &mut ThreadRng,
+    config: &Config,
+    operator: Operator,
+) {
+    match operator {
+        Operator::OnePoint => crossover(child, parent1, parent2, rng, config),
+        Operator::TwoPoint => two_point_crossover(child, parent1, parent2, rng, config),
+        Operator::Uniform => uniform_crossover(child, parent1, parent2, rng, config),
+        Operator::Swap => {
+            child.copy_from(parent1);
+            swap_objects(child, rng);
+            return;
+        }
+    }
+    mutate(child, rng, config);
+}
+
 /// Crossover
 ///
 /// If parents will be able to replicate it will chose a random number
@@ -37,10 +74,10 @@ pub fn new_individual(
 ///
 /// If parents are not able to replicate it will copy `parent1` into
 /// the child.
-fn crossover(
-    child: &mut MutIndividual,
-    parent1: &Individual,
-    parent2: &Individual,
+fn crossover<N: Number>(
+    child: &mut MutIndividual<N>,
+    parent1: &Individual<N>,
+    parent2: &Individual<N>,
     rng: &mut ThreadRng,
     config: &Config,
 ) {
@@ -56,8 +93,8 @@ fn crossover(
     }
 
     // rng.gen_range generates [0, n) so it will never return
-    // parent1.nrows() which would overflow
-    let crossover_portion = rng.gen_range(0, parent1.nrows());
+    // parent1.ncols() which would overflow
+    let crossover_portion = rng.gen_range(0, parent1.ncols());
 
     let child_iter = parent1_iter
         .take(crossover_portion)
@@ -68,11 +105,128 @@ fn crossover(
     }
 }
 
+/// Two-point crossover
+///
+/// Genes between two random points come from `parent2` and the rest from
+/// `parent1`. Parents which are not able to replicate are copied like in
+/// the one-point crossover.
+fn two_point_crossover<N: Number>(
+    child: &mut MutIndividual<N>,
+    parent1: &Individual<N>,
+    parent2: &Individual<N>,
+    rng: &mut ThreadRng,
+    config: &Config,
+) {
+    child.copy_from(parent1);
+    if config.crossover_probability < rng.gen::<f64>() {
+        return;
+    }
+
+    let a = rng.gen_range(0, parent1.ncols() + 1);
+    let b = rng.gen_range(0, parent1.ncols() + 1);
+    for i in a.min(b)..a.max(b) {
+        child[i] = parent2[i];
+    }
+}
+
+/// Uniform crossover
+///
+/// Each gene comes from either parent with the same probability. Parents
+/// which are not able to replicate are copied like in the one-point crossover.
+fn uniform_crossover<N: Number>(
+    child: &mut MutIndividual<N>,
+    parent1: &Individual<N>,
+    parent2: &Individual<N>,
+    rng: &mut ThreadRng,
+    config: &Config,
+) {
+    child.copy_from(parent1);
+    if config.crossover_probability < rng.gen::<f64>() {
+        return;
+    }
+
+    for (i, gene) in parent2.iter().enumerate() {
+        if rng.gen::<bool>() {
+            child[i] = *gene;
+        }
+    }
+}
+
+/// Replaces a random chosen object with a random unchosen one, keeping the
+/// number of chosen objects
+///
+/// An individual choosing all or none of the objects gets a random gene flipped.
+fn swap_objects<N: Number>(individual: &mut MutIndividual<N>, rng: &mut ThreadRng) {
+    if individual.is_empty() {
+        return;
+    }
+
+    let (chosen, unchosen): (Vec<usize>, Vec<usize>) =
+        (0..individual.ncols()).partition(|i| individual[*i] == N::one());
+    if chosen.is_empty() || unchosen.is_empty() {
+        let i = rng.gen_range(0, individual.ncols());
+        individual[i] = N::one() - individual[i];
+        return;
+    }
+
+    let i = chosen[rng.gen_range(0, chosen.len())];
+    let j = unchosen[rng.gen_range(0, unchosen.len())];
+    individual[i] = N::zero();
+    individual[j] = N::one();
+}
+
 /// Mutation
 ///
 /// It will try to mutate each gen of the `child`.
-fn mutate(individual: &mut MutIndividual, rng: &mut ThreadRng, config: &Config) {
+pub fn mutate<N: Number>(individual: &mut MutIndividual<N>, rng: &mut ThreadRng, config: &Config) {
+    mutate_with_rate(individual, rng, config.mutation_probability);
+}
+
+/// Mutation with the own `mutation_probability` of an individual
+pub fn mutate_with_rate<N: Number>(
+    individual: &mut MutIndividual<N>,
+    rng: &mut ThreadRng,
+    mutation_probability: f64,
+) {
     for gen in individual.iter_mut() {
-        *gen = *gen ^ (config.mutation_probability >= rng.gen::<f64>()) as Number;
+        if mutation_probability >= rng.gen::<f64>() {
+            // genes are either 0 or 1 so this flips them
+            *gen = N::one() - *gen;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::population::test_config;
+    use na::DMatrix;
+    use rand::thread_rng;
+
+    #[test]
+    fn one_point_child_mixes_both_parents() {
+        let parents = DMatrix::from_fn(2, 10, |i, _| if i == 0 { 1 } else { 0 });
+        let mut child = DMatrix::<i64>::zeros(1, 10);
+        let config = Config {
+            crossover_probability: 1.0,
+            ..test_config(2)
+        };
+        let mut rng = thread_rng();
+
+        let mut mixed = false;
+        for _ in 0..50 {
+            crossover(
+                &mut child.row_mut(0),
+                &parents.row(0),
+                &parents.row(1),
+                &mut rng,
+                &config,
+            );
+            // genes of the first parent are followed by genes of the second one
+            let cut = child.iter().take_while(|gene| **gene == 1).count();
+            assert!(child.iter().skip(cut).all(|gene| *gene == 0));
+            mixed |= cut > 0;
+        }
+        assert!(mixed);
     }
 }
@@ -0,0 +1,501 @@
+use crate::number::Number;
+use crate::population::config::Config;
+use crate::population::control::Rates;
+use crate::population::diversity::DiversityReport;
+use crate::population::hall_of_fame::HallOfFame;
+use crate::population::niching::{merge_niches, Niche};
+use crate::population::stats::GenerationStats;
+use crate::population::{evaluate_population, Population};
+use crate::scenario::Scenario;
+use crossbeam_utils::thread;
+use na::{DMatrix, RowDVector};
+use rand::seq::index::sample;
+use rand::{thread_rng, Rng};
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Islands individuals migrate to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Topology {
+    /// Island `i` sends emigrants to island `i + 1`, the last one to the first one
+    Ring,
+    /// Every island sends emigrants to every other island
+    Full,
+    /// Every island sends emigrants to another island drawn at each migration
+    Random,
+}
+
+/// Individuals which leave an island
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EmigrantPolicy {
+    Best,
+    Random,
+}
+
+/// Individuals which are replaced by immigrants
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplacementPolicy {
+    Worst,
+    Random,
+}
+
+impl_policy!(Topology, UnknownTopology, "topology", [
+    "ring" => Ring,
+    "full" => Full,
+    "random" => Random
+]);
+impl_policy!(EmigrantPolicy, UnknownEmigrantPolicy, "emigrant policy", [
+    "best" => Best,
+    "random" => Random
+]);
+impl_policy!(ReplacementPolicy, UnknownReplacementPolicy, "replacement policy", [
+    "worst" => Worst,
+    "random" => Random
+]);
+
+#[derive(Debug, Clone)]
+pub struct MigrationConfig {
+    /// Generations between migrations, 0 disables migration
+    pub interval: usize,
+    /// Share of the population of an island which emigrates to each of its neighbours
+    pub rate: f64,
+    pub topology: Topology,
+    pub emigrants: EmigrantPolicy,
+    pub replacement: ReplacementPolicy,
+}
+
+/// Individual travelling between islands, scores do not change on the way
+/// as all islands share the scenario
+///
+/// Scores of migrants from other processes are not trusted, they are
+/// evaluated again on arrival.
+#[derive(Debug, Clone)]
+pub struct Migrant<N: Number> {
+    pub genes: RowDVector<N>,
+    pub score: N,
+    /// Mutation probability of an individual of a self-adaptive population
+    pub mutation_rate: Option<f64>,
+}
+
+/// Several populations evolving in parallel and exchanging individuals
+#[derive(Debug)]
+pub struct Islands<N: Number> {
+    islands: Vec<Population<N>>,
+    migration: MigrationConfig,
+    generation: usize,
+}
+
+impl<N: Number> Islands<N> {
+    /// Creates an island with its own population for each of `configs`
+    ///
+    /// All islands share `scenario` instead of holding copies of it.
+    pub fn new(
+        scenario: Scenario<N>,
+        configs: Vec<Config>,
+        migration: MigrationConfig,
+    ) -> Result<Islands<N>, String> {
+        if configs.is_empty() {
+            return Err("There has to be at least one island".to_string());
+        }
+
+        let scenario = Arc::new(scenario);
+        let islands = configs
+            .into_iter()
+            .map(|config| Population::new(Arc::clone(&scenario), config))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Islands {
+            islands,
+            migration,
+            generation: 0,
+        })
+    }
+
+    /// Evolves all islands and lets individuals migrate if it is time to
+    ///
+    /// Returns the best score of the new generation of all islands.
+    pub fn evolve(&mut self) -> N {
+        // Islands get their own threads instead of rayon tasks. Evolution blocks
+        // on threads of `evaluate_population` which use rayon as well so islands
+        // running on the pool could take all of its threads and deadlock.
+        thread::scope(|scope| {
+            for island in self.islands.iter_mut() {
+                scope.spawn(move |_| island.evolve());
+            }
+        })
+        .unwrap();
+        self.generation += 1;
+
+        if self.is_migration_generation() {
+            self.migrate(&mut thread_rng());
+        }
+
+        self.global_stats().best
+    }
+
+    /// True if individuals migrate after the current generation
+    pub fn is_migration_generation(&self) -> bool {
+        self.migration.interval > 0 && self.generation % self.migration.interval == 0
+    }
+
+    /// Number of individuals emigrating from `island` to each of its neighbours
+    fn emigrant_count(&self, island: &Population<N>) -> usize {
+        (self.migration.rate * island.config.population_size as f64).round() as usize
+    }
+
+    /// Sends emigrants of every island to its neighbours
+    ///
+    /// All emigrants are selected before any of them arrives so an individual
+    /// migrates at most one island further per migration.
+    pub fn migrate<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        let n = self.islands.len();
+        let mut arrivals = vec![Vec::new(); n];
+
+        for (i, island) in self.islands.iter().enumerate() {
+            let count = self.emigrant_count(island);
+            let emigrants = island.emigrants(count, self.migration.emigrants, rng);
+
+            for neighbour in neighbours(self.migration.topology, i, n, rng) {
+                arrivals[neighbour].extend(emigrants.iter().cloned());
+            }
+        }
+
+        for (island, immigrants) in self.islands.iter_mut().zip(arrivals) {
+            island.immigrate(immigrants, self.migration.replacement, rng);
+        }
+    }
+
+    /// Emigrants of all islands leaving for islands of other processes
+    pub fn emigrants<R: Rng + ?Sized>(&self, rng: &mut R) -> Vec<Migrant<N>> {
+        self.islands
+            .iter()
+            .flat_map(|island| {
+                let count = self.emigrant_count(island);
+                island.emigrants(count, self.migration.emigrants, rng)
+            })
+            .collect()
+    }
+
+    /// Spreads immigrants from other processes evenly over all islands
+    ///
+    /// Immigrants are scored anew, the scores they arrive with are ignored.
+    pub fn immigrate<R: Rng + ?Sized>(&mut self, immigrants: Vec<Migrant<N>>, rng: &mut R) {
+        let n = self.islands.len();
+        let mut arrivals = vec![Vec::new(); n];
+        for (i, immigrant) in immigrants.into_iter().enumerate() {
+            arrivals[i % n].push(immigrant);
+        }
+
+        for (island, mut immigrants) in self.islands.iter_mut().zip(arrivals) {
+            if !immigrants.is_empty() {
+                island.rescore(&mut immigrants);
+                island.immigrate(immigrants, self.migration.replacement, rng);
+            }
+        }
+    }
+
+    pub fn islands(&self) -> &[Population<N>] {
+        &self.islands
+    }
+
+    /// Statistics of the current generation of each island
+    pub fn stats(&self) -> Vec<GenerationStats<N>> {
+        self.islands
+            .iter()
+            .map(|island| GenerationStats::new(island.scores().iter()))
+            .collect()
+    }
+
+    /// Statistics of the current generation of all islands together
+    pub fn global_stats(&self) -> GenerationStats<N> {
+        GenerationStats::new(self.islands.iter().flat_map(|x| x.scores().iter()))
+    }
+
+    /// The island which has seen the best individual
+    fn best_island(&self) -> &Population<N> {
+        self.islands.iter().fold(&self.islands[0], |best, x| {
+            if x.best_score() > best.best_score() {
+                x
+            } else {
+                best
+            }
+        })
+    }
+
+    /// Number of individuals scored so far on all islands
+    pub fn evaluations(&self) -> usize {
+        self.islands.iter().map(|x| x.evaluations()).sum()
+    }
+
+    /// Individuals replaced to keep all islands diverse
+    pub fn diversity(&self) -> DiversityReport {
+        let reports = self.islands.iter().map(|x| *x.diversity());
+        reports.fold(DiversityReport::default(), |sum, x| DiversityReport {
+            last: sum.last + x.last,
+            total: sum.total + x.total,
+        })
+    }
+
+    /// Mean probabilities the current generation of all islands was bred with
+    pub fn rates(&self) -> Rates {
+        let n = self.islands.len() as f64;
+        let rates = self.islands.iter().map(|x| x.rates());
+        rates.fold(Rates::default(), |sum, x| Rates {
+            mutation: sum.mutation + x.mutation / n,
+            crossover: sum.crossover + x.crossover / n,
+        })
+    }
+
+    /// The best individual of each niche of the current generation of all islands
+    pub fn niches(&self, radius: usize) -> Vec<Niche<N>> {
+        let niches = self.islands.iter().flat_map(|x| x.niches(radius)).collect();
+        merge_niches(niches, radius)
+    }
+
+    /// Offers the current individuals of all islands to `hall_of_fame`
+    pub fn update_hall_of_fame(&self, hall_of_fame: &mut HallOfFame<N>) {
+        for island in self.islands.iter() {
+            island.update_hall_of_fame(hall_of_fame);
+        }
+    }
+
+    /// Score of the best individual seen so far on any island
+    pub fn best_score(&self) -> N {
+        self.best_island().best_score()
+    }
+
+    /// Indices of objects chosen by the best individual seen so far on any island
+    pub fn best_objects(&self) -> &[usize] {
+        self.best_island().best_objects()
+    }
+}
+
+/// Islands which island `i` of `n` sends its emigrants to
+fn neighbours<R: Rng + ?Sized>(topology: Topology, i: usize, n: usize, rng: &mut R) -> Vec<usize> {
+    if n < 2 {
+        return Vec::new();
+    }
+
+    match topology {
+        Topology::Ring => vec![(i + 1) % n],
+        Topology::Full => (0..n).filter(|j| *j != i).collect(),
+        Topology::Random => {
+            // skipping `i` keeps the draw uniform among the other islands
+            let j = rng.gen_range(0, n - 1);
+            vec![if j >= i { j + 1 } else { j }]
+        }
+    }
+}
+
+impl<N: Number> Population<N> {
+    /// Copies of `count` individuals selected by `policy`
+    pub fn emigrants<R: Rng + ?Sized>(
+        &self,
+        count: usize,
+        policy: EmigrantPolicy,
+        rng: &mut R,
+    ) -> Vec<Migrant<N>> {
+        let count = count.min(self.config.population_size);
+        let indices = match policy {
+            EmigrantPolicy::Best => self.ranking().into_iter().take(count).collect(),
+            EmigrantPolicy::Random => sample(rng, self.config.population_size, count).into_vec(),
+        };
+
+        indices
+            .into_iter()
+            .map(|i| Migrant {
+                genes: self.population.row(i).into_owned(),
+                score: self.scores[i],
+                mutation_rate: self.mutation_rate(i),
+            })
+            .collect()
+    }
+
+    /// Evaluates `immigrants` in the scenario of this population
+    fn rescore(&mut self, immigrants: &mut [Migrant<N>]) {
+        let genes = DMatrix::from_fn(immigrants.len(), self.scenario.number_of_objects, |i, j| {
+            immigrants[i].genes[j]
+        });
+        let scores = evaluate_population(&genes, &self.scenario);
+        self.evaluations += immigrants.len();
+
+        for (immigrant, score) in immigrants.iter_mut().zip(scores.iter()) {
+            immigrant.score = *score;
+        }
+    }
+
+    /// Replaces individuals selected by `policy` with `immigrants`
+    ///
+    /// If more individuals arrive then the population holds only the best of them stay.
+    pub fn immigrate<R: Rng + ?Sized>(
+        &mut self,
+        mut immigrants: Vec<Migrant<N>>,
+        policy: ReplacementPolicy,
+        rng: &mut R,
+    ) {
+        let size = self.config.population_size;
+        if immigrants.len() > size {
+            immigrants.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
+            immigrants.truncate(size);
+        }
+
+        let replaced = match policy {
+            ReplacementPolicy::Worst => self.ranking().into_iter().rev().collect(),
+            ReplacementPolicy::Random => sample(rng, size, immigrants.len()).into_vec(),
+        };
+
+        let replaced = replaced
+            .into_iter()
+            .zip(immigrants)
+            .map(|(i, immigrant)| {
+                self.population.row_mut(i).copy_from(&immigrant.genes);
+                self.scores[i] = immigrant.score;
+                self.set_mutation_rate(i, immigrant.mutation_rate);
+                i
+            })
+            .collect::<Vec<_>>();
+        self.replaced(&replaced);
+        self.update_best();
+    }
+
+    /// Indices of individuals from the best to the worst one
+    pub(super) fn ranking(&self) -> Vec<usize> {
+        let mut indices = (0..self.config.population_size).collect::<Vec<_>>();
+        indices.sort_by(|a, b| {
+            self.scores[*b]
+                .partial_cmp(&self.scores[*a])
+                .unwrap_or(Ordering::Equal)
+        });
+        indices
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::population::network::{decode, encode};
+    use crate::population::{test_config, test_scenario};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn migration(topology: Topology) -> MigrationConfig {
+        MigrationConfig {
+            interval: 1,
+            rate: 0.25,
+            topology,
+            emigrants: EmigrantPolicy::Best,
+            replacement: ReplacementPolicy::Worst,
+        }
+    }
+
+    #[test]
+    fn neighbours_follow_the_topology() {
+        let mut rng = StdRng::seed_from_u64(0);
+
+        assert_eq!(neighbours(Topology::Ring, 3, 4, &mut rng), vec![0]);
+        assert_eq!(neighbours(Topology::Full, 1, 4, &mut rng), vec![0, 2, 3]);
+        assert!(neighbours(Topology::Full, 0, 1, &mut rng).is_empty());
+        for _ in 0..100 {
+            let random = neighbours(Topology::Random, 2, 4, &mut rng);
+            assert!(random.len() == 1 && random[0] != 2 && random[0] < 4);
+        }
+    }
+
+    #[test]
+    fn best_emigrants_replace_the_worst_individuals() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut islands = Islands::new(
+            test_scenario(4, 4, 2),
+            vec![test_config(8); 2],
+            migration(Topology::Ring),
+        )
+        .unwrap();
+
+        // the first island only has empty knapsacks, the second one only the optimum
+        islands.islands[0].population.fill(0);
+        islands.islands[0].scores.fill(0);
+        islands.islands[1].population.fill(0);
+        islands.islands[1].population.column_mut(0).fill(1);
+        islands.islands[1].population.column_mut(1).fill(1);
+        islands.islands[1].scores.fill(7);
+
+        islands.migrate(&mut rng);
+
+        // 25% of 8 individuals arrive
+        assert_eq!(
+            islands.islands[0]
+                .scores()
+                .iter()
+                .filter(|x| **x == 7)
+                .count(),
+            2
+        );
+        assert_eq!(islands.islands[0].best_score(), 7);
+        assert_eq!(islands.islands[0].best_objects(), &[0, 1]);
+    }
+
+    #[test]
+    fn network_immigrants_are_rescored() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut islands = Islands::new(
+            test_scenario(4, 4, 2),
+            vec![test_config(4)],
+            migration(Topology::Ring),
+        )
+        .unwrap();
+        islands.islands[0].population.fill(0);
+        islands.islands[0].scores.fill(0);
+        islands.islands[0].update_best();
+
+        // an infeasible knapsack and the optimum, both claiming too high scores
+        let migrants: Vec<Migrant<i64>> = vec![
+            Migrant {
+                genes: RowDVector::from_row_slice(&[1, 1, 1, 1]),
+                score: 15,
+                mutation_rate: None,
+            },
+            Migrant {
+                genes: RowDVector::from_row_slice(&[1, 1, 0, 0]),
+                score: 100,
+                mutation_rate: None,
+            },
+        ];
+        let frame = encode(&migrants, 7, 4);
+        let received = decode::<i64, _>(&mut &frame[..], 7, 4).unwrap().unwrap();
+        assert_eq!(received[1].score, 100);
+
+        let evaluations = islands.evaluations();
+        islands.immigrate(received, &mut rng);
+
+        let mut scores = islands.islands[0]
+            .scores()
+            .iter()
+            .copied()
+            .collect::<Vec<_>>();
+        scores.sort_unstable();
+        assert_eq!(scores, vec![0, 0, 0, 7]);
+        assert_eq!(islands.best_score(), 7);
+        assert_eq!(islands.evaluations(), evaluations + 2);
+    }
+
+    #[test]
+    fn islands_keep_their_own_configs() {
+        let configs = vec![test_config(4), test_config(6), test_config(10)];
+        let mut islands =
+            Islands::new(test_scenario(4, 4, 2), configs, migration(Topology::Full)).unwrap();
+
+        for _ in 0..5 {
+            let best = islands.evolve();
+            assert!(best <= islands.best_score());
+        }
+
+        let sizes = islands.stats().iter().map(|x| x.size).collect::<Vec<_>>();
+        assert_eq!(sizes, vec![4, 6, 10]);
+        assert_eq!(islands.global_stats().size, 20);
+        // the optimum takes the two lightest objects
+        assert!(islands.best_score() <= 7);
+    }
+}
@@ -1,62 +1,226 @@
+/// Implements `FromStr` and `Display` using names from `VARIANTS`
+///
+/// Modules using it have to import `FromStr`, `Display`, `Formatter`, `fmt` and `Error`.
+macro_rules! impl_policy {
+    ($t:ident, $error:ident, $what:expr, [$($name:expr => $variant:ident),*]) => {
+        impl $t {
+            pub const VARIANTS: &'static [&'static str] = &[$($name),*];
+            const ALL: &'static [$t] = &[$($t::$variant),*];
+        }
+
+        impl FromStr for $t {
+            type Err = $error;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                $t::VARIANTS
+                    .iter()
+                    .position(|name| *name == s)
+                    .map(|i| $t::ALL[i])
+                    .ok_or_else(|| $error(s.to_string()))
+            }
+        }
+
+        impl Display for $t {
+            fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                let i = $t::ALL.iter().position(|x| x == self).unwrap();
+                write!(f, "{}", $t::VARIANTS[i])
+            }
+        }
+
+        #[derive(Debug)]
+        pub struct $error(String);
+
+        impl Error for $error {}
+
+        impl Display for $error {
+            fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                write!(
+                    f,
+                    "unknown {} {}, expected one of {}",
+                    $what,
+                    self.0,
+                    $t::VARIANTS.join(", ")
+                )
+            }
+        }
+    };
+}
+
+pub mod cellular;
 pub mod config;
+pub mod control;
+pub mod diversity;
+pub mod hall_of_fame;
 mod individual;
-mod random_vector;
-
-use crate::consts::Number;
+pub mod island;
+pub mod network;
+pub mod niching;
+pub mod operators;
+pub mod random_vector;
+pub mod stats;
+pub mod steady_state;
+pub mod survivors;
+pub mod tournament;
+
+use crate::number::Number;
 use crate::population::config::Config;
+use crate::population::control::{Control, ControlMethod};
+use crate::population::diversity::DiversityReport;
 use crate::population::individual::new_individual;
-use crate::population::random_vector::random_vector;
+use crate::population::niching::NichingMethod;
+use crate::population::operators::OperatorSelection;
+use crate::population::steady_state::SteadyState;
+use crate::population::tournament::tournament;
 use crate::scenario::Scenario;
 use crossbeam_utils::thread;
-use na::{DMatrix, DVector};
+use na::{DMatrix, DVector, Scalar};
 use rand::distributions::{Distribution, Uniform};
 use rand::thread_rng;
 use rayon::prelude::*;
 use std::mem::swap;
+use std::sync::Arc;
 
 #[derive(Debug)]
-pub struct Population {
-    scenario: Scenario,
+pub struct Population<N: Number> {
+    /// Shared by all islands
+    scenario: Arc<Scenario<N>>,
     config: Config,
-    population: DMatrix<Number>,
-    next_population: DMatrix<Number>,
+    population: DMatrix<N>,
+    next_population: DMatrix<N>,
+    /// Scores of the individuals of `population`
+    scores: DVector<N>,
+    /// Score and objects of the best individual seen so far
+    best: (N, Vec<usize>),
+    /// Cells each cell of a cellular population chooses parents from, empty otherwise
+    neighbourhoods: Vec<Vec<usize>>,
+    /// Totals and ages of individuals of a steady-state population
+    steady_state: Option<SteadyState<N>>,
+    /// Number of individuals scored so far
+    evaluations: usize,
+    /// Duplicates and random immigrants replaced by `maintain_diversity`
+    diversity: DiversityReport,
+    /// State of the control of mutation and crossover probabilities
+    control: Option<Control>,
+    /// Mutation probability of each individual when it is self-adaptive
+    ///
+    /// Like its score it belongs to the individual and moves with its genes.
+    mutation_rates: Option<DVector<f64>>,
+    /// State of adaptive operator selection
+    operators: Option<OperatorSelection>,
 }
 
-#[derive(Debug)]
-struct EvaluationArena {
-    weights: DVector<Number>,
-    sizes: DVector<Number>,
-    costs: DVector<Number>,
-}
+impl<N: Number> Population<N> {
+    pub fn new(scenario: Arc<Scenario<N>>, config: Config) -> Result<Population<N>, String> {
+        config.validate()?;
+        let neighbourhoods = match config.cellular {
+            Some(cellular) => cellular
+                .grid
+                .neighbourhoods(cellular.neighbourhood, cellular.radius),
+            None => Vec::new(),
+        };
 
-impl Population {
-    pub fn new(scenario: Scenario, config: Config) -> Population {
         let population =
-            generate_random_population(config.population_size, scenario.number_of_objects as usize);
+            generate_random_population(config.population_size, scenario.number_of_objects);
+        let scores = evaluate_population(&population, &scenario);
 
         // it is save because next_populations is only allocated memory placeholder
         let next_population = unsafe {
-            DMatrix::<Number>::new_uninitialized(
-                config.population_size,
-                scenario.number_of_objects as usize,
-            )
+            DMatrix::<N>::new_uninitialized(config.offspring_size(), scenario.number_of_objects)
         };
 
-        Population {
+        let mut population = Population {
             population,
             next_population,
+            scores,
             scenario,
             config,
+            best: (N::zero(), Vec::new()),
+            neighbourhoods,
+            steady_state: None,
+            evaluations: 0,
+            diversity: DiversityReport::default(),
+            control: None,
+            mutation_rates: None,
+            operators: None,
+        };
+        population.evaluations = population.config.population_size;
+        if let Some(steady_state) = population.config.steady_state {
+            population.steady_state = Some(SteadyState::new(steady_state, &population));
         }
+        if let Some(control) = population.config.control {
+            population.control = Some(Control::new(control, &population));
+            if control.method == ControlMethod::SelfAdaptive {
+                let size = population.config.population_size;
+                let initial = population.config.mutation_probability;
+                population.mutation_rates = Some(DVector::from_element(size, initial));
+            }
+        }
+        population.operators = population
+            .config
+            .operators
+            .clone()
+            .map(OperatorSelection::new);
+        population.update_best();
+        Ok(population)
+    }
+
+    /// Replaces the population with its offspring, or with the survivors
+    /// among parents and offspring
+    ///
+    /// Returns the best score of the new population. Without survivor
+    /// selection it holds only offspring, so the initial random population
+    /// is never reported. Its best individual is still remembered by
+    /// `best_score`.
+    pub fn evolve(&mut self) -> N {
+        self.control_rates();
+        self.breed();
+
+        if let Some(diversity) = self.config.diversity {
+            self.maintain_diversity(diversity, &mut thread_rng());
+        }
+
+        self.update_best()
     }
 
-    pub fn evolve(&mut self) -> Number {
-        let best_individual = evolve_population(
-            &self.population,
-            &mut self.next_population,
-            &self.scenario,
-            &self.config,
-        );
+    fn breed(&mut self) {
+        if self.steady_state.is_some() {
+            self.evolve_steady_state();
+            return;
+        }
+
+        self.evaluations += self.next_population.nrows();
+        if let Some(cellular) = self.config.cellular {
+            self.evolve_cellular(cellular);
+            return;
+        }
+
+        let parents = match self.config.niching {
+            Some(niching) if niching.method == NichingMethod::Crowding => {
+                self.evolve_crowding();
+                return;
+            }
+            Some(niching) if niching.method == NichingMethod::RestrictedTournament => {
+                self.evolve_restricted_tournament(niching.window);
+                return;
+            }
+            Some(niching) => self.breed_shared(niching),
+            None if self.is_self_adaptive() => self.breed_self_adaptive(),
+            None if self.operators.is_some() => self.breed_with_operators(),
+            None => breed_population(
+                &self.population,
+                &mut self.next_population,
+                &self.scores,
+                &self.config,
+            ),
+        };
+        let offspring_scores = evaluate_population(&self.next_population, &self.scenario);
+        self.record_successes(&parents, &offspring_scores);
+        self.credit_operators(&parents, &offspring_scores);
+
+        if let Some(survivors) = self.config.survivors {
+            self.select_survivors(survivors, offspring_scores);
+            return;
+        }
 
         // it is save because both matrices have the same size
         unsafe {
@@ -65,70 +229,133 @@ impl Population {
                 self.next_population.data.as_vec_mut(),
             );
         }
+        self.scores = offspring_scores;
+        self.inherit_rates();
+    }
+
+    /// Remembers the best individual of the population if it is the best one seen so far
+    ///
+    /// Returns the best score of the population.
+    fn update_best(&mut self) -> N {
+        let (best_individual, best_score) = best_individual(&self.scores);
+
+        if best_score > self.best.0 || self.best.1.is_empty() {
+            let objects = self
+                .population
+                .row(best_individual)
+                .iter()
+                .enumerate()
+                .filter(|(_, gene)| **gene == N::one())
+                .map(|(i, _)| i)
+                .collect();
+            self.best = (best_score, objects);
+        }
+
+        best_score
+    }
+
+    /// Score of the best individual seen so far
+    pub fn best_score(&self) -> N {
+        self.best.0
+    }
+
+    /// Indices of objects chosen by the best individual seen so far
+    pub fn best_objects(&self) -> &[usize] {
+        &self.best.1
+    }
+
+    /// Scores of the current individuals, infeasible ones score 0
+    pub fn scores(&self) -> &DVector<N> {
+        &self.scores
+    }
+
+    /// Number of individuals scored so far, including the initial population
+    pub fn evaluations(&self) -> usize {
+        self.evaluations
+    }
+
+    pub fn diversity(&self) -> &DiversityReport {
+        &self.diversity
+    }
 
-        best_individual
+    pub fn config(&self) -> &Config {
+        &self.config
     }
 }
 
 /// Generates and returns random population of given size
-fn generate_random_population(population_size: usize, number_of_objects: usize) -> DMatrix<Number> {
+fn generate_random_population<N: Number>(
+    population_size: usize,
+    number_of_objects: usize,
+) -> DMatrix<N> {
     // It creates a vector containing genes for feature population.
     // This vector is being created using multiple threads.
 
-    let bool_int = Uniform::from(0..11 as Number);
+    let bool_int = Uniform::from(0..11);
 
     let vec = (0..(population_size * number_of_objects))
         // creates a parallel iterator
         .into_par_iter()
         .map_init(
             // initializes state for each thread
-            || thread_rng(),
+            thread_rng,
             // evaluates value for each gene
-            |mut rng, _| (bool_int.sample(&mut rng) == 1) as Number,
+            |mut rng, _| bool_to_number(bool_int.sample(&mut rng) == 1),
         )
         .collect();
 
-    DMatrix::<Number>::from_vec(population_size, number_of_objects, vec)
+    DMatrix::<N>::from_vec(population_size, number_of_objects, vec)
+}
+
+/// Returns index and score of the best individual
+fn best_individual<N: Number>(scores: &DVector<N>) -> (usize, N) {
+    // scores are never negative because neither are costs
+    scores
+        .data
+        .as_vec()
+        .par_iter()
+        .cloned()
+        .enumerate()
+        .reduce(|| (0, N::zero()), |a, b| if b.1 > a.1 { b } else { a })
 }
 
-/// Evolves population and stores the result in `next_population`.
+/// Breeds offspring of `population` and stores it in `next_population`
 ///
-/// Returns the `best_score` before the evolution.
-fn evolve_population(
-    population: &DMatrix<Number>,
-    next_population: &mut DMatrix<Number>,
-    scenario: &Scenario,
+/// Returns parents of each child.
+fn breed_population<N: Number, S: Scalar + PartialOrd + Copy + Sync>(
+    population: &DMatrix<N>,
+    next_population: &mut DMatrix<N>,
+    scores: &DVector<S>,
     population_config: &Config,
-) -> Number {
-    let scores = evaluate_population(population, scenario);
-
-    let best_score = *scores.data.as_vec().par_iter().max().unwrap();
-
+) -> Vec<[usize; 2]> {
     // chunk population by each individual
     next_population
         .row_iter_mut()
         .collect::<Vec<_>>()
         .par_iter_mut()
-        .for_each_init(
-            || thread_rng(),
-            |rng, mut child| {
-                let parent1 = tournament(&scores, population_config.tournament_size);
-                let parent2 = tournament(&scores, population_config.tournament_size);
-
-                let parent1 = population.row(parent1);
-                let parent2 = population.row(parent2);
-
-                new_individual(&mut child, &parent1, &parent2, rng, population_config);
-            },
-        );
-
-    best_score
+        .map_init(thread_rng, |rng, child| {
+            let tournament_size = population_config.tournament_size;
+            let with_replacement = population_config.tournament_with_replacement;
+
+            let parent1 = tournament(scores, tournament_size, with_replacement, rng);
+            let parent2 = tournament(scores, tournament_size, with_replacement, rng);
+
+            new_individual(
+                child,
+                &population.row(parent1),
+                &population.row(parent2),
+                rng,
+                population_config,
+            );
+            [parent1, parent2]
+        })
+        .collect()
 }
 
 /// Evaluates the population
 ///
 /// Returns a vector of scores
-fn evaluate_population(population: &DMatrix<Number>, scenario: &Scenario) -> DVector<Number> {
+fn evaluate_population<N: Number>(population: &DMatrix<N>, scenario: &Scenario<N>) -> DVector<N> {
     // This function evaluates population using 3 independent threads.
     //
     // The first one calculates weights for each individual and checks the requirements.
@@ -147,12 +374,13 @@ fn evaluate_population(population: &DMatrix<Number>, scenario: &Scenario) -> DVe
         let weights_thread = scope.spawn(|_| {
             // calculate weights for population individuals
             let mut weights = population * &scenario.weights;
+            let weight_limit = scenario.weight_limit();
 
             // select only individuals matching the requirements
             // it is save because vec len stays the same
             let vec = unsafe { weights.data.as_vec_mut() };
             vec.into_par_iter().for_each(|elem| {
-                *elem = (*elem <= scenario.max_weight) as Number;
+                *elem = bool_to_number(*elem <= weight_limit);
             });
 
             weights
@@ -161,12 +389,13 @@ fn evaluate_population(population: &DMatrix<Number>, scenario: &Scenario) -> DVe
         let sizes_thread = scope.spawn(|_| {
             // calculate sizes for population individuals
             let mut sizes = population * &scenario.sizes;
+            let size_limit = scenario.size_limit();
 
             // select only individuals matching the requirements
             // it is save because vec len stays the same
             let vec = unsafe { sizes.data.as_vec_mut() };
             vec.into_par_iter().for_each(|elem| {
-                *elem = (*elem <= scenario.max_size) as Number;
+                *elem = bool_to_number(*elem <= size_limit);
             });
 
             sizes
@@ -187,16 +416,76 @@ fn evaluate_population(population: &DMatrix<Number>, scenario: &Scenario) -> DVe
     .unwrap()
 }
 
-/// Selects individual using tournament algorithm
-/// Returns selected individual's index
-fn tournament(scores: &DVector<Number>, tournament_size: usize) -> usize {
-    // Creates a random vector of {0, 1} and multiplies value by score.
-    // Them finds the best value and returns index of chosen individual.
+fn bool_to_number<N: Number>(b: bool) -> N {
+    if b {
+        N::one()
+    } else {
+        N::zero()
+    }
+}
+
+/// Scenario for tests, objects get heavier and cheaper with their index
+#[cfg(test)]
+fn test_scenario(number_of_objects: usize, max_weight: i64, max_size: i64) -> Scenario<i64> {
+    let n = number_of_objects as i64;
+    Scenario {
+        weights: (1..=n).collect::<Vec<_>>().into(),
+        sizes: vec![1; number_of_objects].into(),
+        costs: (1..=n).rev().collect::<Vec<_>>().into(),
+        number_of_objects,
+        max_weight,
+        max_size,
+        tolerance: 0,
+        labels: crate::scenario::Labels::default(),
+    }
+}
+
+/// Plain generational config, tests override the options they exercise
+#[cfg(test)]
+fn test_config(population_size: usize) -> Config {
+    Config {
+        mutation_probability: 0.1,
+        crossover_probability: 0.8,
+        population_size,
+        tournament_size: 2,
+        tournament_with_replacement: false,
+        cellular: None,
+        steady_state: None,
+        survivors: None,
+        diversity: None,
+        niching: None,
+        control: None,
+        operators: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scenario::Labels;
+
+    fn scenario(tolerance: f64) -> Scenario<f64> {
+        Scenario {
+            weights: vec![0.1, 0.2, 5.0].into(),
+            sizes: vec![1.0, 1.0, 1.0].into(),
+            costs: vec![1.5, 2.5, 10.0].into(),
+            number_of_objects: 3,
+            max_weight: 0.3,
+            max_size: 2.0,
+            tolerance,
+            labels: Labels::default(),
+        }
+    }
 
-    let mut selector = random_vector(tournament_size, scores.nrows());
+    #[test]
+    fn tolerance_absorbs_rounding_errors() {
+        // 0.1 + 0.2 is slightly more then 0.3 in floating point arithmetic
+        let population = DMatrix::from_row_slice(2, 3, &[1.0, 1.0, 0.0, 1.0, 1.0, 1.0]);
 
-    // Filter selected individuals
-    selector.component_mul_assign(scores);
-    let (best_idx, _) = selector.argmax();
-    best_idx
+        let scores = evaluate_population(&population, &scenario(0.0));
+        assert_eq!(scores.as_slice(), &[0.0, 0.0]);
+
+        let scores = evaluate_population(&population, &scenario(1e-9));
+        assert_eq!(scores.as_slice(), &[4.0, 0.0]);
+    }
 }
@@ -0,0 +1,549 @@
+use crate::number::{Number, NumberType};
+use crate::population::island::Migrant;
+use crate::scenario::Scenario;
+use crc32fast::Hasher;
+use na::RowDVector;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, BufReader, Read, Write};
+use std::mem::size_of;
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+const MAGIC: &[u8; 4] = b"GNTM";
+const VERSION: u16 = 2;
+const HEADER_SIZE: usize = 24;
+/// Larger batches are rejected before allocating memory for them
+const MAX_MIGRANTS: u32 = 1 << 20;
+
+// Frame header fields, all little-endian:
+//  0..4   MAGIC
+//  4..6   wire format version [u16]
+//  6      number type, index in `NumberType::ALL` [u8]
+//  7      reserved, zero
+//  8..12  fingerprint of the scenario [u32]
+// 12..20  number of objects [u64]
+// 20..24  number of migrants [u32]
+// Each migrant follows as its score [N padded with zeros to 8 bytes], its
+// self-adaptive mutation probability [f64, NaN if it carries none] and its
+// genes packed 8 per byte, the first gene in the lowest bit. Receivers only
+// trust the genes and score them again.
+
+impl<N: Number> Scenario<N> {
+    /// Checksum of the values of the scenario
+    ///
+    /// Processes only exchange migrants if they solve the same scenario.
+    pub fn fingerprint(&self) -> u32 {
+        let mut hasher = Hasher::new();
+        for column in [&self.weights, &self.sizes, &self.costs].iter() {
+            hasher.update(bytemuck::cast_slice(column.as_slice()));
+        }
+        for value in [self.max_weight, self.max_size, self.tolerance].iter() {
+            hasher.update(bytemuck::bytes_of(value));
+        }
+        hasher.finalize()
+    }
+}
+
+/// Error of a received frame
+#[derive(Debug)]
+pub enum WireError {
+    Io(io::Error),
+    InvalidMagic,
+    UnsupportedVersion {
+        version: u16,
+    },
+    NumberTypeMismatch {
+        found: String,
+        expected: &'static str,
+    },
+    ScenarioMismatch,
+    TooManyMigrants {
+        count: u32,
+    },
+    InvalidGene,
+}
+
+impl Error for WireError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            WireError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl Display for WireError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            WireError::Io(err) => write!(f, "{}", err),
+            WireError::InvalidMagic => write!(f, "peer does not speak the genet protocol"),
+            WireError::UnsupportedVersion { version } => {
+                write!(f, "wire format version {} is not supported", version)
+            }
+            WireError::NumberTypeMismatch { found, expected } => write!(
+                f,
+                "peer uses {} values but this process uses {}",
+                found, expected
+            ),
+            WireError::ScenarioMismatch => write!(f, "peer solves a different scenario"),
+            WireError::TooManyMigrants { count } => {
+                write!(f, "{} migrants do not fit into a single frame", count)
+            }
+            WireError::InvalidGene => write!(f, "genes beyond the last object are set"),
+        }
+    }
+}
+
+impl From<io::Error> for WireError {
+    fn from(err: io::Error) -> Self {
+        WireError::Io(err)
+    }
+}
+
+/// Serializes migrants of a scenario with given fingerprint into a frame
+pub fn encode<N: Number>(
+    migrants: &[Migrant<N>],
+    fingerprint: u32,
+    number_of_objects: usize,
+) -> Vec<u8> {
+    let genes_size = number_of_objects.div_ceil(8);
+    let mut frame = Vec::with_capacity(HEADER_SIZE + migrants.len() * (16 + genes_size));
+
+    frame.extend_from_slice(MAGIC);
+    frame.extend_from_slice(&VERSION.to_le_bytes());
+    frame.push(number_type_tag(N::TYPE));
+    frame.push(0);
+    frame.extend_from_slice(&fingerprint.to_le_bytes());
+    frame.extend_from_slice(&(number_of_objects as u64).to_le_bytes());
+    frame.extend_from_slice(&(migrants.len() as u32).to_le_bytes());
+
+    for migrant in migrants {
+        let mut score = [0; 8];
+        score[..size_of::<N>()].copy_from_slice(bytemuck::bytes_of(&migrant.score));
+        frame.extend_from_slice(&score);
+        let mutation_rate = migrant.mutation_rate.unwrap_or(f64::NAN);
+        frame.extend_from_slice(&mutation_rate.to_le_bytes());
+
+        let mut genes = vec![0u8; genes_size];
+        for (i, gene) in migrant.genes.iter().enumerate() {
+            if *gene == N::one() {
+                genes[i / 8] |= 1 << (i % 8);
+            }
+        }
+        frame.extend_from_slice(&genes);
+    }
+
+    frame
+}
+
+/// Reads a single frame, returns `None` if the stream ends before it starts
+pub fn decode<N: Number, R: Read>(
+    reader: &mut R,
+    fingerprint: u32,
+    number_of_objects: usize,
+) -> Result<Option<Vec<Migrant<N>>>, WireError> {
+    let mut header = [0; HEADER_SIZE];
+    match reader.read(&mut header[..1])? {
+        0 => return Ok(None),
+        _ => reader.read_exact(&mut header[1..])?,
+    }
+
+    if &header[..4] != MAGIC {
+        return Err(WireError::InvalidMagic);
+    }
+    let version = u16::from_le_bytes([header[4], header[5]]);
+    if version != VERSION {
+        return Err(WireError::UnsupportedVersion { version });
+    }
+    if header[6] != number_type_tag(N::TYPE) {
+        let found = NumberType::ALL.get(header[6] as usize).map_or_else(
+            || format!("unknown type {}", header[6]),
+            |x| x.name().to_string(),
+        );
+        return Err(WireError::NumberTypeMismatch {
+            found,
+            expected: N::NAME,
+        });
+    }
+
+    let mut bytes = [0; 8];
+    bytes[..4].copy_from_slice(&header[8..12]);
+    let peer_fingerprint = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    bytes.copy_from_slice(&header[12..20]);
+    let peer_objects = u64::from_le_bytes(bytes);
+    if peer_fingerprint != fingerprint || peer_objects != number_of_objects as u64 {
+        return Err(WireError::ScenarioMismatch);
+    }
+
+    let count = u32::from_le_bytes([header[20], header[21], header[22], header[23]]);
+    if count > MAX_MIGRANTS {
+        return Err(WireError::TooManyMigrants { count });
+    }
+
+    let mut genes = vec![0u8; number_of_objects.div_ceil(8)];
+    let mut migrants = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let mut score = [0; 8];
+        reader.read_exact(&mut score)?;
+        let mut mutation_rate = [0; 8];
+        reader.read_exact(&mut mutation_rate)?;
+        reader.read_exact(&mut genes)?;
+
+        let unused_bits = genes.len() * 8 - number_of_objects;
+        if unused_bits > 0 && genes[genes.len() - 1] >> (8 - unused_bits) != 0 {
+            return Err(WireError::InvalidGene);
+        }
+
+        migrants.push(Migrant {
+            score: bytemuck::pod_read_unaligned(&score[..size_of::<N>()]),
+            genes: RowDVector::from_fn(number_of_objects, |_, i| {
+                if genes[i / 8] & (1 << (i % 8)) != 0 {
+                    N::one()
+                } else {
+                    N::zero()
+                }
+            }),
+            mutation_rate: Some(f64::from_le_bytes(mutation_rate)).filter(|x| !x.is_nan()),
+        });
+    }
+
+    Ok(Some(migrants))
+}
+
+fn number_type_tag(number_type: NumberType) -> u8 {
+    NumberType::ALL
+        .iter()
+        .position(|x| *x == number_type)
+        .unwrap() as u8
+}
+
+/// Options of a process exchanging migrants with other processes
+#[derive(Debug, Clone)]
+pub struct NetworkConfig {
+    /// Address migrants are received on
+    pub listen: String,
+    /// Addresses of processes migrants are sent to
+    pub peers: Vec<String>,
+    /// Limit of connecting to and writing to a peer
+    pub timeout: Duration,
+}
+
+/// Counters of a node since it started listening
+#[derive(Debug, Default)]
+pub struct NodeStats {
+    pub sent: AtomicUsize,
+    pub received: AtomicUsize,
+    /// Frames of peers which use another version, number type or scenario
+    pub rejected_frames: AtomicUsize,
+}
+
+/// Peer migrants are sent to
+#[derive(Debug)]
+struct Peer {
+    address: String,
+    stream: Option<TcpStream>,
+    /// Whether the last attempt to reach it succeeded, it is reported when it changes
+    reachable: Option<bool>,
+}
+
+/// Process taking part in a distributed island model
+///
+/// Migrants are sent over TCP to every peer and received from anyone who
+/// connects. Peers which are not running yet or went away are skipped and
+/// tried again with the next batch, so they may join late and leave early.
+pub struct Node<N: Number> {
+    address: SocketAddr,
+    peers: Vec<Peer>,
+    fingerprint: u32,
+    number_of_objects: usize,
+    inbox: Arc<Mutex<Vec<Migrant<N>>>>,
+    stats: Arc<NodeStats>,
+    stopped: Arc<AtomicBool>,
+    timeout: Duration,
+}
+
+impl<N: Number + 'static> Node<N> {
+    /// Starts listening for migrants of `scenario` on `address`
+    ///
+    /// Port 0 lets the system choose a free one, see `local_address`.
+    pub fn listen<A: ToSocketAddrs>(
+        address: A,
+        scenario: &Scenario<N>,
+        timeout: Duration,
+    ) -> io::Result<Node<N>> {
+        let listener = TcpListener::bind(address)?;
+        let address = listener.local_addr()?;
+
+        let node = Node {
+            address,
+            peers: Vec::new(),
+            fingerprint: scenario.fingerprint(),
+            number_of_objects: scenario.number_of_objects,
+            inbox: Arc::new(Mutex::new(Vec::new())),
+            stats: Arc::new(NodeStats::default()),
+            stopped: Arc::new(AtomicBool::new(false)),
+            timeout,
+        };
+
+        let (inbox, stats, stopped) =
+            (node.inbox.clone(), node.stats.clone(), node.stopped.clone());
+        let (fingerprint, number_of_objects) = (node.fingerprint, node.number_of_objects);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                if stopped.load(Ordering::SeqCst) {
+                    break;
+                }
+                let stream = match stream {
+                    Ok(stream) => stream,
+                    Err(_) => continue,
+                };
+
+                let (inbox, stats) = (inbox.clone(), stats.clone());
+                thread::spawn(move || {
+                    receive(stream, fingerprint, number_of_objects, &inbox, &stats)
+                });
+            }
+        });
+
+        Ok(node)
+    }
+
+    pub fn local_address(&self) -> SocketAddr {
+        self.address
+    }
+
+    pub fn add_peer(&mut self, address: String) {
+        self.peers.push(Peer {
+            address,
+            stream: None,
+            reachable: None,
+        });
+    }
+
+    pub fn stats(&self) -> &NodeStats {
+        &self.stats
+    }
+
+    /// Sends migrants to every reachable peer
+    ///
+    /// Returns the number of peers which got them.
+    pub fn send(&mut self, migrants: &[Migrant<N>]) -> usize {
+        let frame = encode(migrants, self.fingerprint, self.number_of_objects);
+        let mut delivered = 0;
+
+        for peer in self.peers.iter_mut() {
+            let result = send_to(peer, &frame, self.timeout);
+
+            let reachable = result.is_ok();
+            if peer.reachable != Some(reachable) {
+                match &result {
+                    Ok(()) => eprintln!("Peer {} connected", peer.address),
+                    Err(err) => eprintln!("Peer {} unavailable: {}", peer.address, err),
+                }
+            }
+            peer.reachable = Some(reachable);
+
+            if reachable {
+                delivered += 1;
+                self.stats.sent.fetch_add(migrants.len(), Ordering::SeqCst);
+            }
+        }
+
+        delivered
+    }
+
+    /// Takes all migrants received since the last call
+    pub fn receive(&self) -> Vec<Migrant<N>> {
+        let mut inbox = self.inbox.lock().unwrap();
+        std::mem::take(&mut *inbox)
+    }
+}
+
+impl<N: Number> Drop for Node<N> {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        // wakes up the listening thread so it can notice it should stop
+        let _ = TcpStream::connect_timeout(&self.address, self.timeout);
+    }
+}
+
+/// Writes `frame` to the peer, connecting first if it is not connected
+fn send_to(peer: &mut Peer, frame: &[u8], timeout: Duration) -> io::Result<()> {
+    if peer.stream.is_none() {
+        let mut last_error = io::Error::new(io::ErrorKind::NotFound, "address resolves to nothing");
+        for address in peer.address.to_socket_addrs()? {
+            match TcpStream::connect_timeout(&address, timeout) {
+                Ok(stream) => {
+                    stream.set_write_timeout(Some(timeout))?;
+                    stream.set_nodelay(true)?;
+                    peer.stream = Some(stream);
+                    break;
+                }
+                Err(err) => last_error = err,
+            }
+        }
+        if peer.stream.is_none() {
+            return Err(last_error);
+        }
+    }
+
+    let result = peer.stream.as_mut().unwrap().write_all(frame);
+    if result.is_err() {
+        // the peer went away, it is reconnected with the next batch
+        peer.stream = None;
+    }
+    result
+}
+
+/// Reads frames from a connected peer until it disconnects or sends an invalid one
+fn receive<N: Number>(
+    stream: TcpStream,
+    fingerprint: u32,
+    number_of_objects: usize,
+    inbox: &Mutex<Vec<Migrant<N>>>,
+    stats: &NodeStats,
+) {
+    let peer = stream
+        .peer_addr()
+        .map_or_else(|_| "unknown".to_string(), |x| x.to_string());
+    let mut reader = BufReader::new(stream);
+
+    loop {
+        match decode::<N, _>(&mut reader, fingerprint, number_of_objects) {
+            Ok(Some(migrants)) => {
+                stats.received.fetch_add(migrants.len(), Ordering::SeqCst);
+                inbox.lock().unwrap().extend(migrants);
+            }
+            Ok(None) => break,
+            // a peer which disappears in the middle of a frame is not an error of the protocol
+            Err(WireError::Io(_)) => break,
+            Err(err) => {
+                stats.rejected_frames.fetch_add(1, Ordering::SeqCst);
+                eprintln!("Rejected migrants from {}: {}", peer, err);
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::population::test_scenario;
+    use std::time::Instant;
+
+    fn migrant(genes: &[i64], score: i64) -> Migrant<i64> {
+        Migrant {
+            genes: RowDVector::from_row_slice(genes),
+            score,
+            mutation_rate: None,
+        }
+    }
+
+    const TIMEOUT: Duration = Duration::from_millis(500);
+
+    /// Waits until `node` receives `count` migrants
+    fn wait_for(node: &Node<i64>, count: usize) -> Vec<Migrant<i64>> {
+        let start = Instant::now();
+        let mut received = Vec::new();
+        while received.len() < count && start.elapsed() < Duration::from_secs(5) {
+            received.extend(node.receive());
+            thread::sleep(Duration::from_millis(10));
+        }
+        received
+    }
+
+    #[test]
+    fn frames_round_trip() {
+        let migrants = vec![
+            migrant(&[1, 0, 0, 0, 0, 0, 0, 0, 0, 1], 11),
+            Migrant {
+                mutation_rate: Some(0.03),
+                ..migrant(&[0, 1, 1, 0, 0, 0, 0, 0, 0, 0], 17)
+            },
+        ];
+        let frame = encode(&migrants, 7, 10);
+        // header, then 8 bytes of score, 8 bytes of mutation probability and
+        // 2 bytes of genes per migrant
+        assert_eq!(frame.len(), HEADER_SIZE + 2 * 18);
+
+        let decoded = decode::<i64, _>(&mut &frame[..], 7, 10).unwrap().unwrap();
+        assert_eq!(decoded[0].genes, migrants[0].genes);
+        assert_eq!(decoded[0].mutation_rate, None);
+        assert_eq!(decoded[1].score, 17);
+        assert_eq!(decoded[1].mutation_rate, Some(0.03));
+
+        assert!(matches!(
+            decode::<i64, _>(&mut &frame[..], 8, 10),
+            Err(WireError::ScenarioMismatch)
+        ));
+        assert!(matches!(
+            decode::<f64, _>(&mut &frame[..], 7, 10),
+            Err(WireError::NumberTypeMismatch { .. })
+        ));
+        assert!(matches!(decode::<i64, _>(&mut &[][..], 7, 10), Ok(None)));
+    }
+
+    #[test]
+    fn late_peers_receive_later_batches() {
+        let scenario = test_scenario(10, 20, 4);
+        let batch = vec![migrant(&[1, 1, 0, 0, 0, 0, 0, 0, 0, 0], 19)];
+
+        // reserve a free port for the peer which joins later
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let mut sender = Node::listen("127.0.0.1:0", &scenario, TIMEOUT).unwrap();
+        sender.add_peer(format!("127.0.0.1:{}", port));
+
+        assert_eq!(sender.send(&batch), 0);
+
+        let receiver = Node::listen(("127.0.0.1", port), &scenario, TIMEOUT).unwrap();
+        assert_eq!(sender.send(&batch), 1);
+        assert_eq!(wait_for(&receiver, 1).len(), 1);
+
+        assert_eq!(sender.stats().sent.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn peers_which_disappear_are_skipped() {
+        let scenario = test_scenario(10, 20, 4);
+        let batch = vec![migrant(&[1, 1, 0, 0, 0, 0, 0, 0, 0, 0], 19)];
+
+        let peer = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut sender = Node::listen("127.0.0.1:0", &scenario, TIMEOUT).unwrap();
+        sender.add_peer(peer.local_addr().unwrap().to_string());
+        assert_eq!(sender.send(&batch), 1);
+
+        // the peer closes its connection and stops listening
+        drop(peer.accept().unwrap());
+        drop(peer);
+
+        let delivered = (0..5).map(|_| sender.send(&batch)).collect::<Vec<_>>();
+        assert_eq!(delivered.last(), Some(&0));
+    }
+
+    #[test]
+    fn peers_with_another_scenario_are_rejected() {
+        let receiver = Node::listen("127.0.0.1:0", &test_scenario(10, 20, 4), TIMEOUT).unwrap();
+        let mut sender = Node::listen("127.0.0.1:0", &test_scenario(10, 21, 4), TIMEOUT).unwrap();
+        sender.add_peer(receiver.local_address().to_string());
+
+        sender.send(&[migrant(&[1, 0, 0, 0, 0, 0, 0, 0, 0, 0], 10)]);
+
+        let start = Instant::now();
+        while receiver.stats().rejected_frames.load(Ordering::SeqCst) == 0
+            && start.elapsed() < Duration::from_secs(5)
+        {
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(receiver.stats().rejected_frames.load(Ordering::SeqCst), 1);
+        assert!(receiver.receive().is_empty());
+    }
+}
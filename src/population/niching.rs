@@ -0,0 +1,358 @@
+use crate::number::Number;
+use crate::population::diversity::genotype;
+use crate::population::individual::new_individual;
+use crate::population::{breed_population, evaluate_population, Population};
+use na::DVector;
+use rand::seq::index::sample;
+use rand::seq::SliceRandom;
+use rand::thread_rng;
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+/// Ways of keeping several niches of good individuals in the population
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NichingMethod {
+    /// Parents are selected by their score divided by the number of
+    /// individuals within the radius around them
+    Sharing,
+    /// Parents are paired at random and each child competes with the parent
+    /// it is more similar to
+    Crowding,
+    /// Each child competes with the most similar individual of a random window
+    RestrictedTournament,
+}
+
+impl_policy!(NichingMethod, UnknownNichingMethod, "niching method", [
+    "sharing" => Sharing,
+    "crowding" => Crowding,
+    "rts" => RestrictedTournament
+]);
+
+/// Options of niching
+#[derive(Debug, Clone, Copy)]
+pub struct NichingConfig {
+    pub method: NichingMethod,
+    /// Hamming distance within which individuals share their score and
+    /// belong to the same niche in the report
+    pub radius: usize,
+    /// Shape α of the sharing function `1 - (d / radius)^α`
+    pub alpha: f64,
+    /// Number of individuals a child of restricted tournament selection is compared to
+    pub window: usize,
+}
+
+/// The best individual of a group of similar ones
+#[derive(Debug, Clone, PartialEq)]
+pub struct Niche<N> {
+    pub score: N,
+    /// Indices of objects chosen by the best individual, in increasing order
+    pub objects: Vec<usize>,
+    /// Number of individuals in the niche
+    pub members: usize,
+}
+
+/// Number of genes in which two packed genotypes differ
+fn distance(a: &[u8], b: &[u8]) -> usize {
+    a.iter()
+        .zip(b.iter())
+        .map(|(a, b)| (a ^ b).count_ones() as usize)
+        .sum()
+}
+
+/// Number of objects chosen by exactly one of two individuals
+pub(super) fn objects_distance(a: &[usize], b: &[usize]) -> usize {
+    let (mut i, mut j, mut common) = (0, 0, 0);
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            Ordering::Less => i += 1,
+            Ordering::Greater => j += 1,
+            Ordering::Equal => {
+                common += 1;
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    a.len() + b.len() - 2 * common
+}
+
+/// Groups niches whose best individuals are within `radius` of each other
+///
+/// Going from the best individual down, each one starts a new niche unless it
+/// is within the radius of the best individual of an existing one, in which
+/// case it joins the closest such niche.
+pub fn merge_niches<N: Number>(mut candidates: Vec<Niche<N>>, radius: usize) -> Vec<Niche<N>> {
+    candidates.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
+
+    let mut niches: Vec<Niche<N>> = Vec::new();
+    for candidate in candidates {
+        let closest = niches
+            .iter_mut()
+            .map(|niche| (objects_distance(&niche.objects, &candidate.objects), niche))
+            .filter(|(distance, _)| *distance <= radius)
+            .min_by_key(|(distance, _)| *distance);
+
+        match closest {
+            Some((_, niche)) => niche.members += candidate.members,
+            None => niches.push(candidate),
+        }
+    }
+
+    niches
+}
+
+impl<N: Number> Population<N> {
+    /// Scores divided by niche counts, which are sums of `1 - (d / radius)^α`
+    /// over all individuals closer than the radius including the individual itself
+    fn shared_scores(&self, niching: NichingConfig) -> DVector<f64> {
+        let size = self.config.population_size;
+        let genotypes = self.genotypes();
+        let radius = niching.radius.max(1) as f64;
+
+        DVector::from_fn(size, |i, _| {
+            let niche_count = genotypes
+                .iter()
+                .map(|other| distance(&genotypes[i], other) as f64)
+                .filter(|d| *d < radius)
+                .map(|d| 1.0 - (d / radius).powf(niching.alpha))
+                .sum::<f64>();
+            self.scores[i].to_f64() / niche_count
+        })
+    }
+
+    fn genotypes(&self) -> Vec<Vec<u8>> {
+        (0..self.population.nrows())
+            .map(|i| genotype(self.population.row(i).iter()))
+            .collect()
+    }
+
+    /// Breeds offspring from parents selected by their shared scores
+    ///
+    /// Returns parents of each child.
+    pub(super) fn breed_shared(&mut self, niching: NichingConfig) -> Vec<[usize; 2]> {
+        let shared = self.shared_scores(niching);
+        breed_population(
+            &self.population,
+            &mut self.next_population,
+            &shared,
+            &self.config,
+        )
+    }
+
+    /// Deterministic crowding
+    ///
+    /// Parents are paired at random and each pair breeds two children. The
+    /// children are matched with the parents so that the sum of distances
+    /// of the matched couples is the smallest and each child replaces its
+    /// parent unless it scores worse.
+    pub(super) fn evolve_crowding(&mut self) {
+        let mut rng = thread_rng();
+        let size = self.config.population_size;
+
+        let mut order = (0..size).collect::<Vec<_>>();
+        order.shuffle(&mut rng);
+        // with an odd population size the last individual pairs with the first
+        // one and they breed a single child
+        let pairs = (0..size)
+            .step_by(2)
+            .map(|k| (order[k], order[(k + 1) % size]))
+            .collect::<Vec<_>>();
+
+        for (k, (parent1, parent2)) in pairs.iter().cloned().enumerate() {
+            for (j, (a, b)) in [(parent1, parent2), (parent2, parent1)].iter().enumerate() {
+                if 2 * k + j < size {
+                    new_individual(
+                        &mut self.next_population.row_mut(2 * k + j),
+                        &self.population.row(*a),
+                        &self.population.row(*b),
+                        &mut rng,
+                        &self.config,
+                    );
+                }
+            }
+        }
+
+        let child_scores = evaluate_population(&self.next_population, &self.scenario);
+        let parents = pairs
+            .iter()
+            .flat_map(|(a, b)| vec![[*a, *b], [*b, *a]])
+            .take(size)
+            .collect::<Vec<_>>();
+        self.record_successes(&parents, &child_scores);
+
+        for (k, (parent1, parent2)) in pairs.into_iter().enumerate() {
+            let child = |j: usize| genotype(self.next_population.row(2 * k + j).iter());
+            let parent = |i: usize| genotype(self.population.row(i).iter());
+            let (child1, parent1_genes, parent2_genes) =
+                (child(0), parent(parent1), parent(parent2));
+
+            let matches = if 2 * k + 1 < size {
+                let child2 = child(1);
+                let straight =
+                    distance(&parent1_genes, &child1) + distance(&parent2_genes, &child2);
+                let crossed = distance(&parent1_genes, &child2) + distance(&parent2_genes, &child1);
+                if straight <= crossed {
+                    vec![(parent1, 0), (parent2, 1)]
+                } else {
+                    vec![(parent1, 1), (parent2, 0)]
+                }
+            } else if distance(&parent1_genes, &child1) <= distance(&parent2_genes, &child1) {
+                vec![(parent1, 0)]
+            } else {
+                vec![(parent2, 0)]
+            };
+
+            for (parent, j) in matches {
+                let row = 2 * k + j;
+                if child_scores[row] >= self.scores[parent] {
+                    self.population
+                        .row_mut(parent)
+                        .copy_from(&self.next_population.row(row));
+                    self.scores[parent] = child_scores[row];
+                }
+            }
+        }
+    }
+
+    /// Restricted tournament selection
+    ///
+    /// Offspring is bred as usual and each child replaces the most similar
+    /// individual of `window` random ones if it scores better.
+    pub(super) fn evolve_restricted_tournament(&mut self, window: usize) {
+        let mut rng = thread_rng();
+        let size = self.config.population_size;
+
+        let parents = breed_population(
+            &self.population,
+            &mut self.next_population,
+            &self.scores,
+            &self.config,
+        );
+        let child_scores = evaluate_population(&self.next_population, &self.scenario);
+        self.record_successes(&parents, &child_scores);
+
+        let mut genotypes = self.genotypes();
+        for (j, score) in child_scores.iter().enumerate() {
+            let child = genotype(self.next_population.row(j).iter());
+            let nearest = sample(&mut rng, size, window.clamp(1, size))
+                .into_iter()
+                .min_by_key(|i| distance(&genotypes[*i], &child))
+                .unwrap();
+
+            if *score > self.scores[nearest] {
+                self.population
+                    .row_mut(nearest)
+                    .copy_from(&self.next_population.row(j));
+                self.scores[nearest] = *score;
+                genotypes[nearest] = child;
+            }
+        }
+    }
+
+    /// The best feasible individual of each niche of the current population
+    pub fn niches(&self, radius: usize) -> Vec<Niche<N>> {
+        let candidates = (0..self.config.population_size)
+            .filter(|i| self.scores[*i] > N::zero())
+            .map(|i| Niche {
+                score: self.scores[i],
+                objects: self
+                    .population
+                    .row(i)
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, gene)| **gene == N::one())
+                    .map(|(i, _)| i)
+                    .collect(),
+                members: 1,
+            })
+            .collect();
+
+        merge_niches(candidates, radius)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::population::config::Config;
+    use crate::population::{test_config, test_scenario};
+    use std::sync::Arc;
+
+    fn population(method: NichingMethod) -> Population<i64> {
+        let config = Config {
+            mutation_probability: 0.05,
+            niching: Some(NichingConfig {
+                method,
+                radius: 4,
+                alpha: 1.0,
+                window: 5,
+            }),
+            ..test_config(21)
+        };
+        Population::new(Arc::new(test_scenario(16, 30, 6)), config).unwrap()
+    }
+
+    #[test]
+    fn crowded_individuals_share_their_scores() {
+        let mut population = population(NichingMethod::Sharing);
+        // 3 copies of objects {0, 1}, 17 copies of objects {2, 3}, which are 4
+        // genes away, and objects {2, 3, 4}, which are 1 gene away from them
+        population.population.fill(0);
+        for i in 0..21 {
+            let objects: &[usize] = match i {
+                0..=2 => &[0, 1],
+                3..=19 => &[2, 3],
+                _ => &[2, 3, 4],
+            };
+            for j in objects {
+                population.population[(i, *j)] = 1;
+            }
+        }
+        population.scores = evaluate_population(&population.population, &population.scenario);
+
+        let shared = population.shared_scores(population.config.niching.unwrap());
+        assert_eq!(shared[0], 31.0 / 3.0);
+        // each of the 17 copies counts 1, the close neighbour counts 1 - 1 / 4
+        assert_eq!(shared[3], 27.0 / 17.75);
+    }
+
+    #[test]
+    fn replacement_schemes_never_lose_the_best() {
+        for method in [NichingMethod::Crowding, NichingMethod::RestrictedTournament].iter() {
+            let mut population = population(*method);
+
+            for _ in 0..30 {
+                let best = population.scores().max();
+                population.evolve();
+                assert!(population.scores().max() >= best, "{}", method);
+            }
+
+            let scores = evaluate_population(&population.population, &population.scenario);
+            assert_eq!(&scores, population.scores());
+        }
+    }
+
+    #[test]
+    fn niches_group_similar_individuals() {
+        let niche = |score, objects: &[usize]| Niche {
+            score,
+            objects: objects.to_vec(),
+            members: 1,
+        };
+        let niches = merge_niches(
+            vec![
+                niche(5, &[0, 1]),
+                niche(9, &[0, 1, 2]),
+                niche(8, &[5, 6, 7]),
+                niche(7, &[5, 6]),
+            ],
+            1,
+        );
+
+        assert_eq!(niches.len(), 2);
+        assert_eq!((niches[0].score, niches[0].members), (9, 2));
+        assert_eq!(niches[1].objects, vec![5, 6, 7]);
+    }
+}
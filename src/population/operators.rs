@@ -0,0 +1,435 @@
+use crate::number::Number;
+use crate::population::individual::new_individual_with_operator;
+use crate::population::tournament::tournament;
+use crate::population::Population;
+use na::DVector;
+use rand::{thread_rng, Rng};
+use rayon::prelude::*;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+/// Ways of breeding a child out of two parents
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operator {
+    /// One-point crossover followed by bit-flip mutation, the default breeding
+    OnePoint,
+    /// Two-point crossover followed by bit-flip mutation
+    TwoPoint,
+    /// Uniform crossover followed by bit-flip mutation
+    Uniform,
+    /// Copy of the first parent with a chosen object swapped for an unchosen one
+    Swap,
+}
+
+impl_policy!(Operator, UnknownOperator, "operator", [
+    "one-point" => OnePoint,
+    "two-point" => TwoPoint,
+    "uniform" => Uniform,
+    "swap" => Swap
+]);
+
+/// Ways of choosing operators from their credit
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SelectionStrategy {
+    /// Operators are chosen with probabilities proportional to their quality
+    ProbabilityMatching,
+    /// Probability of the best operator moves towards the largest allowed one
+    /// and probabilities of the others towards the smallest one
+    AdaptivePursuit,
+    /// Operator with the best quality plus an exploration bonus of
+    /// `c * sqrt(2 * ln(uses of all operators) / uses of the operator)`
+    Ucb,
+}
+
+impl_policy!(SelectionStrategy, UnknownSelectionStrategy, "operator selection", [
+    "probability-matching" => ProbabilityMatching,
+    "adaptive-pursuit" => AdaptivePursuit,
+    "ucb" => Ucb
+]);
+
+/// Options of adaptive operator selection
+#[derive(Debug, Clone)]
+pub struct OperatorConfig {
+    pub operators: Vec<Operator>,
+    pub strategy: SelectionStrategy,
+    /// Smallest probability of each operator of probability matching and adaptive pursuit
+    pub min_probability: f64,
+    /// Share α of the reward of the last generation in the quality of an operator
+    pub adaptation_rate: f64,
+    /// Learning rate β of adaptive pursuit
+    pub pursuit_rate: f64,
+    /// Weight c of the exploration bonus of UCB
+    pub exploration: f64,
+}
+
+impl OperatorConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        let operators = &self.operators;
+        if operators.is_empty() {
+            return Err("There has to be at least one operator".to_string());
+        }
+        if let Some((_, operator)) = operators
+            .iter()
+            .enumerate()
+            .find(|(i, operator)| operators[..*i].contains(operator))
+        {
+            return Err(format!("Operator {} is given more then once", operator));
+        }
+        if !(0.0..=1.0 / operators.len() as f64).contains(&self.min_probability) {
+            return Err(format!(
+                "Smallest operator probability has to be in range [0, 1/{}]",
+                operators.len()
+            ));
+        }
+        for (name, rate) in [
+            ("Operator adaptation rate", self.adaptation_rate),
+            ("Pursuit rate", self.pursuit_rate),
+        ]
+        .iter()
+        {
+            if !(0.0..=1.0).contains(rate) {
+                return Err(format!("{} has to be in range [0, 1]", name));
+            }
+        }
+        if self.exploration < 0.0 {
+            return Err("UCB exploration has to be non negative".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Usage and credit of an operator in the last generation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OperatorStats {
+    pub operator: Operator,
+    /// Number of children bred by the operator
+    pub usage: usize,
+    /// Mean relative improvement of the children over their better parent,
+    /// children worse than it count as no improvement
+    pub credit: f64,
+    /// Quality of the operator after the generation
+    pub quality: f64,
+    /// Probability of choosing the operator for the next generation, UCB
+    /// reports the share of children the operator bred
+    pub probability: f64,
+}
+
+impl OperatorStats {
+    /// Header of the CSV columns written by `to_csv`
+    pub const CSV_HEADER: &'static str = "operator,usage,credit,quality,probability";
+
+    pub fn to_csv(&self) -> String {
+        format!(
+            "{},{},{},{},{}",
+            self.operator, self.usage, self.credit, self.quality, self.probability
+        )
+    }
+}
+
+/// State of adaptive operator selection of a population
+#[derive(Debug)]
+pub(super) struct OperatorSelection {
+    config: OperatorConfig,
+    /// Running averages of rewards, which are credits of a generation
+    /// divided by the largest one of them
+    quality: Vec<f64>,
+    probability: Vec<f64>,
+    /// Number of children each operator bred since the start
+    total_usage: Vec<usize>,
+    /// Index of the operator of each child of the current generation
+    assigned: Vec<usize>,
+    /// Usage and credit of the last generation
+    usage: Vec<usize>,
+    credit: Vec<f64>,
+}
+
+impl OperatorSelection {
+    pub(super) fn new(config: OperatorConfig) -> OperatorSelection {
+        let n = config.operators.len();
+        OperatorSelection {
+            config,
+            quality: vec![0.0; n],
+            probability: vec![1.0 / n as f64; n],
+            total_usage: vec![0; n],
+            assigned: Vec::new(),
+            usage: vec![0; n],
+            credit: vec![0.0; n],
+        }
+    }
+
+    /// Chooses an operator for each of `children`
+    fn assign<R: Rng + ?Sized>(&mut self, children: usize, rng: &mut R) {
+        let n = self.config.operators.len();
+        self.assigned = match self.config.strategy {
+            SelectionStrategy::Ucb => {
+                // children are assigned one by one as if each of them was bred
+                // before the next one is assigned, with the qualities unchanged
+                let mut usage = self.total_usage.clone();
+                (0..children)
+                    .map(|_| {
+                        let total = usage.iter().sum::<usize>().max(1) as f64;
+                        let bound = |i: usize| match usage[i] {
+                            0 => f64::INFINITY,
+                            uses => {
+                                self.quality[i]
+                                    + self.config.exploration
+                                        * (2.0 * total.ln() / uses as f64).sqrt()
+                            }
+                        };
+                        let best =
+                            (1..n).fold(0, |best, i| if bound(i) > bound(best) { i } else { best });
+                        usage[best] += 1;
+                        best
+                    })
+                    .collect()
+            }
+            _ => (0..children)
+                .map(|_| {
+                    let mut x = rng.gen::<f64>();
+                    // rounding errors may leave `x` above the sum of probabilities
+                    (0..n)
+                        .find(|i| {
+                            x -= self.probability[*i];
+                            x < 0.0
+                        })
+                        .unwrap_or(n - 1)
+                })
+                .collect(),
+        };
+    }
+
+    /// Updates qualities and probabilities from credits of the assigned children
+    fn reward(&mut self, credits: &[f64]) {
+        let n = self.config.operators.len();
+        let (alpha, p_min) = (self.config.adaptation_rate, self.config.min_probability);
+
+        self.usage = vec![0; n];
+        self.credit = vec![0.0; n];
+        for (operator, credit) in self.assigned.iter().zip(credits.iter()) {
+            self.usage[*operator] += 1;
+            self.credit[*operator] += credit;
+        }
+        for i in 0..n {
+            if self.usage[i] > 0 {
+                self.credit[i] /= self.usage[i] as f64;
+            }
+            self.total_usage[i] += self.usage[i];
+        }
+
+        let largest = self.credit.iter().cloned().fold(0.0, f64::max);
+        for i in 0..n {
+            if self.usage[i] == 0 {
+                continue;
+            }
+            let reward = if largest > 0.0 {
+                self.credit[i] / largest
+            } else {
+                0.0
+            };
+            self.quality[i] += alpha * (reward - self.quality[i]);
+        }
+
+        match self.config.strategy {
+            SelectionStrategy::ProbabilityMatching => {
+                let sum = self.quality.iter().sum::<f64>();
+                for i in 0..n {
+                    self.probability[i] = if sum > 0.0 {
+                        p_min + (1.0 - n as f64 * p_min) * self.quality[i] / sum
+                    } else {
+                        1.0 / n as f64
+                    };
+                }
+            }
+            SelectionStrategy::AdaptivePursuit => {
+                let beta = self.config.pursuit_rate;
+                let p_max = 1.0 - (n - 1) as f64 * p_min;
+                let best = (1..n).fold(0, |best, i| {
+                    if self.quality[i] > self.quality[best] {
+                        i
+                    } else {
+                        best
+                    }
+                });
+                for i in 0..n {
+                    let target = if i == best { p_max } else { p_min };
+                    self.probability[i] += beta * (target - self.probability[i]);
+                }
+            }
+            SelectionStrategy::Ucb => {
+                let children = self.assigned.len().max(1) as f64;
+                for i in 0..n {
+                    self.probability[i] = self.usage[i] as f64 / children;
+                }
+            }
+        }
+    }
+}
+
+impl<N: Number> Population<N> {
+    /// Breeds offspring with operators chosen by adaptive operator selection
+    ///
+    /// Returns parents of each child.
+    pub(super) fn breed_with_operators(&mut self) -> Vec<[usize; 2]> {
+        let selection = self.operators.as_mut().unwrap();
+        selection.assign(self.next_population.nrows(), &mut thread_rng());
+
+        let operators = &selection.config.operators;
+        let assigned = &selection.assigned;
+        let (population, scores, config) = (&self.population, &self.scores, &self.config);
+
+        self.next_population
+            .row_iter_mut()
+            .collect::<Vec<_>>()
+            .par_iter_mut()
+            .enumerate()
+            .map_init(thread_rng, |rng, (j, child)| {
+                let tournament_size = config.tournament_size;
+                let with_replacement = config.tournament_with_replacement;
+
+                let parent1 = tournament(scores, tournament_size, with_replacement, rng);
+                let parent2 = tournament(scores, tournament_size, with_replacement, rng);
+
+                new_individual_with_operator(
+                    child,
+                    &population.row(parent1),
+                    &population.row(parent2),
+                    rng,
+                    config,
+                    operators[assigned[j]],
+                );
+                [parent1, parent2]
+            })
+            .collect()
+    }
+
+    /// Credits operators with the improvements of their children over the
+    /// better of their parents, before the children replace anyone
+    pub(super) fn credit_operators(&mut self, parents: &[[usize; 2]], scores: &DVector<N>) {
+        if self.operators.is_none() {
+            return;
+        }
+
+        let credits = parents
+            .iter()
+            .zip(scores.iter())
+            .map(|(parents, score)| {
+                let parent = self.scores[parents[0]]
+                    .to_f64()
+                    .max(self.scores[parents[1]].to_f64());
+                let score = score.to_f64();
+                if parent > 0.0 {
+                    (score - parent).max(0.0) / parent
+                } else if score > 0.0 {
+                    // the first feasible child of infeasible parents
+                    1.0
+                } else {
+                    0.0
+                }
+            })
+            .collect::<Vec<_>>();
+        self.operators.as_mut().unwrap().reward(&credits);
+    }
+
+    /// Usage and credit of each operator in the last generation, `None`
+    /// unless operators are chosen adaptively
+    pub fn operator_stats(&self) -> Option<Vec<OperatorStats>> {
+        let selection = self.operators.as_ref()?;
+
+        let stats = selection
+            .config
+            .operators
+            .iter()
+            .enumerate()
+            .map(|(i, operator)| OperatorStats {
+                operator: *operator,
+                usage: selection.usage[i],
+                credit: selection.credit[i],
+                quality: selection.quality[i],
+                probability: selection.probability[i],
+            })
+            .collect();
+        Some(stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::population::config::Config;
+    use crate::population::{test_config, test_scenario};
+    use std::sync::Arc;
+
+    fn selection(strategy: SelectionStrategy) -> OperatorSelection {
+        OperatorSelection::new(OperatorConfig {
+            operators: Operator::ALL.to_vec(),
+            strategy,
+            min_probability: 0.1,
+            adaptation_rate: 0.5,
+            pursuit_rate: 0.5,
+            exploration: 0.1,
+        })
+    }
+
+    #[test]
+    fn strategies_prefer_operators_with_credit() {
+        // only children of the uniform crossover improve on their parents
+        let credit = |operator: &usize| if *operator == 2 { 0.3 } else { 0.0 };
+        let mut rng = thread_rng();
+
+        for strategy in SelectionStrategy::ALL.iter() {
+            let mut selection = selection(*strategy);
+            for _ in 0..20 {
+                selection.assign(100, &mut rng);
+                let credits = selection.assigned.iter().map(credit).collect::<Vec<_>>();
+                selection.reward(&credits);
+            }
+
+            assert!(selection.usage[2] > 50, "{}", strategy);
+            assert!(
+                selection.total_usage.iter().all(|usage| *usage > 0),
+                "{}",
+                strategy
+            );
+        }
+
+        let selection = {
+            let mut selection = selection(SelectionStrategy::ProbabilityMatching);
+            selection.assign(100, &mut rng);
+            let credits = selection.assigned.iter().map(credit).collect::<Vec<_>>();
+            selection.reward(&credits);
+            selection
+        };
+        // the uniform crossover gets the quality 0.5, the others stay at 0
+        assert!((selection.probability[2] - 0.7).abs() < 1e-12);
+        assert!((selection.probability[0] - 0.1).abs() < 1e-12);
+    }
+
+    #[test]
+    fn ucb_tries_every_operator_first() {
+        let mut selection = selection(SelectionStrategy::Ucb);
+        selection.assign(4, &mut thread_rng());
+
+        let mut assigned = selection.assigned.clone();
+        assigned.sort_unstable();
+        assert_eq!(assigned, vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn operators_breed_the_whole_offspring() {
+        let config = Config {
+            operators: Some(selection(SelectionStrategy::AdaptivePursuit).config),
+            ..test_config(10)
+        };
+        let mut population = Population::new(Arc::new(test_scenario(12, 20, 5)), config).unwrap();
+
+        for _ in 0..5 {
+            population.evolve();
+            let stats = population.operator_stats().unwrap();
+            assert_eq!(stats.iter().map(|x| x.usage).sum::<usize>(), 10);
+            let probability = stats.iter().map(|x| x.probability).sum::<f64>();
+            assert!((probability - 1.0).abs() < 1e-9);
+        }
+        assert_eq!(population.evaluations(), 60);
+    }
+}
@@ -1,8 +1,10 @@
-use crate::consts::Number;
 use nalgebra::DVector;
 use rand::distributions::{Distribution, Uniform};
 use rand::prelude::thread_rng;
 
+/// Masks only hold {0, 1} so they do not depend on the scenario number type.
+type Number = i32;
+
 /// Returns DVector of zeros and ones.
 /// It will contain randomly distributed `desired_positives` of ones (1).
 /// The rest of values will be 0.
@@ -24,7 +26,7 @@ pub fn random_vector(desired_positives: usize, size: usize) -> DVector<Number> {
     let mut res = sparse_random_vec(size - desired_positives, size);
 
     for num in res.iter_mut() {
-      *num = *num ^ (1 as Number);
+      *num ^= 1;
     }
 
     res
@@ -0,0 +1,80 @@
+use crate::number::Number;
+
+/// Summary of the scores of a single generation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GenerationStats<N> {
+    pub best: N,
+    pub worst: N,
+    pub mean: f64,
+    pub std_dev: f64,
+    /// Number of individuals the statistics were computed from
+    pub size: usize,
+}
+
+impl<N: Number> GenerationStats<N> {
+    /// Computes statistics of `scores`, which may not be empty
+    pub fn new<'a, I: IntoIterator<Item = &'a N>>(scores: I) -> GenerationStats<N> {
+        let mut scores = scores.into_iter();
+        let first = *scores.next().expect("statistics of no scores");
+
+        let mut stats = GenerationStats {
+            best: first,
+            worst: first,
+            mean: first.to_f64(),
+            std_dev: 0.0,
+            size: 1,
+        };
+        // Welford's algorithm, `std_dev` holds the sum of squared differences until the end
+        for score in scores {
+            if *score > stats.best {
+                stats.best = *score;
+            }
+            if *score < stats.worst {
+                stats.worst = *score;
+            }
+
+            let x = score.to_f64();
+            stats.size += 1;
+            let delta = x - stats.mean;
+            stats.mean += delta / stats.size as f64;
+            stats.std_dev += delta * (x - stats.mean);
+        }
+        stats.std_dev = (stats.std_dev / stats.size as f64).sqrt();
+
+        stats
+    }
+
+    /// Statistics of the scores increased by `offset`
+    pub fn with_offset(self, offset: N) -> GenerationStats<N> {
+        GenerationStats {
+            best: self.best + offset,
+            worst: self.worst + offset,
+            mean: self.mean + offset.to_f64(),
+            ..self
+        }
+    }
+
+    /// Header of the CSV columns written by `to_csv`
+    pub const CSV_HEADER: &'static str = "best,worst,mean,std_dev";
+
+    pub fn to_csv(&self) -> String {
+        format!(
+            "{},{},{},{}",
+            self.best, self.worst, self.mean, self.std_dev
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stats_summarize_scores() {
+        let stats = GenerationStats::new(&[2, 4, 4, 4, 5, 5, 7, 9]);
+
+        assert_eq!((stats.best, stats.worst, stats.size), (9, 2, 8));
+        assert_eq!(stats.mean, 5.0);
+        assert_eq!(stats.std_dev, 2.0);
+    }
+}
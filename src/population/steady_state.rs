@@ -0,0 +1,418 @@
+use crate::number::Number;
+use crate::population::individual::new_individual;
+use crate::population::tournament::tournament;
+use crate::population::Population;
+use na::{DMatrix, DVector};
+use rand::prelude::ThreadRng;
+use rand::{thread_rng, Rng};
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+/// Individuals replaced by children in the steady-state mode
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Replacement {
+    /// The individual with the lowest score
+    Worst,
+    /// Any individual
+    Random,
+    /// The individual which was born first
+    Oldest,
+    /// The parent of the child which differs from it in the fewest genes
+    SimilarParent,
+}
+
+impl_policy!(Replacement, UnknownReplacement, "steady-state replacement", [
+    "worst" => Worst,
+    "random" => Random,
+    "oldest" => Oldest,
+    "similar-parent" => SimilarParent
+]);
+
+/// Steps after which totals of all individuals are recomputed from scratch
+///
+/// Incremental totals of floating point numbers accumulate rounding errors,
+/// this bounds them.
+const RESCORE_INTERVAL: usize = 64;
+
+/// Relative distance from a limit within which totals of a child are
+/// recomputed from scratch before deciding whether it fits
+const BOUNDARY_MARGIN: f64 = 1e-9;
+
+/// Options of the steady-state mode
+#[derive(Debug, Clone, Copy)]
+pub struct SteadyStateConfig {
+    /// Children bred in each step
+    pub children: usize,
+    pub replacement: Replacement,
+}
+
+/// State of a population evolving in the steady-state mode
+#[derive(Debug)]
+pub(super) struct SteadyState<N: Number> {
+    config: SteadyStateConfig,
+    /// Total weight, size and cost of each individual so children can be
+    /// scored from the totals of their parent
+    weights: DVector<N>,
+    sizes: DVector<N>,
+    costs: DVector<N>,
+    /// Number of evaluations made before each individual was born
+    births: Vec<usize>,
+    steps: usize,
+}
+
+impl<N: Number> SteadyState<N> {
+    pub(super) fn new(config: SteadyStateConfig, population: &Population<N>) -> SteadyState<N> {
+        let scenario = &population.scenario;
+
+        SteadyState {
+            config,
+            weights: &population.population * &scenario.weights,
+            sizes: &population.population * &scenario.sizes,
+            costs: &population.population * &scenario.costs,
+            births: vec![0; population.config.population_size],
+            steps: 0,
+        }
+    }
+}
+
+/// Child bred in a step, not yet placed into the population
+struct Child<N> {
+    parents: [usize; 2],
+    weight: N,
+    size: N,
+    cost: N,
+}
+
+impl<N: Number> Population<N> {
+    /// Makes steps until the number of new children reaches the population size
+    pub(super) fn evolve_steady_state(&mut self) {
+        let mut rng = thread_rng();
+        let mut children = 0;
+
+        while children < self.config.population_size {
+            children += self.step(&mut rng);
+        }
+    }
+
+    /// Breeds a few children, scores them and replaces individuals with them
+    ///
+    /// Returns the number of children.
+    pub fn step(&mut self, rng: &mut ThreadRng) -> usize {
+        let config = match &self.steady_state {
+            Some(state) => state.config,
+            None => panic!("Steps are made only in the steady-state mode"),
+        };
+        let count = config.children.min(self.config.population_size);
+
+        let mut offspring = DMatrix::<N>::zeros(count, self.scenario.number_of_objects);
+        let mut children = Vec::with_capacity(count);
+        for j in 0..count {
+            let parents = [self.select(rng), self.select(rng)];
+            new_individual(
+                &mut offspring.row_mut(j),
+                &self.population.row(parents[0]),
+                &self.population.row(parents[1]),
+                rng,
+                &self.config,
+            );
+            let mut child = self.score_child(&offspring, j, parents);
+            if self.near_limits(&child) {
+                child = self.rescore_child(&offspring, j, parents);
+            }
+            children.push(child);
+        }
+        self.evaluations += count;
+
+        let scores = children
+            .iter()
+            .map(|child| {
+                if child.weight <= self.scenario.weight_limit()
+                    && child.size <= self.scenario.size_limit()
+                {
+                    child.cost
+                } else {
+                    N::zero()
+                }
+            })
+            .collect::<Vec<_>>();
+        for (child, score) in children.iter().zip(scores.iter()) {
+            self.record_success(child.parents, *score);
+        }
+
+        let mut replaced = Vec::with_capacity(count);
+        for (j, child) in children.into_iter().enumerate() {
+            let i = self.victim(config.replacement, &offspring, j, &child, &replaced, rng);
+            replaced.push(i);
+
+            self.population.row_mut(i).copy_from(&offspring.row(j));
+            self.scores[i] = scores[j];
+
+            let state = self.steady_state.as_mut().unwrap();
+            state.weights[i] = child.weight;
+            state.sizes[i] = child.size;
+            state.costs[i] = child.cost;
+            state.births[i] = self.evaluations;
+        }
+
+        let state = self.steady_state.as_mut().unwrap();
+        state.steps += 1;
+        if state.steps % RESCORE_INTERVAL == 0 {
+            self.rescore_all();
+        }
+
+        count
+    }
+
+    fn select(&self, rng: &mut ThreadRng) -> usize {
+        tournament(
+            &self.scores,
+            self.config.tournament_size,
+            self.config.tournament_with_replacement,
+            rng,
+        )
+    }
+
+    /// Computes totals of row `j` of `offspring` from the totals of its first parent
+    ///
+    /// Only genes in which the child differs from the parent are visited.
+    fn score_child(&self, offspring: &DMatrix<N>, j: usize, parents: [usize; 2]) -> Child<N> {
+        let state = self.steady_state.as_ref().unwrap();
+        let parent = parents[0];
+
+        let mut child = Child {
+            parents,
+            weight: state.weights[parent],
+            size: state.sizes[parent],
+            cost: state.costs[parent],
+        };
+
+        let genes = offspring.row(j);
+        let parent_genes = self.population.row(parent);
+        for (i, (gene, parent_gene)) in genes.iter().zip(parent_genes.iter()).enumerate() {
+            if gene == parent_gene {
+                continue;
+            }
+            if *gene == N::one() {
+                child.weight += self.scenario.weights[i];
+                child.size += self.scenario.sizes[i];
+                child.cost += self.scenario.costs[i];
+            } else {
+                child.weight -= self.scenario.weights[i];
+                child.size -= self.scenario.sizes[i];
+                child.cost -= self.scenario.costs[i];
+            }
+        }
+
+        child
+    }
+
+    /// Computes totals of row `j` of `offspring` visiting all genes
+    fn rescore_child(&self, offspring: &DMatrix<N>, j: usize, parents: [usize; 2]) -> Child<N> {
+        let genes = offspring.row(j);
+        Child {
+            parents,
+            weight: (&genes * &self.scenario.weights)[0],
+            size: (&genes * &self.scenario.sizes)[0],
+            cost: (&genes * &self.scenario.costs)[0],
+        }
+    }
+
+    /// Whether rounding errors of the totals of `child` could decide if it fits
+    fn near_limits(&self, child: &Child<N>) -> bool {
+        let near = |total: N, limit: N| {
+            let limit = limit.to_f64();
+            (total.to_f64() - limit).abs() <= BOUNDARY_MARGIN * limit.abs().max(1.0)
+        };
+        near(child.weight, self.scenario.weight_limit())
+            || near(child.size, self.scenario.size_limit())
+    }
+
+    /// Recomputes totals and scores of all individuals from their genes
+    fn rescore_all(&mut self) {
+        let state = self.steady_state.as_mut().unwrap();
+        state.weights = &self.population * &self.scenario.weights;
+        state.sizes = &self.population * &self.scenario.sizes;
+        state.costs = &self.population * &self.scenario.costs;
+
+        for i in 0..self.config.population_size {
+            self.scores[i] = if state.weights[i] <= self.scenario.weight_limit()
+                && state.sizes[i] <= self.scenario.size_limit()
+            {
+                state.costs[i]
+            } else {
+                N::zero()
+            };
+        }
+    }
+
+    /// Individual replaced by a child, never one replaced earlier in the same step
+    fn victim(
+        &self,
+        replacement: Replacement,
+        offspring: &DMatrix<N>,
+        j: usize,
+        child: &Child<N>,
+        replaced: &[usize],
+        rng: &mut ThreadRng,
+    ) -> usize {
+        let state = self.steady_state.as_ref().unwrap();
+        let candidates = (0..self.config.population_size).filter(|i| !replaced.contains(i));
+
+        match replacement {
+            Replacement::Worst => candidates
+                .min_by(|a, b| {
+                    self.scores[*a]
+                        .partial_cmp(&self.scores[*b])
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                })
+                .unwrap(),
+            Replacement::Random => loop {
+                let i = rng.gen_range(0, self.config.population_size);
+                if !replaced.contains(&i) {
+                    break i;
+                }
+            },
+            Replacement::Oldest => candidates.min_by_key(|i| state.births[*i]).unwrap(),
+            Replacement::SimilarParent => {
+                let genes = offspring.row(j);
+                let distance = |i: usize| {
+                    let parent = self.population.row(i);
+                    genes
+                        .iter()
+                        .zip(parent.iter())
+                        .filter(|(a, b)| a != b)
+                        .count()
+                };
+
+                let mut parents = child.parents;
+                parents.sort_by_key(|i| distance(*i));
+                match parents.iter().find(|i| !replaced.contains(i)) {
+                    Some(i) => *i,
+                    // both parents were replaced by siblings of the child
+                    None => self.victim(Replacement::Worst, offspring, j, child, replaced, rng),
+                }
+            }
+        }
+    }
+
+    /// Updates totals of individuals written into `rows` from outside of the steady-state mode
+    pub(super) fn replaced(&mut self, rows: &[usize]) {
+        let evaluations = self.evaluations;
+        let state = match self.steady_state.as_mut() {
+            Some(state) => state,
+            None => return,
+        };
+
+        for i in rows.iter().cloned() {
+            let genes = self.population.row(i);
+            state.weights[i] = (&genes * &self.scenario.weights)[0];
+            state.sizes[i] = (&genes * &self.scenario.sizes)[0];
+            state.costs[i] = (&genes * &self.scenario.costs)[0];
+            state.births[i] = evaluations;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::population::config::Config;
+    use crate::population::{evaluate_population, test_config, test_scenario};
+    use crate::scenario::{Labels, Scenario};
+    use std::sync::Arc;
+
+    fn config(replacement: Replacement) -> Config {
+        Config {
+            steady_state: Some(SteadyStateConfig {
+                children: 3,
+                replacement,
+            }),
+            ..test_config(10)
+        }
+    }
+
+    fn population(replacement: Replacement) -> Population<i64> {
+        let scenario = Arc::new(test_scenario(12, 20, 5));
+        Population::new(scenario, config(replacement)).unwrap()
+    }
+
+    #[test]
+    fn incremental_scores_match_full_evaluation() {
+        for replacement in Replacement::ALL.iter() {
+            let mut population = population(*replacement);
+            let mut rng = thread_rng();
+
+            for _ in 0..50 {
+                population.step(&mut rng);
+            }
+            // 10 initial individuals and 3 children in each step
+            assert_eq!(population.evaluations(), 160);
+
+            let scores = evaluate_population(&population.population, &population.scenario);
+            assert_eq!(&scores, population.scores(), "{}", replacement);
+        }
+    }
+
+    #[test]
+    fn incremental_f64_scores_match_full_evaluation() {
+        // sums of tenths are not exact, 5 objects only just fit
+        let scenario = Arc::new(Scenario {
+            weights: (1..=12).map(|i| 0.1 * i as f64).collect::<Vec<_>>().into(),
+            sizes: vec![0.1; 12].into(),
+            costs: (1..=12)
+                .rev()
+                .map(|i| 0.3 * i as f64)
+                .collect::<Vec<_>>()
+                .into(),
+            number_of_objects: 12,
+            max_weight: 2.0,
+            max_size: 0.5,
+            tolerance: 0.0,
+            labels: Labels::default(),
+        });
+
+        for replacement in Replacement::ALL.iter() {
+            let mut population = Population::new(scenario.clone(), config(*replacement)).unwrap();
+            let mut rng = thread_rng();
+
+            for step in 1..=2 * RESCORE_INTERVAL {
+                population.step(&mut rng);
+
+                let scores = evaluate_population(&population.population, &population.scenario);
+                if step % RESCORE_INTERVAL == 0 {
+                    assert_eq!(&scores, population.scores(), "{}", replacement);
+                }
+                // a wrong decision whether a child fits changes its score by a whole cost
+                for (expected, actual) in scores.iter().zip(population.scores().iter()) {
+                    assert!((expected - actual).abs() < 1e-9, "{}", replacement);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn worst_replacement_keeps_the_best_individual() {
+        let mut population = population(Replacement::Worst);
+        let mut rng = thread_rng();
+
+        for _ in 0..20 {
+            let best = population.scores().max();
+            population.step(&mut rng);
+            assert!(population.scores().max() >= best);
+        }
+    }
+
+    #[test]
+    fn oldest_individuals_are_replaced_first() {
+        let mut population = population(Replacement::Oldest);
+        let mut rng = thread_rng();
+
+        // the first 3 steps replace 9 of the 10 initial individuals
+        for _ in 0..3 {
+            population.step(&mut rng);
+        }
+        let births = &population.steady_state.as_ref().unwrap().births;
+        assert_eq!(births.iter().filter(|x| **x == 0).count(), 1);
+    }
+}
@@ -0,0 +1,181 @@
+use crate::number::Number;
+use crate::population::Population;
+use na::{DMatrix, DVector};
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+/// Individuals competing to survive into the next generation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SurvivorSelection {
+    /// (μ+λ): parents and their children
+    Plus,
+    /// (μ,λ): children only, apart from elite parents
+    Comma,
+}
+
+impl_policy!(SurvivorSelection, UnknownSurvivorSelection, "survivor selection", [
+    "plus" => Plus,
+    "comma" => Comma
+]);
+
+/// Options of breeding λ children out of μ parents, where μ is the population size
+#[derive(Debug, Clone, Copy)]
+pub struct SurvivorConfig {
+    /// Number of children λ bred every generation
+    pub offspring: usize,
+    pub selection: SurvivorSelection,
+    /// Number of the best parents which survive no matter how good their children are
+    ///
+    /// Plus-selection never loses the best individuals so it is only used by comma-selection.
+    pub elitism: usize,
+}
+
+impl SurvivorConfig {
+    /// Checks that there are enough candidates for `population_size` survivors
+    pub fn validate(&self, population_size: usize) -> Result<(), String> {
+        if self.offspring == 0 {
+            return Err("There has to be at least one child in every generation".to_string());
+        }
+        if self.elitism > population_size {
+            return Err(format!(
+                "Elitism {} is larger then population size {}",
+                self.elitism, population_size
+            ));
+        }
+        if self.selection == SurvivorSelection::Comma
+            && self.offspring + self.elitism < population_size
+        {
+            return Err(format!(
+                "Comma-selection needs at least {} children to keep population size {} with elitism {}",
+                population_size - self.elitism,
+                population_size,
+                self.elitism
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Individual which may survive, either a parent or a child
+#[derive(Debug, Clone, Copy)]
+enum Candidate {
+    Parent(usize),
+    Child(usize),
+}
+
+impl<N: Number> Population<N> {
+    /// Replaces the population with the best individuals among the candidates
+    /// chosen by the survivor selection
+    ///
+    /// Children are preferred over parents with the same score so the
+    /// population keeps moving on plateaus.
+    pub(super) fn select_survivors(
+        &mut self,
+        survivors: SurvivorConfig,
+        offspring_scores: DVector<N>,
+    ) {
+        let score = |candidate: &Candidate| match *candidate {
+            Candidate::Parent(i) => self.scores[i],
+            Candidate::Child(i) => offspring_scores[i],
+        };
+
+        let children = (0..self.next_population.nrows()).map(Candidate::Child);
+        let mut survivors = match survivors.selection {
+            SurvivorSelection::Plus => {
+                let parents = (0..self.config.population_size).map(Candidate::Parent);
+                sorted(children.chain(parents).collect(), score)
+            }
+            // elite parents survive first and children compete for the remaining places
+            SurvivorSelection::Comma => self
+                .ranking()
+                .into_iter()
+                .take(survivors.elitism)
+                .map(Candidate::Parent)
+                .chain(sorted(children.collect(), score))
+                .collect(),
+        };
+        survivors.truncate(self.config.population_size);
+
+        let rows = survivors
+            .iter()
+            .map(|candidate| match *candidate {
+                Candidate::Parent(i) => self.population.row(i),
+                Candidate::Child(i) => self.next_population.row(i),
+            })
+            .collect::<Vec<_>>();
+        let population = DMatrix::from_rows(&rows);
+        let scores = DVector::from_iterator(survivors.len(), survivors.iter().map(score));
+
+        self.population = population;
+        self.scores = scores;
+    }
+}
+
+/// Candidates from the best to the worst one, keeping the order of ties
+fn sorted<N: PartialOrd, F: Fn(&Candidate) -> N>(
+    mut candidates: Vec<Candidate>,
+    score: F,
+) -> Vec<Candidate> {
+    candidates.sort_by(|a, b| score(b).partial_cmp(&score(a)).unwrap_or(Ordering::Equal));
+    candidates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::population::config::Config;
+    use crate::population::{test_config, test_scenario};
+    use std::sync::Arc;
+
+    fn population(
+        selection: SurvivorSelection,
+        offspring: usize,
+        elitism: usize,
+    ) -> Population<i64> {
+        let config = Config {
+            mutation_probability: 0.2,
+            survivors: Some(SurvivorConfig {
+                offspring,
+                selection,
+                elitism,
+            }),
+            ..test_config(6)
+        };
+        Population::new(Arc::new(test_scenario(12, 20, 5)), config).unwrap()
+    }
+
+    #[test]
+    fn plus_selection_never_loses_the_best() {
+        let mut population = population(SurvivorSelection::Plus, 3, 0);
+
+        for _ in 0..30 {
+            let best = population.scores().max();
+            population.evolve();
+            assert_eq!(population.scores().nrows(), 6);
+            assert!(population.scores().max() >= best);
+        }
+        // 6 initial individuals and 3 children in each generation
+        assert_eq!(population.evaluations(), 96);
+    }
+
+    #[test]
+    fn comma_selection_keeps_elite_parents() {
+        let mut population = population(SurvivorSelection::Comma, 10, 1);
+
+        for _ in 0..30 {
+            let best = population.scores().max();
+            population.evolve();
+            assert!(population.scores().max() >= best);
+        }
+
+        assert!(SurvivorConfig {
+            offspring: 4,
+            selection: SurvivorSelection::Comma,
+            elitism: 1,
+        }
+        .validate(6)
+        .is_err());
+    }
+}
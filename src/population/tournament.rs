@@ -0,0 +1,291 @@
+use na::{DVector, Scalar};
+use rand::distributions::{Distribution, Uniform};
+use rand::Rng;
+
+/// Selects individual using tournament algorithm
+/// Returns selected individual's index
+///
+/// Only `tournament_size` contestants are drawn and compared so the cost
+/// of a single pick is O(tournament_size) regardless of the population size.
+/// When several contestants share the best score the winner is chosen
+/// uniformly at random among them.
+pub fn tournament<N: Scalar + PartialOrd + Copy, R: Rng + ?Sized>(
+    scores: &DVector<N>,
+    tournament_size: usize,
+    with_replacement: bool,
+    rng: &mut R,
+) -> usize {
+    if with_replacement {
+        tournament_with_replacement(scores, tournament_size, rng)
+    } else {
+        tournament_without_replacement(scores, tournament_size, rng)
+    }
+}
+
+/// Tournament in which the same individual may be drawn more then once.
+pub fn tournament_with_replacement<N: Scalar + PartialOrd + Copy, R: Rng + ?Sized>(
+    scores: &DVector<N>,
+    tournament_size: usize,
+    rng: &mut R,
+) -> usize {
+    debug_assert_ne!(tournament_size, 0);
+
+    let scores = scores.as_slice();
+    let slots = Uniform::from(0..scores.len());
+    let mut best = Contestant::new(slots.sample(rng), scores);
+
+    for _ in 1..tournament_size {
+        best.challenge(slots.sample(rng), scores, rng);
+    }
+
+    best.idx
+}
+
+/// Tournament in which every contestant is a distinct individual.
+pub fn tournament_without_replacement<N: Scalar + PartialOrd + Copy, R: Rng + ?Sized>(
+    scores: &DVector<N>,
+    tournament_size: usize,
+    rng: &mut R,
+) -> usize {
+    let scores = scores.as_slice();
+    let population_size = scores.len();
+
+    debug_assert_ne!(tournament_size, 0);
+    debug_assert!(tournament_size <= population_size);
+
+    // Robert Floyd's sampling algorithm.
+    //
+    // For each `j` in [n - k, n) it draws `t` from [0, j]. If `t` has not been
+    // drawn yet it becomes a contestant, otherwise `j` does (it could not have
+    // been drawn before because all previous draws were smaller then `j`).
+    // The result is a uniformly distributed k-subset and only k indices
+    // are ever stored.
+    let mut drawn = DrawnSet::with_capacity(tournament_size);
+    let mut best: Option<Contestant<N>> = None;
+
+    for j in (population_size - tournament_size)..population_size {
+        let t = below(j + 1, rng);
+        let idx = if drawn.insert(t) {
+            t
+        } else {
+            drawn.insert(j);
+            j
+        };
+
+        match best.as_mut() {
+            Some(best) => best.challenge(idx, scores, rng),
+            None => best = Some(Contestant::new(idx, scores)),
+        }
+    }
+
+    best.unwrap().idx
+}
+
+/// Returns uniformly distributed number in range [0, bound)
+///
+/// Daniel Lemire's multiply-shift method. It only needs a division when
+/// the first draw falls into the biased zone, unlike `Rng::gen_range`
+/// which has to compute the rejection zone every time it is called
+/// with a new range.
+fn below<R: Rng + ?Sized>(bound: usize, rng: &mut R) -> usize {
+    let bound = bound as u64;
+    let mut m = u128::from(rng.next_u64()) * u128::from(bound);
+
+    if (m as u64) < bound {
+        let threshold = bound.wrapping_neg() % bound;
+        while (m as u64) < threshold {
+            m = u128::from(rng.next_u64()) * u128::from(bound);
+        }
+    }
+
+    (m >> 64) as usize
+}
+
+/// Set of already drawn indices.
+///
+/// Open addressing hash set with linear probing. It never grows above
+/// the declared capacity so it can be allocated once per tournament.
+/// Indices are already uniformly distributed so a single multiplication
+/// (Fibonacci hashing) is enough to spread them over the slots.
+struct DrawnSet {
+    slots: Vec<usize>,
+    shift: u32,
+}
+
+impl DrawnSet {
+    const EMPTY: usize = usize::MAX;
+
+    fn with_capacity(capacity: usize) -> DrawnSet {
+        // keep the load factor at most 1/2
+        let len = (2 * capacity).next_power_of_two().max(2);
+
+        DrawnSet {
+            slots: vec![DrawnSet::EMPTY; len],
+            shift: 64 - len.trailing_zeros(),
+        }
+    }
+
+    /// Returns `true` if `idx` was not present in the set.
+    fn insert(&mut self, idx: usize) -> bool {
+        let mask = self.slots.len() - 1;
+        let mut slot = ((idx as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15) >> self.shift) as usize;
+
+        loop {
+            match self.slots[slot] {
+                DrawnSet::EMPTY => {
+                    self.slots[slot] = idx;
+                    return true;
+                }
+                drawn if drawn == idx => return false,
+                _ => slot = (slot + 1) & mask,
+            }
+        }
+    }
+}
+
+/// The best contestant seen so far in a tournament.
+struct Contestant<N> {
+    idx: usize,
+    score: N,
+    /// Number of contestants seen so far with the same score
+    ties: usize,
+}
+
+impl<N: PartialOrd + Copy> Contestant<N> {
+    fn new(idx: usize, scores: &[N]) -> Contestant<N> {
+        Contestant {
+            idx,
+            score: scores[idx],
+            ties: 1,
+        }
+    }
+
+    /// Replaces the current best with `idx` if it has better score.
+    ///
+    /// Ties are resolved with reservoir sampling: the n-th contestant with
+    /// the best score replaces the current one with probability 1/n, so each
+    /// of them has the same chance to win no matter in which order they
+    /// were drawn.
+    fn challenge<R: Rng + ?Sized>(&mut self, idx: usize, scores: &[N], rng: &mut R) {
+        let score = scores[idx];
+
+        if score > self.score {
+            self.idx = idx;
+            self.score = score;
+            self.ties = 1;
+        } else if score == self.score {
+            self.ties += 1;
+            if below(self.ties, rng) == 0 {
+                self.idx = idx;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{thread_rng, SeedableRng};
+
+    /// Pearson's chi-squared statistic of `counts` against the uniform distribution
+    fn chi_squared(counts: &[usize]) -> f64 {
+        let total = counts.iter().sum::<usize>() as f64;
+        let expected = total / counts.len() as f64;
+
+        counts
+            .iter()
+            .map(|observed| (*observed as f64 - expected).powi(2) / expected)
+            .sum()
+    }
+
+    /// Runs many tournaments on a flat fitness landscape and checks
+    /// if every individual was selected equally often.
+    fn assert_uniform_selection(tournament_size: usize, with_replacement: bool) {
+        // 20 individuals gives 19 degrees of freedom. The critical value
+        // for p = 0.001 is 43.82. The rng is seeded so the test is repeatable.
+        const POPULATION_SIZE: usize = 20;
+        const CRITICAL_VALUE: f64 = 43.82;
+
+        let scores = DVector::from_vec(vec![0; POPULATION_SIZE]);
+        let mut rng = StdRng::seed_from_u64(tournament_size as u64);
+        let mut counts = [0; POPULATION_SIZE];
+
+        for _ in 0..20_000 {
+            counts[tournament(&scores, tournament_size, with_replacement, &mut rng)] += 1;
+        }
+
+        let statistic = chi_squared(&counts);
+        assert!(
+            statistic < CRITICAL_VALUE,
+            "selection is not uniform (chi^2 = {}): {:?}",
+            statistic,
+            counts
+        );
+    }
+
+    #[test]
+    fn flat_landscape_selection_without_replacement_is_uniform() {
+        for tournament_size in [1, 2, 5, 19, 20].iter().cloned() {
+            assert_uniform_selection(tournament_size, false);
+        }
+    }
+
+    #[test]
+    fn flat_landscape_selection_with_replacement_is_uniform() {
+        for tournament_size in [1, 2, 5, 20, 40].iter().cloned() {
+            assert_uniform_selection(tournament_size, true);
+        }
+    }
+
+    #[test]
+    fn ties_are_broken_only_among_contestants() {
+        // only individuals 1 and 3 share the best score so a full
+        // tournament has to pick one of them, each about half of the time
+        let scores = DVector::from_vec(vec![0, 5, 0, 5, 0]);
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut counts = [0; 5];
+
+        for _ in 0..10_000 {
+            counts[tournament(&scores, scores.nrows(), false, &mut rng)] += 1;
+        }
+
+        assert_eq!(counts[0] + counts[2] + counts[4], 0);
+        assert!(counts[1] > 4_500 && counts[3] > 4_500, "{:?}", counts);
+    }
+
+    #[test]
+    fn full_tournament_without_replacement_selects_the_best() {
+        let scores = DVector::from_vec(vec![3, 7, 1, 9, 4, 2]);
+        let mut rng = thread_rng();
+
+        for _ in 0..100 {
+            assert_eq!(tournament(&scores, scores.nrows(), false, &mut rng), 3);
+        }
+    }
+
+    #[test]
+    fn single_contestant_tournament_covers_whole_population() {
+        let scores = DVector::from_vec(vec![0; 5]);
+        let mut rng = thread_rng();
+        let mut seen = [false; 5];
+
+        for _ in 0..1000 {
+            seen[tournament(&scores, 1, false, &mut rng)] = true;
+        }
+
+        assert!(seen.iter().all(|x| *x));
+    }
+
+    #[test]
+    fn tournament_with_replacement_stays_in_bounds() {
+        let scores = DVector::from_vec(vec![5, 1, 8]);
+        let mut rng = thread_rng();
+
+        for _ in 0..100 {
+            // more contestants then individuals is fine when drawing with replacement
+            let idx = tournament(&scores, 10, true, &mut rng);
+            assert!(idx < scores.nrows());
+        }
+    }
+}
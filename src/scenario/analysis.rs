@@ -0,0 +1,543 @@
+use crate::number::Number;
+use crate::scenario::Scenario;
+use serde::Serialize;
+use std::cmp::Ordering;
+use std::fmt::{self, Display, Formatter};
+
+/// Summary of a scenario
+///
+/// All values are converted to `f64` so scenarios of every number type
+/// produce the same report. Object indices are 0-based, in file order.
+#[derive(Debug, Serialize)]
+pub struct Analysis {
+    pub number_of_objects: usize,
+    pub max_weight: f64,
+    pub max_size: f64,
+    pub weight: Statistics,
+    pub size: Statistics,
+    pub cost: Statistics,
+    /// Pearson correlation between costs and weights
+    pub cost_weight_correlation: f64,
+    /// Pearson correlation between costs and sizes
+    pub cost_size_correlation: f64,
+    /// Max weight divided by the total weight
+    pub weight_tightness: f64,
+    /// Max size divided by the total size
+    pub size_tightness: f64,
+    /// Objects heavier or larger then the limits
+    pub never_fit: Vec<usize>,
+    /// Objects for which another one is at most as heavy and large and at least as valuable
+    pub dominated: Vec<usize>,
+    /// Optimum of the LP relaxation, no solution can be worth more
+    pub lp_bound: f64,
+    /// Cost of the greedy solution, the optimum is worth at least that much
+    pub greedy_bound: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Statistics {
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub std_dev: f64,
+    pub total: f64,
+}
+
+impl Analysis {
+    pub fn new<N: Number>(scenario: &Scenario<N>) -> Analysis {
+        let objects = Objects::new(scenario);
+        let weight = Statistics::new(&objects.weights);
+        let size = Statistics::new(&objects.sizes);
+
+        let never_fit = (0..objects.len())
+            .filter(|i| {
+                objects.weights[*i] > objects.max_weight || objects.sizes[*i] > objects.max_size
+            })
+            .collect();
+
+        Analysis {
+            number_of_objects: scenario.number_of_objects,
+            max_weight: scenario.max_weight.to_f64(),
+            max_size: scenario.max_size.to_f64(),
+            cost_weight_correlation: correlation(&objects.costs, &objects.weights),
+            cost_size_correlation: correlation(&objects.costs, &objects.sizes),
+            weight_tightness: scenario.max_weight.to_f64() / weight.total,
+            size_tightness: scenario.max_size.to_f64() / size.total,
+            weight,
+            size,
+            cost: Statistics::new(&objects.costs),
+            never_fit,
+            dominated: objects.dominated(),
+            lp_bound: objects.lp_relaxation().bound,
+            greedy_bound: objects.greedy(),
+        }
+    }
+}
+
+impl Statistics {
+    fn new(values: &[f64]) -> Statistics {
+        let len = values.len() as f64;
+        let total = values.iter().sum::<f64>();
+        let mean = total / len;
+        let variance = values.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / len;
+
+        Statistics {
+            min: values.iter().cloned().fold(f64::INFINITY, f64::min),
+            max: values.iter().cloned().fold(f64::NEG_INFINITY, f64::max),
+            mean,
+            std_dev: variance.sqrt(),
+            total,
+        }
+    }
+}
+
+fn correlation(xs: &[f64], ys: &[f64]) -> f64 {
+    let len = xs.len() as f64;
+    let mean_x = xs.iter().sum::<f64>() / len;
+    let mean_y = ys.iter().sum::<f64>() / len;
+
+    let mut covariance = 0.0;
+    let mut variance_x = 0.0;
+    let mut variance_y = 0.0;
+    for (x, y) in xs.iter().zip(ys) {
+        covariance += (x - mean_x) * (y - mean_y);
+        variance_x += (x - mean_x).powi(2);
+        variance_y += (y - mean_y).powi(2);
+    }
+
+    // NaN when either of the columns is constant, there is nothing to correlate
+    covariance / (variance_x * variance_y).sqrt()
+}
+
+/// Scenario converted to `f64` with the feasibility tolerance applied to the limits
+pub(crate) struct Objects {
+    pub weights: Vec<f64>,
+    pub sizes: Vec<f64>,
+    pub costs: Vec<f64>,
+    pub max_weight: f64,
+    pub max_size: f64,
+}
+
+/// Feasible solution of the dual of the LP relaxation
+pub(crate) struct LpRelaxation {
+    /// Upper bound of the cost of any solution
+    pub bound: f64,
+    /// Price of a unit of weight
+    pub weight_price: f64,
+    /// Price of a unit of size
+    pub size_price: f64,
+}
+
+impl Objects {
+    pub fn new<N: Number>(scenario: &Scenario<N>) -> Objects {
+        let to_vec = |values: &[N]| values.iter().map(|x| x.to_f64()).collect();
+
+        Objects {
+            weights: to_vec(&scenario.weights),
+            sizes: to_vec(&scenario.sizes),
+            costs: to_vec(&scenario.costs),
+            max_weight: scenario.weight_limit().to_f64(),
+            max_size: scenario.size_limit().to_f64(),
+        }
+    }
+
+    /// Objects with given indices and the same limits
+    pub fn subset(&self, indices: &[usize]) -> Objects {
+        let select = |values: &[f64]| indices.iter().map(|i| values[*i]).collect();
+
+        Objects {
+            weights: select(&self.weights),
+            sizes: select(&self.sizes),
+            costs: select(&self.costs),
+            max_weight: self.max_weight,
+            max_size: self.max_size,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.costs.len()
+    }
+
+    /// Returns indices of dominated objects in ascending order
+    ///
+    /// Object is dominated if another, different one is at most as heavy, at most
+    /// as large and at least as valuable. Identical objects do not dominate each
+    /// other. Runs in O(n log n): objects are sorted so that all dominators of an
+    /// object come before it and a Fenwick tree answers "the most valuable object
+    /// seen so far with size at most s".
+    pub fn dominated(&self) -> Vec<usize> {
+        let mut order = (0..self.len()).collect::<Vec<_>>();
+        order.sort_by(|a, b| {
+            cmp(self.weights[*a], self.weights[*b])
+                .then(cmp(self.sizes[*a], self.sizes[*b]))
+                .then(cmp(self.costs[*b], self.costs[*a]))
+        });
+
+        let mut sizes = self.sizes.clone();
+        sizes.sort_by(|a, b| cmp(*a, *b));
+        sizes.dedup();
+
+        let mut best_cost = MaxFenwickTree::new(sizes.len());
+        let mut dominated = Vec::new();
+
+        let mut group_start = 0;
+        while group_start < order.len() {
+            // identical objects are checked and inserted together
+            let first = order[group_start];
+            let group_end = order[group_start..]
+                .iter()
+                .position(|i| !self.same(*i, first))
+                .map_or(order.len(), |x| group_start + x);
+
+            let size = sizes.partition_point(|x| *x < self.sizes[first]);
+            if best_cost.max(size) >= self.costs[first] {
+                dominated.extend_from_slice(&order[group_start..group_end]);
+            }
+            best_cost.insert(size, self.costs[first]);
+
+            group_start = group_end;
+        }
+
+        dominated.sort_unstable();
+        dominated
+    }
+
+    /// Whether object `a` dominates object `b`
+    pub fn dominates(&self, a: usize, b: usize) -> bool {
+        !self.same(a, b)
+            && self.weights[a] <= self.weights[b]
+            && self.sizes[a] <= self.sizes[b]
+            && self.costs[a] >= self.costs[b]
+    }
+
+    fn same(&self, a: usize, b: usize) -> bool {
+        self.weights[a] == self.weights[b]
+            && self.sizes[a] == self.sizes[b]
+            && self.costs[a] == self.costs[b]
+    }
+
+    /// Solves the dual of the LP relaxation
+    ///
+    /// The dual is `min W * u + S * v + sum(max(0, c - u * w - v * s))` over
+    /// `u, v >= 0`. It is convex so `u` is found with a golden-section search
+    /// and for a fixed `u` the optimal `v` is found exactly. Every pair of
+    /// prices gives a valid upper bound so the result is safe to use even if
+    /// the search did not fully converge.
+    pub fn lp_relaxation(&self) -> LpRelaxation {
+        let max_price = (0..self.len())
+            .filter(|i| self.weights[*i] > 0.0)
+            .map(|i| self.costs[i] / self.weights[i])
+            .fold(0.0, f64::max);
+
+        let golden = (5f64.sqrt() - 1.0) / 2.0;
+        let (mut low, mut high) = (0.0, max_price);
+        let mut a = high - golden * (high - low);
+        let mut b = low + golden * (high - low);
+        let (mut fa, mut fb) = (self.dual(a), self.dual(b));
+
+        for _ in 0..100 {
+            if fa.bound <= fb.bound {
+                high = b;
+                b = a;
+                fb = fa;
+                a = high - golden * (high - low);
+                fa = self.dual(a);
+            } else {
+                low = a;
+                a = b;
+                fa = fb;
+                b = low + golden * (high - low);
+                fb = self.dual(b);
+            }
+        }
+
+        // the search never evaluates the boundary where the optimum often is
+        let candidates = vec![fa, fb, self.dual(0.0), self.dual(max_price)];
+        candidates
+            .into_iter()
+            .min_by(|a, b| cmp(a.bound, b.bound))
+            .unwrap()
+    }
+
+    /// Profit of object `i` over the value of the resources it uses
+    pub fn reduced_cost(&self, lp: &LpRelaxation, i: usize) -> f64 {
+        self.costs[i] - lp.weight_price * self.weights[i] - lp.size_price * self.sizes[i]
+    }
+
+    /// The dual objective with optimal size price for given weight price
+    fn dual(&self, weight_price: f64) -> LpRelaxation {
+        let mut constant = weight_price * self.max_weight;
+        let mut breakpoints = Vec::new();
+
+        for i in 0..self.len() {
+            let profit = self.costs[i] - weight_price * self.weights[i];
+            if self.sizes[i] > 0.0 {
+                if profit > 0.0 {
+                    breakpoints.push((profit / self.sizes[i], i));
+                }
+            } else {
+                constant += profit.max(0.0);
+            }
+        }
+
+        // objects with the highest profit per size are the last to leave
+        // when the price goes up, the price stops where they no longer fit
+        breakpoints.sort_by(|a, b| cmp(b.0, a.0));
+        let mut size = 0.0;
+        let mut size_price = 0.0;
+        for (breakpoint, i) in breakpoints.iter() {
+            size += self.sizes[*i];
+            if size > self.max_size {
+                size_price = *breakpoint;
+                break;
+            }
+        }
+
+        let bound = constant
+            + size_price * self.max_size
+            + breakpoints
+                .iter()
+                .map(|(_, i)| {
+                    (self.costs[*i] - weight_price * self.weights[*i] - size_price * self.sizes[*i])
+                        .max(0.0)
+                })
+                .sum::<f64>();
+
+        LpRelaxation {
+            bound,
+            weight_price,
+            size_price,
+        }
+    }
+
+    /// Cost of the solution built by taking the most valuable objects first
+    ///
+    /// Value is the cost divided by the share of the limits the object uses.
+    pub fn greedy(&self) -> f64 {
+        let share = |value: f64, limit: f64| match value {
+            _ if value == 0.0 => 0.0,
+            _ if limit > 0.0 => value / limit,
+            _ => f64::INFINITY,
+        };
+        let efficiency = |i: usize| {
+            self.costs[i]
+                / (share(self.weights[i], self.max_weight) + share(self.sizes[i], self.max_size))
+        };
+
+        let mut order = (0..self.len()).collect::<Vec<_>>();
+        order.sort_by(|a, b| cmp(efficiency(*b), efficiency(*a)));
+
+        let mut weight = 0.0;
+        let mut size = 0.0;
+        let mut cost = 0.0;
+        for i in order {
+            if weight + self.weights[i] <= self.max_weight && size + self.sizes[i] <= self.max_size
+            {
+                weight += self.weights[i];
+                size += self.sizes[i];
+                cost += self.costs[i];
+            }
+        }
+
+        cost
+    }
+}
+
+/// Scenarios never contain NaN, loading rejects them
+fn cmp(a: f64, b: f64) -> Ordering {
+    a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+}
+
+/// Fenwick tree of prefix maximums
+struct MaxFenwickTree {
+    tree: Vec<f64>,
+}
+
+impl MaxFenwickTree {
+    fn new(len: usize) -> MaxFenwickTree {
+        MaxFenwickTree {
+            tree: vec![f64::NEG_INFINITY; len + 1],
+        }
+    }
+
+    fn insert(&mut self, idx: usize, value: f64) {
+        let mut i = idx + 1;
+        while i < self.tree.len() {
+            self.tree[i] = self.tree[i].max(value);
+            i += i & i.wrapping_neg();
+        }
+    }
+
+    /// The largest value inserted at index `idx` or lower
+    fn max(&self, idx: usize) -> f64 {
+        let mut i = idx + 1;
+        let mut max = f64::NEG_INFINITY;
+        while i > 0 {
+            max = max.max(self.tree[i]);
+            i -= i & i.wrapping_neg();
+        }
+        max
+    }
+}
+
+impl Display for Analysis {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Objects:     {}", self.number_of_objects)?;
+        writeln!(
+            f,
+            "Max weight:  {} (tightness {:.3})",
+            self.max_weight, self.weight_tightness
+        )?;
+        writeln!(
+            f,
+            "Max size:    {} (tightness {:.3})",
+            self.max_size, self.size_tightness
+        )?;
+        writeln!(f)?;
+        writeln!(
+            f,
+            "{:<8}{:>14}{:>14}{:>14}{:>14}{:>16}",
+            "", "min", "max", "mean", "std dev", "total"
+        )?;
+        for (name, stats) in [
+            ("weight", &self.weight),
+            ("size", &self.size),
+            ("cost", &self.cost),
+        ]
+        .iter()
+        {
+            writeln!(
+                f,
+                "{:<8}{:>14}{:>14}{:>14.3}{:>14.3}{:>16}",
+                name, stats.min, stats.max, stats.mean, stats.std_dev, stats.total
+            )?;
+        }
+        writeln!(f)?;
+        writeln!(
+            f,
+            "Correlation of cost with weight: {:.3}",
+            self.cost_weight_correlation
+        )?;
+        writeln!(
+            f,
+            "Correlation of cost with size:   {:.3}",
+            self.cost_size_correlation
+        )?;
+        writeln!(f)?;
+        writeln!(f, "Objects which never fit: {}", Indices(&self.never_fit))?;
+        writeln!(f, "Dominated objects:       {}", Indices(&self.dominated))?;
+        writeln!(f)?;
+        writeln!(f, "LP relaxation upper bound: {:.3}", self.lp_bound)?;
+        write!(f, "Greedy lower bound:        {}", self.greedy_bound)
+    }
+}
+
+/// Count of indices followed by the first few of them
+struct Indices<'a>(&'a [usize]);
+
+impl Display for Indices<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        const SHOWN: usize = 10;
+
+        write!(f, "{}", self.0.len())?;
+        if !self.0.is_empty() {
+            let shown = self.0[..self.0.len().min(SHOWN)]
+                .iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            let more = if self.0.len() > SHOWN { ", ..." } else { "" };
+            write!(f, " [{}{}]", shown, more)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scenario::Labels;
+
+    fn scenario(objects: &[(f64, f64, f64)], max_weight: f64, max_size: f64) -> Scenario<f64> {
+        Scenario {
+            weights: objects.iter().map(|x| x.0).collect::<Vec<_>>().into(),
+            sizes: objects.iter().map(|x| x.1).collect::<Vec<_>>().into(),
+            costs: objects.iter().map(|x| x.2).collect::<Vec<_>>().into(),
+            number_of_objects: objects.len(),
+            max_weight,
+            max_size,
+            tolerance: 0.0,
+            labels: Labels::default(),
+        }
+    }
+
+    /// Checks every pair of objects
+    fn dominated_naive(objects: &Objects) -> Vec<usize> {
+        (0..objects.len())
+            .filter(|i| (0..objects.len()).any(|j| objects.dominates(j, *i)))
+            .collect()
+    }
+
+    #[test]
+    fn dominated_objects_match_naive_check() {
+        use rand::rngs::StdRng;
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..50 {
+            // small range so there are plenty of ties and identical objects
+            let objects = (0..40)
+                .map(|_| {
+                    (
+                        rng.gen_range(0, 5) as f64,
+                        rng.gen_range(0, 5) as f64,
+                        rng.gen_range(0, 5) as f64,
+                    )
+                })
+                .collect::<Vec<_>>();
+            let objects = Objects::new(&scenario(&objects, 10.0, 10.0));
+
+            assert_eq!(objects.dominated(), dominated_naive(&objects));
+        }
+    }
+
+    #[test]
+    fn lp_bound_of_a_single_constraint_is_the_fractional_knapsack() {
+        // sizes never bind, the fractional optimum takes objects 0 and 1
+        // and 2/3 of object 2: 60 + 100 + 80
+        let scenario = scenario(
+            &[(10.0, 1.0, 60.0), (20.0, 1.0, 100.0), (30.0, 1.0, 120.0)],
+            50.0,
+            10.0,
+        );
+        let bound = Objects::new(&scenario).lp_relaxation().bound;
+
+        assert!((bound - 240.0).abs() < 1e-6, "{}", bound);
+    }
+
+    #[test]
+    fn lp_bound_of_two_constraints_is_exact() {
+        // the optimum takes x0 = x1 = 2/3 using both resources completely
+        // (heavy object 0 and large object 1 fill each other's spare room)
+        let scenario = scenario(&[(2.0, 1.0, 3.0), (1.0, 2.0, 3.0)], 2.0, 2.0);
+        let analysis = Analysis::new(&scenario);
+
+        assert!(
+            (analysis.lp_bound - 4.0).abs() < 1e-6,
+            "{}",
+            analysis.lp_bound
+        );
+        assert_eq!(analysis.greedy_bound, 3.0);
+    }
+
+    #[test]
+    fn objects_which_never_fit_are_found() {
+        let scenario = scenario(
+            &[(1.0, 1.0, 1.0), (6.0, 1.0, 1.0), (1.0, 6.0, 1.0)],
+            5.0,
+            5.0,
+        );
+
+        assert_eq!(Analysis::new(&scenario).never_fit, vec![1, 2]);
+    }
+}
@@ -0,0 +1,381 @@
+use crate::number::{Number, NumberType};
+use crate::scenario::column::Column;
+use crate::scenario::error::ScenarioErrorKind::{
+    ChecksumMismatch, InvalidBinary, Io, NegativeValue, NumberTypeMismatch, SumOverflow,
+    TotalSizeToSmall, TotalWeightToSmall, UnsupportedVersion,
+};
+use crate::scenario::error::{ScenarioError, ScenarioErrorKind, ScenarioErrors};
+use crate::scenario::{header_overflow, is_non_negative, Labels, Scenario};
+use crc32fast::Hasher;
+use memmap2::Mmap;
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::mem::size_of;
+use std::sync::Arc;
+
+/// First bytes of every binary scenario
+pub(super) const MAGIC: &[u8; 8] = b"GENETSCN";
+const VERSION: u32 = 1;
+const HEADER_SIZE: usize = 64;
+/// Bytes of the header covered by the checksum, everything after the header is covered as well
+const CHECKSUMMED: usize = 48;
+
+// Header fields, all little-endian:
+//  0..8   MAGIC
+//  8..12  format version [u32]
+// 12      number type, index in `NumberType::ALL` [u8]
+// 16..24  number of objects [u64]
+// 24..32  max weight [N padded with zeros]
+// 32..40  max size [N padded with zeros]
+// 40..48  length of the labels [u64]
+// 48..52  CRC-32 checksum [u32]
+// Other bytes are reserved and have to be zero.
+
+/// Scenario file in the `ScenarioFormat::Binary` format mapped into memory
+///
+/// Columns are read straight from the mapping without copying them. Values
+/// are validated once when the file is opened, the same way text formats are.
+pub struct MappedScenario<N: Number> {
+    path: String,
+    map: Arc<Mmap>,
+    number_of_objects: usize,
+    max_weight: N,
+    max_size: N,
+    labels_length: usize,
+}
+
+impl<N: Number> MappedScenario<N> {
+    pub fn open(input_file: &str) -> Result<MappedScenario<N>, ScenarioErrors> {
+        let path = input_file;
+        let fail = |kind| ScenarioErrors::from(ScenarioError::new(path, kind));
+        let invalid = |reason: &str| {
+            fail(InvalidBinary {
+                reason: reason.to_string(),
+            })
+        };
+
+        if cfg!(target_endian = "big") {
+            return Err(invalid("binary scenarios are little-endian only"));
+        }
+
+        let file = File::open(path).map_err(|err| fail(Io(err)))?;
+        // Safety: the mapping is read only. As with every mapped file, it must not
+        // be truncated while in use, which scenario files never are.
+        let map = unsafe { Mmap::map(&file) }.map_err(|err| fail(Io(err)))?;
+
+        if map.len() < HEADER_SIZE || &map[..MAGIC.len()] != MAGIC {
+            return Err(invalid("missing header"));
+        }
+        let header = &map[..HEADER_SIZE];
+
+        let version = u32::from_le_bytes(bytes(header, 8));
+        if version != VERSION {
+            return Err(fail(UnsupportedVersion { version }));
+        }
+
+        let number_type = *NumberType::ALL
+            .get(header[12] as usize)
+            .ok_or_else(|| invalid("unknown number type"))?;
+        if number_type != N::TYPE {
+            return Err(fail(NumberTypeMismatch {
+                found: number_type.name(),
+                expected: N::NAME,
+            }));
+        }
+
+        if header[13..16].iter().chain(&header[52..]).any(|x| *x != 0) {
+            return Err(invalid("reserved header bytes are not zero"));
+        }
+
+        let length = |offset| usize::try_from(u64::from_le_bytes(bytes(header, offset))).ok();
+        let number_of_objects = length(16).ok_or_else(|| invalid("too many objects"))?;
+        let labels_length = length(40).ok_or_else(|| invalid("labels are too long"))?;
+
+        let file_length = number_of_objects
+            .checked_mul(3 * size_of::<N>())
+            .and_then(|x| x.checked_add(HEADER_SIZE))
+            .and_then(|x| x.checked_add(labels_length));
+        if file_length != Some(map.len()) {
+            return Err(invalid("file length does not match the header"));
+        }
+
+        let expected = u32::from_le_bytes(bytes(header, CHECKSUMMED));
+        let mut hasher = Hasher::new();
+        hasher.update(&header[..CHECKSUMMED]);
+        hasher.update(&map[HEADER_SIZE..]);
+        let actual = hasher.finalize();
+        if actual != expected {
+            return Err(fail(ChecksumMismatch { expected, actual }));
+        }
+
+        let scenario = MappedScenario {
+            path: path.to_string(),
+            max_weight: bytemuck::pod_read_unaligned(&header[24..24 + size_of::<N>()]),
+            max_size: bytemuck::pod_read_unaligned(&header[32..32 + size_of::<N>()]),
+            map: Arc::new(map),
+            number_of_objects,
+            labels_length,
+        };
+        scenario.validate().map_err(fail)?;
+
+        Ok(scenario)
+    }
+
+    /// Same checks as text formats do, the checksum only guards against corruption
+    fn validate(&self) -> Result<(), ScenarioErrorKind> {
+        let capacities = [self.max_weight, self.max_size];
+        let columns = [self.weights(), self.sizes(), self.costs()];
+
+        if let Some(value) = capacities
+            .iter()
+            .chain(columns.iter().flat_map(|x| x.iter()))
+            .find(|x| !is_non_negative(**x))
+        {
+            return Err(NegativeValue {
+                value: value.to_string(),
+            });
+        }
+
+        let mut sums = [N::zero(); 3];
+        for ((sum, column), name) in sums
+            .iter_mut()
+            .zip(columns.iter())
+            .zip(["weight", "size", "cost"])
+        {
+            *sum = column
+                .iter()
+                .try_fold(N::zero(), |acc, x| acc.checked_add(*x))
+                .ok_or(SumOverflow {
+                    column: name,
+                    number_type: N::NAME,
+                })?;
+        }
+
+        let [total_weight, total_size, _] = sums;
+        let [max_weight, max_size] = capacities;
+        match max_weight.checked_add(max_weight) {
+            Some(minimal) if total_weight > minimal => {}
+            Some(minimal) => {
+                return Err(TotalWeightToSmall {
+                    minimal: minimal.to_string(),
+                    total_weight: total_weight.to_string(),
+                })
+            }
+            None => return Err(header_overflow::<N>("weight")),
+        }
+        match max_size.checked_add(max_size) {
+            Some(minimal) if total_size > minimal => {}
+            Some(minimal) => {
+                return Err(TotalSizeToSmall {
+                    minimal: minimal.to_string(),
+                    total_size: total_size.to_string(),
+                })
+            }
+            None => return Err(header_overflow::<N>("size")),
+        }
+
+        Ok(())
+    }
+
+    pub fn number_of_objects(&self) -> usize {
+        self.number_of_objects
+    }
+
+    pub fn max_weight(&self) -> N {
+        self.max_weight
+    }
+
+    pub fn max_size(&self) -> N {
+        self.max_size
+    }
+
+    pub fn weights(&self) -> Column<N> {
+        self.column(0)
+    }
+
+    pub fn sizes(&self) -> Column<N> {
+        self.column(1)
+    }
+
+    pub fn costs(&self) -> Column<N> {
+        self.column(2)
+    }
+
+    fn column(&self, i: usize) -> Column<N> {
+        Column::Mapped {
+            map: Arc::clone(&self.map),
+            start: HEADER_SIZE + i * self.number_of_objects * size_of::<N>(),
+            len: self.number_of_objects,
+        }
+    }
+
+    pub fn labels(&self) -> Result<Labels, ScenarioErrors> {
+        if self.labels_length == 0 {
+            return Ok(Labels::default());
+        }
+
+        let start = self.map.len() - self.labels_length;
+        let labels: Labels = serde_json::from_slice(&self.map[start..]).map_err(|err| {
+            let kind = InvalidBinary {
+                reason: format!("invalid labels: {}", err),
+            };
+            ScenarioError::new(&self.path, kind)
+        })?;
+
+        let n = self.number_of_objects;
+        if !(labels.ids.is_empty() || labels.ids.len() == n)
+            || !(labels.names.is_empty() || labels.names.len() == n)
+        {
+            let kind = InvalidBinary {
+                reason: "labels do not match the objects".to_string(),
+            };
+            return Err(ScenarioError::new(&self.path, kind).into());
+        }
+
+        Ok(labels)
+    }
+
+    /// Scenario reading its columns straight from the mapping, nothing is copied
+    pub fn to_scenario(&self) -> Result<Scenario<N>, ScenarioErrors> {
+        Ok(Scenario {
+            weights: self.weights(),
+            sizes: self.sizes(),
+            costs: self.costs(),
+            number_of_objects: self.number_of_objects,
+            max_weight: self.max_weight,
+            max_size: self.max_size,
+            tolerance: N::zero(),
+            labels: self.labels()?,
+        })
+    }
+}
+
+fn bytes<const L: usize>(header: &[u8], offset: usize) -> [u8; L] {
+    let mut bytes = [0; L];
+    bytes.copy_from_slice(&header[offset..offset + L]);
+    bytes
+}
+
+/// Returns true if the file starts with `MAGIC`
+pub(super) fn is_binary(path: &str) -> io::Result<bool> {
+    let mut start = Vec::with_capacity(MAGIC.len());
+    File::open(path)?
+        .take(MAGIC.len() as u64)
+        .read_to_end(&mut start)?;
+
+    Ok(start == MAGIC)
+}
+
+/// Writes scenario in the `ScenarioFormat::Binary` format
+pub(super) fn write<N: Number, W: Write>(scenario: &Scenario<N>, mut writer: W) -> io::Result<()> {
+    if cfg!(target_endian = "big") {
+        let message = "binary scenarios are little-endian only";
+        return Err(io::Error::other(message));
+    }
+
+    let labels = &scenario.labels;
+    let labels = if labels.ids.is_empty() && labels.names.is_empty() && labels.metadata.is_empty() {
+        Vec::new()
+    } else {
+        serde_json::to_vec(labels)?
+    };
+
+    let mut header = [0; HEADER_SIZE];
+    header[..MAGIC.len()].copy_from_slice(MAGIC);
+    header[8..12].copy_from_slice(&VERSION.to_le_bytes());
+    header[12] = NumberType::ALL.iter().position(|x| *x == N::TYPE).unwrap() as u8;
+    header[16..24].copy_from_slice(&(scenario.number_of_objects as u64).to_le_bytes());
+    header[24..24 + size_of::<N>()].copy_from_slice(bytemuck::bytes_of(&scenario.max_weight));
+    header[32..32 + size_of::<N>()].copy_from_slice(bytemuck::bytes_of(&scenario.max_size));
+    header[40..48].copy_from_slice(&(labels.len() as u64).to_le_bytes());
+
+    let columns = [&scenario.weights, &scenario.sizes, &scenario.costs]
+        .map(|column| bytemuck::cast_slice::<N, u8>(column.as_slice()));
+
+    let mut hasher = Hasher::new();
+    hasher.update(&header[..CHECKSUMMED]);
+    for column in columns.iter() {
+        hasher.update(column);
+    }
+    hasher.update(&labels);
+    header[CHECKSUMMED..CHECKSUMMED + 4].copy_from_slice(&hasher.finalize().to_le_bytes());
+
+    writer.write_all(&header)?;
+    for column in columns.iter() {
+        writer.write_all(column)?;
+    }
+    writer.write_all(&labels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scenario::error::ScenarioErrorKind::{ChecksumMismatch, NumberTypeMismatch};
+    use std::env::temp_dir;
+    use std::process;
+
+    fn path(name: &str) -> String {
+        let path = temp_dir().join(format!("genet_{}_{}.bin", name, process::id()));
+        path.to_string_lossy().into_owned()
+    }
+
+    fn scenario() -> Scenario<i64> {
+        let mut scenario = Scenario {
+            weights: vec![2, 3, 4].into(),
+            sizes: vec![1, 2, 3].into(),
+            costs: vec![10, 12, 1].into(),
+            number_of_objects: 3,
+            max_weight: 3,
+            max_size: 2,
+            tolerance: 0,
+            labels: Labels::default(),
+        };
+        scenario.labels.ids = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        scenario
+    }
+
+    #[test]
+    fn saved_scenario_is_mapped_back() {
+        let path = path("binary");
+        scenario().save(&path).unwrap();
+
+        let mapped = MappedScenario::<i64>::open(&path).unwrap();
+        assert_eq!(mapped.weights().as_slice(), &[2, 3, 4]);
+        assert_eq!(mapped.costs().as_slice(), &[10, 12, 1]);
+        assert_eq!((mapped.max_weight(), mapped.max_size()), (3, 2));
+
+        let loaded = Scenario::<i64>::load(path).unwrap();
+        assert!(matches!(loaded.sizes, Column::Mapped { .. }));
+        assert_eq!(loaded.sizes.as_slice(), &[1, 2, 3]);
+        assert_eq!(loaded.labels.ids, vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn corrupted_file_fails_the_checksum() {
+        let path = path("binary_corrupted");
+        scenario().save(&path).unwrap();
+
+        let mut content = std::fs::read(&path).unwrap();
+        content[HEADER_SIZE] ^= 1;
+        std::fs::write(&path, content).unwrap();
+
+        let errors = Scenario::<i64>::load(path).unwrap_err();
+        assert!(matches!(errors.0[0].kind, ChecksumMismatch { .. }));
+    }
+
+    #[test]
+    fn number_type_has_to_match() {
+        let path = path("binary_type");
+        scenario().save(&path).unwrap();
+
+        let errors = Scenario::<f64>::load(path).unwrap_err();
+        assert!(matches!(
+            errors.0[0].kind,
+            NumberTypeMismatch {
+                found: "i64",
+                expected: "f64"
+            }
+        ));
+    }
+}
@@ -0,0 +1,93 @@
+use crate::number::Number;
+use memmap2::Mmap;
+use na::allocator::Allocator;
+use na::constraint::{AreMultipliable, ShapeConstraint};
+use na::storage::Storage;
+use na::{DVector, DVectorSlice, DefaultAllocator, Dim, Dynamic, Matrix, MatrixMN, U1};
+use std::fmt::{self, Debug, Formatter};
+use std::mem::size_of;
+use std::ops::{Deref, Mul};
+use std::sync::Arc;
+
+/// Values of one scenario column
+///
+/// Columns of binary scenarios point straight into the memory-mapped file,
+/// columns parsed from text formats own their values.
+#[derive(Clone)]
+pub enum Column<N: Number> {
+    Owned(Vec<N>),
+    Mapped {
+        map: Arc<Mmap>,
+        /// Offset of the first value in bytes
+        start: usize,
+        len: usize,
+    },
+}
+
+impl<N: Number> Column<N> {
+    pub fn as_slice(&self) -> &[N] {
+        match self {
+            Column::Owned(values) => values,
+            Column::Mapped { map, start, len } => {
+                // the mapping starts at a page boundary and binary scenarios place
+                // every column at a multiple of `size_of::<N>()` so it is aligned
+                bytemuck::cast_slice(&map[*start..*start + *len * size_of::<N>()])
+            }
+        }
+    }
+
+    /// Column as a vector, so the population can be multiplied by it
+    pub fn vector(&self) -> DVectorSlice<'_, N> {
+        let values = self.as_slice();
+        DVectorSlice::from_slice(values, values.len())
+    }
+}
+
+impl<N: Number> Deref for Column<N> {
+    type Target = [N];
+
+    fn deref(&self) -> &[N] {
+        self.as_slice()
+    }
+}
+
+/// Multiplies the population by the column without copying it
+impl<'b, N, R, C, S> Mul<&'b Column<N>> for &Matrix<N, R, C, S>
+where
+    N: Number,
+    R: Dim,
+    C: Dim,
+    S: Storage<N, R, C>,
+    DefaultAllocator: Allocator<N, R, U1>,
+    ShapeConstraint: AreMultipliable<R, C, Dynamic, U1>,
+{
+    type Output = MatrixMN<N, R, U1>;
+
+    fn mul(self, column: &'b Column<N>) -> MatrixMN<N, R, U1> {
+        self * column.vector()
+    }
+}
+
+impl<N: Number> From<Vec<N>> for Column<N> {
+    fn from(values: Vec<N>) -> Column<N> {
+        Column::Owned(values)
+    }
+}
+
+impl<N: Number> From<DVector<N>> for Column<N> {
+    fn from(values: DVector<N>) -> Column<N> {
+        Column::Owned(values.data.into())
+    }
+}
+
+impl<N: Number> PartialEq for Column<N> {
+    fn eq(&self, other: &Column<N>) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl<N: Number> Debug for Column<N> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
@@ -0,0 +1,279 @@
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::io;
+
+/// Error found while loading a scenario file
+///
+/// `line` and `column` are 1-based. They are not set for errors which do
+/// not relate to any particular place in the file, such as I/O errors.
+/// `field` names the value in formats with named fields, such as
+/// `items[3].weight` in JSON.
+#[derive(Debug)]
+pub struct ScenarioError {
+    pub path: String,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub field: Option<String>,
+    pub kind: ScenarioErrorKind,
+}
+
+#[derive(Debug)]
+pub enum ScenarioErrorKind {
+    Io(io::Error),
+
+    NoHeader,
+
+    HeaderLengthOtherThen3 {
+        found: usize,
+    },
+
+    LineLengthOtherThen3 {
+        found: usize,
+    },
+
+    InvalidNumber {
+        value: String,
+        number_type: &'static str,
+        reason: String,
+    },
+
+    /// Value is negative or NaN
+    NegativeValue {
+        value: String,
+    },
+
+    IncorrectNumberOfObjects {
+        declared: usize,
+        actual: usize,
+    },
+
+    TotalWeightToSmall {
+        minimal: String,
+        total_weight: String,
+    },
+
+    TotalSizeToSmall {
+        minimal: String,
+        total_size: String,
+    },
+
+    /// Sum of the column does not fit into the selected number type
+    SumOverflow {
+        column: &'static str,
+        number_type: &'static str,
+    },
+
+    InvalidJson {
+        reason: String,
+    },
+
+    InvalidCsv {
+        reason: String,
+    },
+
+    MissingColumn {
+        name: &'static str,
+    },
+
+    MissingCapacity {
+        name: &'static str,
+    },
+
+    /// Column given on the command line is not in the table
+    UnknownColumn {
+        name: String,
+    },
+
+    EmptyId,
+
+    DuplicateId {
+        id: String,
+    },
+
+    InvalidBinary {
+        reason: String,
+    },
+
+    UnsupportedVersion {
+        version: u32,
+    },
+
+    ChecksumMismatch {
+        expected: u32,
+        actual: u32,
+    },
+
+    /// Binary scenario was saved with a different number type
+    NumberTypeMismatch {
+        found: &'static str,
+        expected: &'static str,
+    },
+}
+
+impl ScenarioError {
+    pub fn new(path: &str, kind: ScenarioErrorKind) -> ScenarioError {
+        ScenarioError {
+            path: path.to_string(),
+            line: None,
+            column: None,
+            field: None,
+            kind,
+        }
+    }
+
+    pub fn at(path: &str, line: usize, column: usize, kind: ScenarioErrorKind) -> ScenarioError {
+        ScenarioError {
+            path: path.to_string(),
+            line: Some(line),
+            column: Some(column),
+            field: None,
+            kind,
+        }
+    }
+}
+
+impl Error for ScenarioError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.kind {
+            ScenarioErrorKind::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl Display for ScenarioError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        // the same format as compilers use so editors can jump to the position
+        write!(f, "{}", self.path)?;
+        if let Some(line) = self.line {
+            write!(f, ":{}", line)?;
+        }
+        if let Some(column) = self.column {
+            write!(f, ":{}", column)?;
+        }
+        if let Some(field) = &self.field {
+            write!(f, ": {}", field)?;
+        }
+        write!(f, ": {}", self.kind)
+    }
+}
+
+impl Display for ScenarioErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        use ScenarioErrorKind::*;
+
+        match self {
+            Io(err) => write!(f, "{}", err),
+            NoHeader => write!(
+                f,
+                "file is empty, expected header `number_of_objects,max_weight,max_size`"
+            ),
+            HeaderLengthOtherThen3 { found } => write!(
+                f,
+                "header has {} fields, expected 3: `number_of_objects,max_weight,max_size`",
+                found
+            ),
+            LineLengthOtherThen3 { found } => write!(
+                f,
+                "line has {} fields, expected 3: `weight,size,cost`",
+                found
+            ),
+            InvalidNumber {
+                value,
+                number_type,
+                reason,
+            } => write!(f, "`{}` is not a valid {}: {}", value, number_type, reason),
+            NegativeValue { value } => write!(f, "`{}` has to be a non negative number", value),
+            IncorrectNumberOfObjects { declared, actual } => write!(
+                f,
+                "header declares {} objects but {} were found",
+                declared, actual
+            ),
+            TotalWeightToSmall {
+                minimal,
+                total_weight,
+            } => write!(
+                f,
+                "total weight of all objects is {}, it has to be greater then 2 * max_weight = {}",
+                total_weight, minimal
+            ),
+            TotalSizeToSmall {
+                minimal,
+                total_size,
+            } => write!(
+                f,
+                "total size of all objects is {}, it has to be greater then 2 * max_size = {}",
+                total_size, minimal
+            ),
+            SumOverflow {
+                column,
+                number_type,
+            } => write!(
+                f,
+                "sum of {} does not fit into {}, try a wider --number-type",
+                column, number_type
+            ),
+            InvalidJson { reason } => write!(f, "invalid JSON: {}", reason),
+            InvalidCsv { reason } => write!(f, "invalid CSV: {}", reason),
+            MissingColumn { name } => write!(f, "table has no `{}` column", name),
+            MissingCapacity { name } => write!(
+                f,
+                "capacity `{}` is missing, expected a `# {} = value` line before the table",
+                name, name
+            ),
+            UnknownColumn { name } => write!(f, "table has no `{}` column", name),
+            EmptyId => write!(f, "object id is empty"),
+            DuplicateId { id } => write!(f, "id `{}` is used by more then one object", id),
+            InvalidBinary { reason } => write!(f, "invalid binary scenario: {}", reason),
+            UnsupportedVersion { version } => {
+                write!(f, "binary scenario version {} is not supported", version)
+            }
+            ChecksumMismatch { expected, actual } => write!(
+                f,
+                "checksum is {:08x} but the header declares {:08x}, the file is corrupted",
+                actual, expected
+            ),
+            NumberTypeMismatch { found, expected } => write!(
+                f,
+                "scenario holds {} values, use --number-type {} or convert it again with --number-type {}",
+                found, found, expected
+            ),
+        }
+    }
+}
+
+/// All errors found in a scenario file
+///
+/// Contains a single error unless the scenario was loaded with
+/// `LoadOptions::collect_errors`.
+#[derive(Debug)]
+pub struct ScenarioErrors(pub Vec<ScenarioError>);
+
+impl Error for ScenarioErrors {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.0.first().map(|err| err as &(dyn Error + 'static))
+    }
+}
+
+impl Display for ScenarioErrors {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.0.len() > 1 {
+            writeln!(f, "{} errors found", self.0.len())?;
+        }
+
+        for (i, err) in self.0.iter().enumerate() {
+            if i != 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", err)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl From<ScenarioError> for ScenarioErrors {
+    fn from(err: ScenarioError) -> Self {
+        ScenarioErrors(vec![err])
+    }
+}
@@ -0,0 +1,905 @@
+use crate::number::Number;
+use crate::scenario::generator::GeneratorError::{
+    CapacityTooLarge, EmptySpanner, InvalidCorrelation, InvalidTightness, NegativeCapacity,
+    NoObjects, Overflow, ZeroCeilingStep, ZeroRange,
+};
+use crate::scenario::{Labels, Scenario};
+use rand::{thread_rng, Rng};
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+/// Instance classes described by D. Pisinger in
+/// "Where are the hard knapsack problems?" (2005)
+///
+/// Pisinger defines them for a single resource. Here costs are correlated
+/// with a reference value which is a weighted average of the weight and
+/// the size of an object, see `GeneratorConfig::reference`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum InstanceClass {
+    /// cost in [1, R]
+    #[default]
+    Uncorrelated,
+    /// cost in [x - R/10, x + R/10]
+    WeaklyCorrelated,
+    /// cost = x + R/10
+    StronglyCorrelated,
+    /// resources in [1 + R/10, R + R/10], cost = x - R/10
+    InverseStronglyCorrelated,
+    /// cost in [x + R/10 - R/500, x + R/10 + R/500]
+    AlmostStronglyCorrelated,
+    /// cost = x
+    SubsetSum,
+    /// cost = d * ceil(x / d)
+    ProfitCeiling,
+    /// cost = d * sqrt(4R^2 - (x - 2R)^2)
+    Circle,
+    /// every object is a multiple of one of a few strongly correlated objects
+    Spanner,
+}
+
+impl InstanceClass {
+    pub const VARIANTS: [&'static str; 9] = [
+        "uncorrelated",
+        "weakly-correlated",
+        "strongly-correlated",
+        "inverse-strongly-correlated",
+        "almost-strongly-correlated",
+        "subset-sum",
+        "profit-ceiling",
+        "circle",
+        "spanner",
+    ];
+
+    const ALL: [InstanceClass; 9] = [
+        InstanceClass::Uncorrelated,
+        InstanceClass::WeaklyCorrelated,
+        InstanceClass::StronglyCorrelated,
+        InstanceClass::InverseStronglyCorrelated,
+        InstanceClass::AlmostStronglyCorrelated,
+        InstanceClass::SubsetSum,
+        InstanceClass::ProfitCeiling,
+        InstanceClass::Circle,
+        InstanceClass::Spanner,
+    ];
+}
+
+impl FromStr for InstanceClass {
+    type Err = UnknownInstanceClass;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        InstanceClass::VARIANTS
+            .iter()
+            .position(|name| *name == s)
+            .map(|i| InstanceClass::ALL[i])
+            .ok_or_else(|| UnknownInstanceClass(s.to_string()))
+    }
+}
+
+impl Display for InstanceClass {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let i = InstanceClass::ALL.iter().position(|x| x == self).unwrap();
+        write!(f, "{}", InstanceClass::VARIANTS[i])
+    }
+}
+
+#[derive(Debug)]
+pub struct UnknownInstanceClass(String);
+
+impl Error for UnknownInstanceClass {}
+
+impl Display for UnknownInstanceClass {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "unknown instance class {}, expected one of {}",
+            self.0,
+            InstanceClass::VARIANTS.join(", ")
+        )
+    }
+}
+
+#[derive(Debug)]
+pub struct GeneratorConfig {
+    pub number_of_objects: usize,
+    pub class: InstanceClass,
+    /// Data range R. Weights and sizes are drawn from [1, R]
+    pub range: u64,
+    /// Share of the weight in the reference value costs are correlated with
+    pub weight_correlation: f64,
+    /// Share of the size in the reference value costs are correlated with
+    pub size_correlation: f64,
+    /// `d` of the profit ceiling class
+    pub ceiling_step: u64,
+    /// `d` of the circle class
+    pub circle_factor: f64,
+    /// `v` of the spanner class, number of objects in the spanner set
+    pub spanner_size: usize,
+    /// `m` of the spanner class, the largest multiplier
+    pub spanner_multiplier: u64,
+}
+
+impl GeneratorConfig {
+    /// Data range used when capacities are given as tightness only
+    pub const DEFAULT_RANGE: u64 = 1000;
+
+    /// Data range used when none is given
+    ///
+    /// Objects drawn from it are expected to sum up to about 5 times
+    /// the larger of the capacities.
+    pub fn default_range(number_of_objects: usize, max_capacity: f64) -> u64 {
+        (10.0 * max_capacity / number_of_objects as f64)
+            .ceil()
+            .max(1.0) as u64
+    }
+
+    fn validate(&self) -> Result<(), GeneratorError> {
+        if self.number_of_objects == 0 {
+            Err(NoObjects)?
+        }
+        if self.range == 0 {
+            Err(ZeroRange)?
+        }
+        if !(self.weight_correlation >= 0.0 && self.size_correlation >= 0.0)
+            || self.weight_correlation + self.size_correlation <= 0.0
+        {
+            Err(InvalidCorrelation)?
+        }
+        if self.ceiling_step == 0 {
+            Err(ZeroCeilingStep)?
+        }
+        if self.spanner_size == 0 || self.spanner_multiplier == 0 {
+            Err(EmptySpanner)?
+        }
+
+        Ok(())
+    }
+
+    /// The value costs are correlated with
+    ///
+    /// Weighted average of `weight` and `size` so it stays in the data range.
+    fn reference(&self, weight: u64, size: u64) -> f64 {
+        (self.weight_correlation * weight as f64 + self.size_correlation * size as f64)
+            / (self.weight_correlation + self.size_correlation)
+    }
+
+    /// Range of weights and sizes of a single object, Spanner class excluded
+    fn resource_range(&self, class: InstanceClass) -> (u64, u64) {
+        match class {
+            InstanceClass::InverseStronglyCorrelated => {
+                (1 + self.range / 10, self.range + self.range / 10)
+            }
+            _ => (1, self.range),
+        }
+    }
+
+    /// Draws cost of an object with given resources, Spanner class excluded
+    fn cost<R: Rng + ?Sized>(
+        &self,
+        class: InstanceClass,
+        weight: u64,
+        size: u64,
+        rng: &mut R,
+    ) -> u64 {
+        use InstanceClass::*;
+
+        let r = self.range as f64;
+        let x = self.reference(weight, size);
+
+        let cost = match class {
+            Uncorrelated => rng.gen_range(1, self.range + 1) as f64,
+            WeaklyCorrelated => rng.gen_range(x - r / 10.0, x + r / 10.0),
+            StronglyCorrelated => x + r / 10.0,
+            InverseStronglyCorrelated => x - r / 10.0,
+            AlmostStronglyCorrelated => {
+                rng.gen_range(x + r / 10.0 - r / 500.0, x + r / 10.0 + r / 500.0)
+            }
+            SubsetSum => x,
+            ProfitCeiling => {
+                let d = self.ceiling_step as f64;
+                d * (x / d).ceil()
+            }
+            Circle => self.circle_factor * (4.0 * r * r - (x - 2.0 * r).powi(2)).sqrt(),
+            Spanner => unreachable!("spanner objects are multiples of the spanner set"),
+        };
+
+        // every object has to be worth something
+        (cost.round() as u64).max(1)
+    }
+
+    /// Draws `spanner_size` strongly correlated objects scaled down by `m + 1`
+    fn spanner_set<R: Rng + ?Sized>(&self, rng: &mut R) -> Vec<Object> {
+        let divisor = self.spanner_multiplier + 1;
+        let scale = |x: u64| x.div_ceil(divisor).max(1);
+        let (low, high) = self.resource_range(InstanceClass::StronglyCorrelated);
+
+        (0..self.spanner_size)
+            .map(|_| {
+                let weight = rng.gen_range(low, high + 1);
+                let size = rng.gen_range(low, high + 1);
+                let cost = self.cost(InstanceClass::StronglyCorrelated, weight, size, rng);
+
+                Object {
+                    weight: scale(weight),
+                    size: scale(size),
+                    cost: scale(cost),
+                }
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Object {
+    weight: u64,
+    size: u64,
+    cost: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Resource {
+    Weight,
+    Size,
+}
+
+impl Resource {
+    fn name(self) -> &'static str {
+        match self {
+            Resource::Weight => "weight",
+            Resource::Size => "size",
+        }
+    }
+}
+
+/// Object before its cost is drawn
+#[derive(Debug, Clone, Copy)]
+struct Shape {
+    weight: u64,
+    size: u64,
+    /// Spanner set object this one is a multiple of
+    span: usize,
+    multiplier: u64,
+}
+
+impl Shape {
+    fn get(&self, resource: Resource) -> u64 {
+        match resource {
+            Resource::Weight => self.weight,
+            Resource::Size => self.size,
+        }
+    }
+}
+
+/// Draws objects of the configured class
+///
+/// Objects are created in three steps. First their weights and sizes are drawn,
+/// then they are enlarged until the totals are big enough for the capacities
+/// and only then costs are drawn, so they stay correlated with the final
+/// weights and sizes.
+struct ObjectGenerator<'a> {
+    config: &'a GeneratorConfig,
+    spanner_set: Vec<Object>,
+}
+
+impl<'a> ObjectGenerator<'a> {
+    fn new<R: Rng + ?Sized>(config: &'a GeneratorConfig, rng: &mut R) -> ObjectGenerator<'a> {
+        let spanner_set = match config.class {
+            InstanceClass::Spanner => config.spanner_set(rng),
+            _ => Vec::new(),
+        };
+
+        ObjectGenerator {
+            config,
+            spanner_set,
+        }
+    }
+
+    fn shape<R: Rng + ?Sized>(&self, rng: &mut R) -> Shape {
+        match self.config.class {
+            InstanceClass::Spanner => {
+                let span = rng.gen_range(0, self.spanner_set.len());
+                let multiplier = rng.gen_range(1, self.config.spanner_multiplier + 1);
+
+                Shape {
+                    weight: multiplier * self.spanner_set[span].weight,
+                    size: multiplier * self.spanner_set[span].size,
+                    span,
+                    multiplier,
+                }
+            }
+            class => {
+                let (low, high) = self.config.resource_range(class);
+
+                Shape {
+                    weight: rng.gen_range(low, high + 1),
+                    size: rng.gen_range(low, high + 1),
+                    span: 0,
+                    multiplier: 1,
+                }
+            }
+        }
+    }
+
+    /// The largest value `resource` of `shape` can be enlarged to
+    fn limit(&self, shape: &Shape, resource: Resource) -> u64 {
+        match self.config.class {
+            InstanceClass::Spanner => {
+                let span = self.spanner_set[shape.span];
+                let unit = match resource {
+                    Resource::Weight => span.weight,
+                    Resource::Size => span.size,
+                };
+                self.config.spanner_multiplier * unit
+            }
+            class => self.config.resource_range(class).1,
+        }
+    }
+
+    /// Enlarges `resource` of `shape` by at most `deficit`. Returns by how much.
+    fn enlarge<R: Rng + ?Sized>(
+        &self,
+        shape: &mut Shape,
+        resource: Resource,
+        deficit: u64,
+        rng: &mut R,
+    ) -> u64 {
+        match self.config.class {
+            // spanner objects have to stay multiples of the spanner set
+            InstanceClass::Spanner => {
+                let span = self.spanner_set[shape.span];
+                shape.multiplier += 1;
+                shape.weight += span.weight;
+                shape.size += span.size;
+
+                match resource {
+                    Resource::Weight => span.weight,
+                    Resource::Size => span.size,
+                }
+            }
+            _ => {
+                let room = self.limit(shape, resource) - shape.get(resource);
+                let step = rng.gen_range(1, room.min(deficit) + 1);
+
+                match resource {
+                    Resource::Weight => shape.weight += step,
+                    Resource::Size => shape.size += step,
+                }
+
+                step
+            }
+        }
+    }
+
+    /// Enlarges randomly chosen objects until total of `resource` is greater then `minimal`
+    fn top_up<R: Rng + ?Sized>(
+        &self,
+        shapes: &mut [Shape],
+        resource: Resource,
+        minimal: u128,
+        rng: &mut R,
+    ) -> Result<(), GeneratorError> {
+        let mut total = shapes
+            .iter()
+            .map(|x| u128::from(x.get(resource)))
+            .sum::<u128>();
+
+        let largest_total = shapes
+            .iter()
+            .map(|x| u128::from(self.limit(x, resource)))
+            .sum::<u128>();
+        if largest_total <= minimal {
+            return Err(CapacityTooLarge {
+                resource: resource.name(),
+                minimal_total: minimal + 1,
+                largest_total,
+            });
+        }
+
+        let mut candidates = (0..shapes.len())
+            .filter(|i| shapes[*i].get(resource) < self.limit(&shapes[*i], resource))
+            .collect::<Vec<_>>();
+
+        while total <= minimal {
+            let candidate = rng.gen_range(0, candidates.len());
+            let shape = &mut shapes[candidates[candidate]];
+
+            let deficit = (minimal + 1 - total).min(u128::from(u64::MAX)) as u64;
+            total += u128::from(self.enlarge(shape, resource, deficit, rng));
+
+            if shape.get(resource) >= self.limit(shape, resource) {
+                candidates.swap_remove(candidate);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn object<R: Rng + ?Sized>(&self, shape: &Shape, rng: &mut R) -> Object {
+        let cost = match self.config.class {
+            InstanceClass::Spanner => shape.multiplier * self.spanner_set[shape.span].cost,
+            class => self.config.cost(class, shape.weight, shape.size, rng),
+        };
+
+        Object {
+            weight: shape.weight,
+            size: shape.size,
+            cost,
+        }
+    }
+}
+
+/// Capacity of a single resource
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Capacity<N> {
+    Absolute(N),
+    /// Fraction of the total of the resource over all objects, has to be in (0, 1]
+    ///
+    /// Unlike absolute capacities it is not required to be less then a half of the total.
+    Tightness(f64),
+}
+
+/// Total of `resource` has to be greater then this to satisfy twice the `capacity`
+fn minimal_total<N: Number>(
+    capacity: Capacity<N>,
+    resource: Resource,
+) -> Result<u128, GeneratorError> {
+    match capacity {
+        Capacity::Absolute(capacity) => {
+            let minimal = 2.0 * capacity.to_f64();
+            if minimal.is_nan() || minimal < 0.0 {
+                return Err(NegativeCapacity {
+                    resource: resource.name(),
+                });
+            }
+
+            Ok(minimal.floor() as u128)
+        }
+        // capacities given as tightness follow the total so they need no minimal one
+        Capacity::Tightness(tightness) if tightness > 0.0 && tightness <= 1.0 => Ok(0),
+        Capacity::Tightness(tightness) => Err(InvalidTightness {
+            resource: resource.name(),
+            tightness,
+        }),
+    }
+}
+
+/// Computes capacity of `resource` from values of all objects
+fn capacity<N: Number>(
+    capacity: Capacity<N>,
+    values: &[N],
+    resource: Resource,
+) -> Result<N, GeneratorError> {
+    let capacity = match capacity {
+        Capacity::Absolute(capacity) => capacity,
+        Capacity::Tightness(tightness) => {
+            // sums of generated values are known to fit into `N`
+            let total = values.iter().fold(N::zero(), |acc, x| acc + *x);
+            return N::from_f64(tightness * total.to_f64()).ok_or(Overflow {
+                column: resource.name(),
+                number_type: N::NAME,
+            });
+        }
+    };
+
+    // minimal totals are computed in f64 which may round large integers
+    let total = values
+        .iter()
+        .try_fold(N::zero(), |acc, x| acc.checked_add(*x));
+    match (total, capacity.checked_add(capacity)) {
+        (Some(total), Some(twice)) if total > twice => Ok(capacity),
+        _ => {
+            let minimal = minimal_total(Capacity::Absolute(capacity), resource)?;
+            Err(CapacityTooLarge {
+                resource: resource.name(),
+                minimal_total: minimal + 1,
+                largest_total: minimal,
+            })
+        }
+    }
+}
+
+impl<N: Number> Scenario<N> {
+    /// Generates scenario in memory
+    ///
+    /// Total weight and size of the objects are guaranteed to be greater then
+    /// twice `max_weight` and `max_size`. If the drawn objects are too small they
+    /// are enlarged within the limits of their class.
+    pub fn generate(
+        config: &GeneratorConfig,
+        max_weight: N,
+        max_size: N,
+    ) -> Result<Scenario<N>, GeneratorError> {
+        let levels = [(Capacity::Absolute(max_weight), Capacity::Absolute(max_size))];
+        let mut scenarios = Scenario::generate_levels(config, &levels)?;
+        Ok(scenarios.remove(0))
+    }
+
+    /// Generates a single set of objects and a scenario for each pair of
+    /// weight and size capacities
+    ///
+    /// Objects are enlarged until they satisfy the largest absolute capacity.
+    /// Capacities given as tightness are computed from the final objects.
+    pub fn generate_levels(
+        config: &GeneratorConfig,
+        levels: &[(Capacity<N>, Capacity<N>)],
+    ) -> Result<Vec<Scenario<N>>, GeneratorError> {
+        config.validate()?;
+
+        let mut minimal_weight = 0;
+        let mut minimal_size = 0;
+        for (weight, size) in levels {
+            minimal_weight = minimal_weight.max(minimal_total(*weight, Resource::Weight)?);
+            minimal_size = minimal_size.max(minimal_total(*size, Resource::Size)?);
+        }
+
+        let mut rng = thread_rng();
+        let generator = ObjectGenerator::new(config, &mut rng);
+
+        let mut shapes = (0..config.number_of_objects)
+            .map(|_| generator.shape(&mut rng))
+            .collect::<Vec<_>>();
+
+        generator.top_up(&mut shapes, Resource::Weight, minimal_weight, &mut rng)?;
+        generator.top_up(&mut shapes, Resource::Size, minimal_size, &mut rng)?;
+
+        let objects = shapes
+            .iter()
+            .map(|shape| generator.object(shape, &mut rng))
+            .collect::<Vec<_>>();
+
+        let objects = from_objects::<N>(&objects)?;
+
+        levels
+            .iter()
+            .map(|(weight, size)| {
+                Ok(Scenario {
+                    weights: objects.weights.clone(),
+                    sizes: objects.sizes.clone(),
+                    costs: objects.costs.clone(),
+                    number_of_objects: objects.number_of_objects,
+                    max_weight: capacity(*weight, &objects.weights, Resource::Weight)?,
+                    max_size: capacity(*size, &objects.sizes, Resource::Size)?,
+                    tolerance: N::zero(),
+                    labels: Labels::default(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Converts objects into a scenario with zero capacities
+fn from_objects<N: Number>(objects: &[Object]) -> Result<Scenario<N>, GeneratorError> {
+    let mut weights = Vec::with_capacity(objects.len());
+    let mut sizes = Vec::with_capacity(objects.len());
+    let mut costs = Vec::with_capacity(objects.len());
+    let mut sums = [N::zero(); 3];
+
+    for object in objects {
+        let values = [object.weight, object.size, object.cost];
+
+        for (i, value) in values.iter().enumerate() {
+            let overflow = || Overflow {
+                column: ["weight", "size", "cost"][i],
+                number_type: N::NAME,
+            };
+
+            let value = usize::try_from(*value)
+                .ok()
+                .and_then(N::from_usize)
+                .ok_or_else(overflow)?;
+            sums[i] = sums[i].checked_add(value).ok_or_else(overflow)?;
+
+            match i {
+                0 => weights.push(value),
+                1 => sizes.push(value),
+                _ => costs.push(value),
+            }
+        }
+    }
+
+    Ok(Scenario {
+        weights: weights.into(),
+        sizes: sizes.into(),
+        costs: costs.into(),
+        number_of_objects: objects.len(),
+        max_weight: N::zero(),
+        max_size: N::zero(),
+        tolerance: N::zero(),
+        labels: Labels::default(),
+    })
+}
+
+#[derive(Debug)]
+pub enum GeneratorError {
+    NoObjects,
+    ZeroRange,
+    InvalidCorrelation,
+    ZeroCeilingStep,
+    EmptySpanner,
+
+    NegativeCapacity {
+        resource: &'static str,
+    },
+
+    InvalidTightness {
+        resource: &'static str,
+        tightness: f64,
+    },
+
+    /// Objects of the class cannot sum up to more then twice the capacity
+    CapacityTooLarge {
+        resource: &'static str,
+        minimal_total: u128,
+        largest_total: u128,
+    },
+
+    /// Generated values do not fit into the selected number type
+    Overflow {
+        column: &'static str,
+        number_type: &'static str,
+    },
+}
+
+impl Error for GeneratorError {}
+
+impl Display for GeneratorError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            NoObjects => write!(f, "number of objects has to be positive"),
+            ZeroRange => write!(f, "range has to be positive"),
+            InvalidCorrelation => write!(
+                f,
+                "correlations have to be non negative and at least one of them positive"
+            ),
+            ZeroCeilingStep => write!(f, "ceiling step has to be positive"),
+            EmptySpanner => write!(f, "spanner size and multiplier have to be positive"),
+            NegativeCapacity { resource } => {
+                write!(f, "max {} has to be a non negative number", resource)
+            }
+            InvalidTightness {
+                resource,
+                tightness,
+            } => write!(
+                f,
+                "{} tightness is {}, it has to be in range (0, 1]",
+                resource, tightness
+            ),
+            CapacityTooLarge {
+                resource,
+                minimal_total,
+                largest_total,
+            } => write!(
+                f,
+                "total {} has to be at least {} (more then twice the capacity) but objects of this class cannot exceed {}, increase the range or the number of objects",
+                resource, minimal_total, largest_total
+            ),
+            Overflow {
+                column,
+                number_type,
+            } => write!(
+                f,
+                "sum of {} does not fit into {}, try a wider --number-type",
+                column, number_type
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn config(class: InstanceClass) -> GeneratorConfig {
+        GeneratorConfig {
+            number_of_objects: 100,
+            class,
+            range: 1000,
+            weight_correlation: 1.0,
+            size_correlation: 0.0,
+            ceiling_step: 3,
+            circle_factor: 2.0 / 3.0,
+            spanner_size: 2,
+            spanner_multiplier: 10,
+        }
+    }
+
+    fn objects(config: &GeneratorConfig) -> Vec<Object> {
+        let mut rng = StdRng::seed_from_u64(0);
+        let generator = ObjectGenerator::new(config, &mut rng);
+        (0..1000).map(|_| draw(&generator, &mut rng)).collect()
+    }
+
+    fn draw<R: Rng>(generator: &ObjectGenerator, rng: &mut R) -> Object {
+        let shape = generator.shape(rng);
+        generator.object(&shape, rng)
+    }
+
+    fn total(values: &[i64]) -> i64 {
+        values.iter().sum()
+    }
+
+    #[test]
+    fn class_names_round_trip() {
+        for name in InstanceClass::VARIANTS.iter() {
+            assert_eq!(name.parse::<InstanceClass>().unwrap().to_string(), *name);
+        }
+    }
+
+    #[test]
+    fn strongly_correlated_cost_follows_weight() {
+        for object in objects(&config(InstanceClass::StronglyCorrelated)) {
+            assert!(object.weight >= 1 && object.weight <= 1000);
+            assert_eq!(object.cost, object.weight + 100);
+        }
+    }
+
+    #[test]
+    fn inverse_strongly_correlated_weight_follows_cost() {
+        for object in objects(&config(InstanceClass::InverseStronglyCorrelated)) {
+            assert!(object.cost >= 1 && object.cost <= 1000);
+            assert_eq!(object.weight, object.cost + 100);
+        }
+    }
+
+    #[test]
+    fn subset_sum_correlates_with_both_resources() {
+        let mut config = config(InstanceClass::SubsetSum);
+        config.size_correlation = 1.0;
+
+        for object in objects(&config) {
+            let average = (object.weight + object.size) as f64 / 2.0;
+            assert_eq!(object.cost, average.round() as u64);
+        }
+    }
+
+    #[test]
+    fn profit_ceiling_costs_are_multiples_of_the_step() {
+        for object in objects(&config(InstanceClass::ProfitCeiling)) {
+            assert!(object.cost % 3 == 0);
+            assert!(object.cost >= object.weight && object.cost < object.weight + 3);
+        }
+    }
+
+    #[test]
+    fn spanner_objects_are_multiples_of_the_spanner_set() {
+        let config = config(InstanceClass::Spanner);
+        let mut rng = StdRng::seed_from_u64(0);
+        let generator = ObjectGenerator::new(&config, &mut rng);
+
+        for _ in 0..1000 {
+            let object = draw(&generator, &mut rng);
+            assert!(generator.spanner_set.iter().any(|span| {
+                object.weight % span.weight == 0
+                    && object.weight / span.weight == object.cost / span.cost
+                    && object.weight / span.weight == object.size / span.size
+            }));
+        }
+    }
+
+    #[test]
+    fn small_objects_are_enlarged_to_exceed_twice_the_capacity() {
+        // spanner objects are much smaller, see the test below
+        for class in InstanceClass::ALL[..8].iter().cloned() {
+            let mut config = config(class);
+            config.number_of_objects = 10;
+            config.range = 100;
+
+            // 10 objects drawn from [1, 100] sum up to about 500
+            let scenario = Scenario::<i64>::generate(&config, 450, 400).unwrap();
+
+            assert!(total(&scenario.weights) > 900, "{}", class);
+            assert!(total(&scenario.sizes) > 800, "{}", class);
+        }
+    }
+
+    #[test]
+    fn enlarged_spanner_objects_stay_multiples_of_the_spanner_set() {
+        let mut config = config(InstanceClass::Spanner);
+        config.number_of_objects = 10;
+        let mut rng = StdRng::seed_from_u64(0);
+        let generator = ObjectGenerator::new(&config, &mut rng);
+        let mut shapes = (0..10)
+            .map(|_| generator.shape(&mut rng))
+            .collect::<Vec<_>>();
+
+        // the largest reachable total, every multiplier has to reach `m`
+        let minimal = shapes
+            .iter()
+            .map(|x| u128::from(generator.limit(x, Resource::Weight)))
+            .sum::<u128>()
+            - 1;
+        generator
+            .top_up(&mut shapes, Resource::Weight, minimal, &mut rng)
+            .unwrap();
+
+        for shape in shapes {
+            let span = generator.spanner_set[shape.span];
+            assert_eq!(shape.multiplier, 10);
+            assert_eq!(shape.weight, 10 * span.weight);
+            assert_eq!(shape.size, 10 * span.size);
+        }
+    }
+
+    #[test]
+    fn enlarged_objects_keep_their_class() {
+        let mut config = config(InstanceClass::StronglyCorrelated);
+        config.number_of_objects = 10;
+        config.range = 100;
+
+        let scenario = Scenario::<i64>::generate(&config, 450, 0).unwrap();
+
+        for (weight, cost) in scenario.weights.iter().zip(scenario.costs.iter()) {
+            assert!(*weight <= 100);
+            assert_eq!(*cost, weight + 10);
+        }
+    }
+
+    #[test]
+    fn impossible_capacity_is_reported() {
+        let mut config = config(InstanceClass::Uncorrelated);
+        config.number_of_objects = 10;
+        config.range = 100;
+
+        // 10 objects cannot weigh more then 1000 in total
+        match Scenario::<i64>::generate(&config, 500, 0) {
+            Err(CapacityTooLarge {
+                resource: "weight",
+                minimal_total: 1001,
+                largest_total: 1000,
+            }) => {}
+            other => panic!(
+                "unexpected result: {:?}",
+                other.map(|x| x.number_of_objects)
+            ),
+        }
+    }
+
+    #[test]
+    fn invalid_config_is_reported() {
+        let mut config = config(InstanceClass::Uncorrelated);
+        config.number_of_objects = 0;
+
+        assert!(matches!(
+            Scenario::<i64>::generate(&config, 10, 10),
+            Err(NoObjects)
+        ));
+    }
+
+    #[test]
+    fn tightness_levels_share_objects() {
+        let levels = [0.25, 0.5, 0.75]
+            .iter()
+            .map(|x| (Capacity::Tightness(*x), Capacity::Absolute(100)))
+            .collect::<Vec<_>>();
+        let config = config(InstanceClass::Uncorrelated);
+
+        let scenarios = Scenario::<i64>::generate_levels(&config, &levels).unwrap();
+
+        assert_eq!(scenarios[0].weights, scenarios[2].weights);
+        assert_eq!(scenarios[0].costs, scenarios[2].costs);
+        let weights = total(&scenarios[0].weights) as f64;
+        assert_eq!(scenarios[0].max_weight, (0.25 * weights).floor() as i64);
+        assert_eq!(scenarios[1].max_weight, (0.5 * weights).floor() as i64);
+        assert_eq!(scenarios[2].max_weight, (0.75 * weights).floor() as i64);
+        assert_eq!(scenarios[2].max_size, 100);
+    }
+
+    #[test]
+    fn tightness_outside_of_range_is_reported() {
+        let config = config(InstanceClass::Uncorrelated);
+
+        for tightness in [0.0, 1.5, f64::NAN].iter() {
+            let levels = [(Capacity::Tightness(*tightness), Capacity::Absolute(10.0))];
+            assert!(matches!(
+                Scenario::<f64>::generate_levels(&config, &levels),
+                Err(InvalidTightness {
+                    resource: "weight",
+                    ..
+                })
+            ));
+        }
+    }
+}
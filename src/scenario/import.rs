@@ -0,0 +1,478 @@
+use crate::number::Number;
+use crate::scenario::error::ScenarioErrorKind::{InvalidCsv, Io, UnknownColumn};
+use crate::scenario::error::{ScenarioError, ScenarioErrors};
+use crate::scenario::{is_non_negative, Errors, Loaded, Place, Scenario};
+use csv::{ReaderBuilder, StringRecord, Trim};
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::fs::File;
+use std::str::FromStr;
+
+/// Describes how to build a scenario out of an arbitrary CSV table
+///
+/// Column names are matched case insensitively, unmapped columns are ignored.
+#[derive(Debug)]
+pub struct ImportConfig<N> {
+    pub weight_column: String,
+    pub size_column: String,
+    pub cost_column: String,
+    /// Column with object identifiers, 1-based numbers of the data rows are
+    /// used if not given
+    pub id_column: Option<String>,
+    pub name_column: Option<String>,
+    pub max_weight: N,
+    pub max_size: N,
+    /// Only rows matching all filters are imported
+    pub filters: Vec<Filter>,
+    pub delimiter: u8,
+}
+
+/// Imported scenario together with the rows which did not make it
+#[derive(Debug)]
+pub struct Import<N: Number> {
+    pub scenario: Scenario<N>,
+    pub skipped: Vec<SkippedRow>,
+}
+
+#[derive(Debug)]
+pub struct SkippedRow {
+    /// 1-based line of the row
+    pub line: usize,
+    pub id: Option<String>,
+    pub reason: SkipReason,
+}
+
+#[derive(Debug)]
+pub enum SkipReason {
+    Filtered {
+        filter: String,
+        value: String,
+    },
+    InvalidValue {
+        column: String,
+        value: String,
+        reason: String,
+    },
+    DuplicateId,
+    EmptyId,
+    Malformed {
+        reason: String,
+    },
+}
+
+impl<N: Number> Scenario<N> {
+    /// Imports scenario from a CSV table mapped by `config`
+    ///
+    /// Rows with invalid values are skipped and reported instead of failing
+    /// the whole import. Missing columns and overflowing sums are still errors.
+    pub fn import(input_file: &str, config: &ImportConfig<N>) -> Result<Import<N>, ScenarioErrors> {
+        let path = input_file;
+        let file = File::open(path).map_err(|err| ScenarioError::new(path, Io(err)))?;
+        let mut errors = Errors::new(path, false);
+
+        let mut reader = ReaderBuilder::new()
+            .trim(Trim::All)
+            .delimiter(config.delimiter)
+            .from_reader(file);
+        let header = reader
+            .headers()
+            .map_err(|err| {
+                let kind = InvalidCsv {
+                    reason: err.to_string(),
+                };
+                errors.fail(Place::at(1, 1), kind)
+            })?
+            .clone();
+
+        let find = |name: &str| {
+            header
+                .iter()
+                .position(|x| x.eq_ignore_ascii_case(name))
+                .ok_or_else(|| {
+                    let kind = UnknownColumn {
+                        name: name.to_string(),
+                    };
+                    ScenarioErrors::from(ScenarioError::at(path, 1, 1, kind))
+                })
+        };
+
+        let values = [
+            find(&config.weight_column)?,
+            find(&config.size_column)?,
+            find(&config.cost_column)?,
+        ];
+        let id = config.id_column.as_deref().map(find).transpose()?;
+        let name = config.name_column.as_deref().map(find).transpose()?;
+        let filters = config
+            .filters
+            .iter()
+            .map(|filter| Ok((find(&filter.column)?, filter)))
+            .collect::<Result<Vec<_>, ScenarioErrors>>()?;
+
+        let mut loaded = Loaded::<N>::new();
+        let mut skipped = Vec::new();
+
+        let mut record = StringRecord::new();
+        // 1-based number of the data row, skipped rows count too
+        let mut row = 0;
+        loop {
+            let line = reader.position().line() as usize;
+            row += 1;
+            match reader.read_record(&mut record) {
+                Ok(true) => {}
+                Ok(false) => break,
+                Err(err) => {
+                    let reason = SkipReason::Malformed {
+                        reason: err.to_string(),
+                    };
+                    skipped.push(SkippedRow {
+                        line: err.position().map_or(line, |x| x.line() as usize),
+                        id: None,
+                        reason,
+                    });
+                    continue;
+                }
+            }
+
+            let line = record.position().map_or(line, |x| x.line() as usize);
+            let row_id = match id {
+                Some(id) => record[id].to_string(),
+                None => row.to_string(),
+            };
+            let skip = |reason| SkippedRow {
+                line,
+                id: Some(row_id.clone()),
+                reason,
+            };
+
+            if let Some(reason) = filters
+                .iter()
+                .find_map(|(column, filter)| filter.reject(&record[*column]))
+            {
+                skipped.push(skip(reason));
+                continue;
+            }
+
+            if row_id.is_empty() {
+                skipped.push(skip(SkipReason::EmptyId));
+                continue;
+            }
+
+            let mut parsed = [N::zero(); 3];
+            let mut invalid = None;
+            for (i, column) in values.iter().enumerate() {
+                match parse::<N>(&record[*column]) {
+                    Ok(value) => parsed[i] = value,
+                    Err(reason) => {
+                        invalid = Some(SkipReason::InvalidValue {
+                            column: header[*column].to_string(),
+                            value: record[*column].to_string(),
+                            reason,
+                        });
+                        break;
+                    }
+                }
+            }
+            if let Some(reason) = invalid {
+                skipped.push(skip(reason));
+                continue;
+            }
+
+            if loaded.has_id(&row_id) {
+                skipped.push(skip(SkipReason::DuplicateId));
+                continue;
+            }
+
+            let places = values.map(|column| Place::cell(line, &header[column]));
+            loaded.push(&mut errors, parsed, places)?;
+
+            let name = name
+                .map(|i| &record[i])
+                .filter(|x| !x.is_empty())
+                .map(str::to_string);
+            let place = Place::cell(line, "id");
+            loaded.label(&mut errors, row_id, name, place)?;
+        }
+
+        let places = [
+            Place::field("max_weight".to_string()),
+            Place::field("max_size".to_string()),
+        ];
+        let scenario = loaded.finish(
+            errors,
+            Some(config.max_weight),
+            Some(config.max_size),
+            places,
+        )?;
+
+        Ok(Import { scenario, skipped })
+    }
+}
+
+fn parse<N: Number>(value: &str) -> Result<N, String> {
+    match N::parse(value) {
+        Ok(number) if is_non_negative(number) => Ok(number),
+        Ok(_) => Err("has to be a non negative number".to_string()),
+        Err(err) => Err(format!("is not a valid {}: {}", N::NAME, err)),
+    }
+}
+
+/// Condition a row has to meet to be imported, such as `status!=discontinued` or `kg<=30`
+#[derive(Debug, Clone)]
+pub struct Filter {
+    pub column: String,
+    pub operator: Operator,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operator {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Operator {
+    /// Longer operators first so `<=` is not taken for `<`
+    const ALL: [(&'static str, Operator); 6] = [
+        ("!=", Operator::NotEqual),
+        ("<=", Operator::LessOrEqual),
+        (">=", Operator::GreaterOrEqual),
+        ("=", Operator::Equal),
+        ("<", Operator::Less),
+        (">", Operator::Greater),
+    ];
+
+    fn symbol(self) -> &'static str {
+        Operator::ALL.iter().find(|(_, x)| *x == self).unwrap().0
+    }
+}
+
+impl Filter {
+    /// Returns the reason for rejecting `value` or `None` if it matches
+    ///
+    /// Values are compared as numbers if both of them are numbers and as text
+    /// otherwise. Ordering operators require numbers.
+    fn reject(&self, value: &str) -> Option<SkipReason> {
+        let numbers = value
+            .parse::<f64>()
+            .ok()
+            .zip(self.value.parse::<f64>().ok());
+
+        let matches = match (self.operator, numbers) {
+            (Operator::Equal, Some((a, b))) => a == b,
+            (Operator::NotEqual, Some((a, b))) => a != b,
+            (Operator::Equal, None) => value == self.value,
+            (Operator::NotEqual, None) => value != self.value,
+            (Operator::Less, Some((a, b))) => a < b,
+            (Operator::LessOrEqual, Some((a, b))) => a <= b,
+            (Operator::Greater, Some((a, b))) => a > b,
+            (Operator::GreaterOrEqual, Some((a, b))) => a >= b,
+            (_, None) => {
+                return Some(SkipReason::InvalidValue {
+                    column: self.column.clone(),
+                    value: value.to_string(),
+                    reason: format!("is not a number required by filter `{}`", self),
+                })
+            }
+        };
+
+        if matches {
+            None
+        } else {
+            Some(SkipReason::Filtered {
+                filter: self.to_string(),
+                value: value.to_string(),
+            })
+        }
+    }
+}
+
+impl FromStr for Filter {
+    type Err = InvalidFilter;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let position = s.find(['=', '!', '<', '>']);
+
+        let (column, rest) = match position {
+            Some(position) => s.split_at(position),
+            None => return Err(InvalidFilter(s.to_string())),
+        };
+        let (symbol, operator) = Operator::ALL
+            .iter()
+            .find(|(symbol, _)| rest.starts_with(symbol))
+            .ok_or_else(|| InvalidFilter(s.to_string()))?;
+
+        let column = column.trim();
+        if column.is_empty() {
+            return Err(InvalidFilter(s.to_string()));
+        }
+
+        Ok(Filter {
+            column: column.to_string(),
+            operator: *operator,
+            value: rest[symbol.len()..].trim().to_string(),
+        })
+    }
+}
+
+impl Display for Filter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}{}", self.column, self.operator.symbol(), self.value)
+    }
+}
+
+#[derive(Debug)]
+pub struct InvalidFilter(String);
+
+impl Error for InvalidFilter {}
+
+impl Display for InvalidFilter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "`{}` is not a valid filter, expected `column<operator>value` with one of =, !=, <, <=, >, >=",
+            self.0
+        )
+    }
+}
+
+impl Display for SkippedRow {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}", self.line)?;
+        if let Some(id) = &self.id {
+            write!(f, " ({})", id)?;
+        }
+        write!(f, ": {}", self.reason)
+    }
+}
+
+impl Display for SkipReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SkipReason::Filtered { filter, value } => {
+                write!(f, "`{}` does not match filter `{}`", value, filter)
+            }
+            SkipReason::InvalidValue {
+                column,
+                value,
+                reason,
+            } => write!(f, "{} `{}` {}", column, value, reason),
+            SkipReason::DuplicateId => write!(f, "id is used by an earlier row"),
+            SkipReason::EmptyId => write!(f, "id is empty"),
+            SkipReason::Malformed { reason } => write!(f, "{}", reason),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env::temp_dir;
+    use std::process;
+
+    fn write_table(name: &str, content: &str) -> String {
+        let path = temp_dir().join(format!("genet_import_{}_{}.csv", name, process::id()));
+        std::fs::write(&path, content).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    fn config(filters: &[&str]) -> ImportConfig<f64> {
+        ImportConfig {
+            weight_column: "kg".to_string(),
+            size_column: "litres".to_string(),
+            cost_column: "price".to_string(),
+            id_column: Some("sku".to_string()),
+            name_column: None,
+            max_weight: 5.0,
+            max_size: 5.0,
+            filters: filters.iter().map(|x| x.parse().unwrap()).collect(),
+            delimiter: b',',
+        }
+    }
+
+    const TABLE: &str = "sku,kg,litres,price,status\n\
+                         A,2.5,3,10,active\n\
+                         B,4,n/a,12,active\n\
+                         C,3,2,7,discontinued\n\
+                         A,1,1,1,active\n\
+                         D,8,9,20,active\n\
+                         E,1,2,3\n";
+
+    #[test]
+    fn import_maps_columns_and_reports_skipped_rows() {
+        let path = write_table("mapped", TABLE);
+
+        let import = Scenario::import(&path, &config(&["status!=discontinued"])).unwrap();
+        assert_eq!(import.scenario.labels.ids, vec!["A", "D"]);
+        assert_eq!(import.scenario.weights.as_slice(), &[2.5, 8.0]);
+        assert_eq!(import.scenario.costs.as_slice(), &[10.0, 20.0]);
+
+        let skipped = import
+            .skipped
+            .iter()
+            .map(|x| x.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            skipped[0],
+            "line 3 (B): litres `n/a` is not a valid f64: invalid float literal"
+        );
+        assert_eq!(
+            skipped[1],
+            "line 4 (C): `discontinued` does not match filter `status!=discontinued`"
+        );
+        assert_eq!(skipped[2], "line 5 (A): id is used by an earlier row");
+        assert!(skipped[3].starts_with("line 7: "), "{}", skipped[3]);
+    }
+
+    #[test]
+    fn numeric_filters_compare_numbers() {
+        let path = write_table(
+            "numeric",
+            "sku,kg,litres,price\nA,2,3,10\nB,10,3,12\nC,4,3,4\nD,6,9,20\n",
+        );
+
+        let mut config = config(&["kg<=6", "price>4.5"]);
+        config.max_weight = 3.0;
+        config.max_size = 3.0;
+
+        let import = Scenario::import(&path, &config).unwrap();
+        assert_eq!(import.scenario.labels.ids, vec!["A", "D"]);
+    }
+
+    #[test]
+    fn row_numbers_are_ids_by_default() {
+        let path = write_table("row_ids", TABLE);
+        let mut config = config(&[]);
+        config.id_column = None;
+
+        let import = Scenario::import(&path, &config).unwrap();
+        // the second row has an invalid size
+        assert_eq!(import.scenario.labels.ids, vec!["1", "3", "4", "5"]);
+        assert_eq!(import.skipped[0].id.as_deref(), Some("2"));
+    }
+
+    #[test]
+    fn unknown_columns_are_errors() {
+        let path = write_table("unknown", TABLE);
+        let mut config = config(&[]);
+        config.cost_column = "cost".to_string();
+
+        let errors = Scenario::import(&path, &config).unwrap_err();
+        assert!(matches!(&errors.0[0].kind, UnknownColumn { name } if name == "cost"));
+    }
+
+    #[test]
+    fn filters_are_parsed() {
+        let filter = "price >= 10".parse::<Filter>().unwrap();
+        assert_eq!(filter.column, "price");
+        assert_eq!(filter.operator, Operator::GreaterOrEqual);
+        assert_eq!(filter.value, "10");
+
+        assert!("price".parse::<Filter>().is_err());
+        assert!("=10".parse::<Filter>().is_err());
+    }
+}
@@ -0,0 +1,237 @@
+use crate::number::Number;
+use crate::scenario::error::ScenarioErrorKind::{InvalidJson, InvalidNumber};
+use crate::scenario::error::{ScenarioError, ScenarioErrors};
+use crate::scenario::{Errors, Loaded, Place, Scenario};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+#[derive(Deserialize)]
+struct JsonScenario {
+    capacities: Capacities,
+    #[serde(default)]
+    metadata: BTreeMap<String, Value>,
+    items: Vec<Item>,
+}
+
+#[derive(Deserialize)]
+struct Capacities {
+    max_weight: Value,
+    max_size: Value,
+}
+
+/// Object of the scenario, fields other then these are ignored
+#[derive(Deserialize)]
+struct Item {
+    id: Value,
+    #[serde(default)]
+    name: Option<String>,
+    weight: Value,
+    size: Value,
+    cost: Value,
+}
+
+/// Loads scenario in the `ScenarioFormat::Json` format
+///
+/// Numbers are read through their textual representation so integers
+/// too large for `f64` keep all their digits. They may also be given as strings.
+pub(super) fn load<N: Number>(
+    content: &str,
+    mut errors: Errors,
+) -> Result<Scenario<N>, ScenarioErrors> {
+    let scenario: JsonScenario = serde_json::from_str(content).map_err(|err| {
+        let kind = InvalidJson {
+            reason: err.to_string(),
+        };
+        ScenarioError::at(errors.path, err.line(), err.column(), kind)
+    })?;
+
+    let max_weight = parse(
+        &mut errors,
+        &scenario.capacities.max_weight,
+        "capacities.max_weight",
+    )?;
+    let max_size = parse(
+        &mut errors,
+        &scenario.capacities.max_size,
+        "capacities.max_size",
+    )?;
+
+    let mut loaded = Loaded::new();
+    for (i, item) in scenario.items.iter().enumerate() {
+        let field = |name: &str| Place::field(format!("items[{}].{}", i, name));
+        let values = [&item.weight, &item.size, &item.cost]
+            .iter()
+            .map(|value| text(value))
+            .collect::<Vec<_>>();
+        let values = [&values[0][..], &values[1][..], &values[2][..]];
+
+        if loaded.parse(
+            &mut errors,
+            values,
+            [field("weight"), field("size"), field("cost")],
+        )? {
+            loaded.label(&mut errors, text(&item.id), item.name.clone(), field("id"))?;
+        }
+    }
+
+    loaded.labels.metadata = scenario
+        .metadata
+        .into_iter()
+        .map(|(key, value)| (key, text(&value)))
+        .collect();
+
+    let places = [
+        Place::field("capacities.max_weight".to_string()),
+        Place::field("capacities.max_size".to_string()),
+    ];
+    loaded.finish(errors, max_weight, max_size, places)
+}
+
+fn parse<N: Number>(
+    errors: &mut Errors,
+    value: &Value,
+    field: &str,
+) -> Result<Option<N>, ScenarioErrors> {
+    let place = Place::field(field.to_string());
+
+    match value {
+        Value::Number(_) | Value::String(_) => errors.parse_number(place, &text(value)),
+        _ => {
+            let kind = InvalidNumber {
+                value: value.to_string(),
+                number_type: N::NAME,
+                reason: "expected a number".to_string(),
+            };
+            errors.push(place, kind).map(|_| None)
+        }
+    }
+}
+
+/// Strings without quotes, everything else as JSON
+fn text(value: &Value) -> String {
+    match value {
+        Value::String(value) => value.clone(),
+        value => value.to_string(),
+    }
+}
+
+/// Writes scenario in the `ScenarioFormat::Json` format
+///
+/// Objects without ids get their indices.
+pub(super) fn write<N: Number, W: Write>(scenario: &Scenario<N>, writer: W) -> io::Result<()> {
+    let items = (0..scenario.number_of_objects)
+        .map(|i| {
+            let mut item = Map::new();
+            item.insert("id".to_string(), json!(scenario.labels.id(i)));
+            if let Some(Some(name)) = scenario.labels.names.get(i) {
+                item.insert("name".to_string(), json!(name));
+            }
+            item.insert("weight".to_string(), number(scenario.weights[i]));
+            item.insert("size".to_string(), number(scenario.sizes[i]));
+            item.insert("cost".to_string(), number(scenario.costs[i]));
+            Value::Object(item)
+        })
+        .collect::<Vec<_>>();
+
+    let scenario = json!({
+        "capacities": {
+            "max_weight": number(scenario.max_weight),
+            "max_size": number(scenario.max_size),
+        },
+        "metadata": scenario.labels.metadata,
+        "items": items,
+    });
+
+    serde_json::to_writer_pretty(writer, &scenario)?;
+    Ok(())
+}
+
+/// Scenario values are finite so they are always valid JSON numbers
+fn number<N: Number>(value: N) -> Value {
+    Value::Number(serde_json::Number::from_str(&value.to_string()).unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scenario::error::ScenarioErrorKind::DuplicateId;
+    use crate::scenario::Scenario;
+    use std::env::temp_dir;
+    use std::process;
+
+    fn write_scenario(name: &str, content: &str) -> String {
+        let path = temp_dir().join(format!("genet_{}_{}.json", name, process::id()));
+        std::fs::write(&path, content).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    const SCENARIO: &str = r#"{
+        "capacities": {"max_weight": 3, "max_size": "2"},
+        "metadata": {"source": "warehouse", "week": 12},
+        "items": [
+            {"id": "SKU-1", "name": "Chair", "weight": 2, "size": 1, "cost": 10, "color": "red"},
+            {"id": 7, "weight": 3, "size": 2, "cost": 12},
+            {"id": "SKU-3", "weight": 4, "size": 3, "cost": 1}
+        ]
+    }"#;
+
+    #[test]
+    fn load_reads_ids_names_and_metadata() {
+        let scenario = Scenario::<i64>::load(write_scenario("json", SCENARIO)).unwrap();
+
+        assert_eq!(scenario.weights.as_slice(), &[2, 3, 4]);
+        assert_eq!((scenario.max_weight, scenario.max_size), (3, 2));
+        assert_eq!(scenario.labels.ids, vec!["SKU-1", "7", "SKU-3"]);
+        assert_eq!(scenario.labels.names[0].as_deref(), Some("Chair"));
+        assert_eq!(scenario.labels.names[1], None);
+        assert_eq!(scenario.labels.metadata["week"], "12");
+    }
+
+    #[test]
+    fn saved_scenario_loads_back() {
+        let scenario = Scenario::<f64>::load(write_scenario("json_source", SCENARIO)).unwrap();
+        let path = write_scenario("json_save", "");
+
+        scenario.save(&path).unwrap();
+        let loaded = Scenario::<f64>::load(path).unwrap();
+
+        assert_eq!(loaded.costs, scenario.costs);
+        assert_eq!(loaded.labels.ids, scenario.labels.ids);
+        assert_eq!(loaded.labels.names, scenario.labels.names);
+        assert_eq!(loaded.labels.metadata, scenario.labels.metadata);
+    }
+
+    #[test]
+    fn errors_name_the_invalid_field() {
+        let content = SCENARIO.replace(r#""size": 2"#, r#""size": -2"#);
+        let path = write_scenario("json_negative", &content);
+
+        let errors = Scenario::<i64>::load(path.clone()).unwrap_err();
+        assert_eq!(
+            errors.to_string(),
+            format!(
+                "{}: items[1].size: `-2` has to be a non negative number",
+                path
+            )
+        );
+    }
+
+    #[test]
+    fn syntax_errors_point_at_the_position() {
+        let path = write_scenario("json_syntax", "{\n  \"capacities\": {]\n}");
+
+        let err = &Scenario::<i64>::load(path).unwrap_err().0[0];
+        assert_eq!((err.line, err.column), (Some(2), Some(18)));
+    }
+
+    #[test]
+    fn duplicate_ids_are_rejected() {
+        let path = write_scenario("json_duplicate", &SCENARIO.replace("SKU-3", "SKU-1"));
+
+        let errors = Scenario::<i64>::load(path).unwrap_err();
+        assert!(matches!(&errors.0[0].kind, DuplicateId { id } if id == "SKU-1"));
+        assert_eq!(errors.0[0].field.as_deref(), Some("items[2].id"));
+    }
+}
@@ -0,0 +1,784 @@
+pub mod analysis;
+pub mod binary;
+pub mod column;
+pub mod error;
+pub mod generator;
+pub mod import;
+mod json;
+pub mod reduction;
+pub mod solution;
+pub mod solver;
+mod table;
+
+use crate::number::Number;
+use crate::scenario::binary::MappedScenario;
+use crate::scenario::column::Column;
+use crate::scenario::error::ScenarioErrorKind::{
+    DuplicateId, EmptyId, HeaderLengthOtherThen3, IncorrectNumberOfObjects, InvalidNumber, Io,
+    LineLengthOtherThen3, NegativeValue, NoHeader, SumOverflow, TotalSizeToSmall,
+    TotalWeightToSmall,
+};
+use crate::scenario::error::{ScenarioError, ScenarioErrorKind, ScenarioErrors};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashSet};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+#[derive(Debug, Default)]
+pub struct LoadOptions {
+    /// Keep loading after the first error and report all of them
+    pub collect_errors: bool,
+}
+
+/// Format of a scenario file
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScenarioFormat {
+    /// `number_of_objects,max_weight,max_size` header followed by `weight,size,cost` lines
+    Positional,
+    /// `# max_weight = ...` and `# max_size = ...` lines followed by a CSV table
+    /// with `id`, `weight`, `size`, `cost` and optional `name` columns.
+    /// Other `# key = value` lines are metadata.
+    Table,
+    /// `{"capacities": {"max_weight": .., "max_size": ..}, "metadata": {..},
+    /// "items": [{"id": .., "name": .., "weight": .., "size": .., "cost": ..}]}`
+    Json,
+    /// Versioned little-endian header followed by the `weights`, `sizes` and
+    /// `costs` columns and the labels as JSON. Loaded through `binary::MappedScenario`.
+    Binary,
+}
+
+impl ScenarioFormat {
+    /// Guesses the format from the extension and the first line of the file
+    ///
+    /// Headers of positional files only contain numbers.
+    pub fn detect(path: &str, content: &str) -> ScenarioFormat {
+        let has_extension = |extension: &str| {
+            Path::new(path)
+                .extension()
+                .is_some_and(|x| x.eq_ignore_ascii_case(extension))
+        };
+        let first_line = content.lines().next().unwrap_or("").trim();
+        let first_field = first_line.split(',').next().unwrap_or("").trim();
+
+        if has_extension("bin") || content.as_bytes().starts_with(binary::MAGIC) {
+            ScenarioFormat::Binary
+        } else if has_extension("json") || first_line.starts_with('{') {
+            ScenarioFormat::Json
+        } else if first_line.starts_with('#')
+            || first_field.parse::<f64>().is_err() && !first_field.is_empty()
+        {
+            ScenarioFormat::Table
+        } else {
+            ScenarioFormat::Positional
+        }
+    }
+
+    /// Detects the format of an existing file reading only its first line
+    pub fn of_file(path: &str) -> io::Result<ScenarioFormat> {
+        if binary::is_binary(path)? {
+            return Ok(ScenarioFormat::Binary);
+        }
+
+        let mut first_line = Vec::new();
+        BufReader::new(File::open(path)?).read_until(b'\n', &mut first_line)?;
+        Ok(ScenarioFormat::detect(
+            path,
+            &String::from_utf8_lossy(&first_line),
+        ))
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ScenarioFormat::Positional => "positional",
+            ScenarioFormat::Table => "table",
+            ScenarioFormat::Json => "json",
+            ScenarioFormat::Binary => "binary",
+        }
+    }
+}
+
+/// Descriptions of the objects and of the scenario itself
+///
+/// They are not used by the search, only to present its results.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Labels {
+    /// Identifier of each object, empty if the file has none
+    pub ids: Vec<String>,
+    /// Name of each object, empty if the file has none
+    pub names: Vec<Option<String>>,
+    pub metadata: BTreeMap<String, String>,
+}
+
+impl Labels {
+    /// Identifier of the object, its index if the file has none
+    pub fn id(&self, i: usize) -> String {
+        match self.ids.get(i) {
+            Some(id) => id.clone(),
+            None => i.to_string(),
+        }
+    }
+
+    /// Labels of the objects with given indices
+    pub fn subset(&self, indices: &[usize]) -> Labels {
+        let select = |values: &[String]| {
+            if values.is_empty() {
+                Vec::new()
+            } else {
+                indices.iter().map(|i| values[*i].clone()).collect()
+            }
+        };
+
+        Labels {
+            ids: select(&self.ids),
+            names: if self.names.is_empty() {
+                Vec::new()
+            } else {
+                indices.iter().map(|i| self.names[*i].clone()).collect()
+            },
+            metadata: self.metadata.clone(),
+        }
+    }
+}
+
+/// Knapsack problem instance
+///
+/// All sums of `weights`, `sizes` and `costs` are guaranteed to be representable
+/// in `N` so evaluation of any individual cannot overflow.
+#[derive(Debug, Clone)]
+pub struct Scenario<N: Number> {
+    pub weights: Column<N>,
+    pub sizes: Column<N>,
+    pub costs: Column<N>,
+    pub number_of_objects: usize,
+    pub max_weight: N,
+    pub max_size: N,
+    /// How much total weight and size may exceed their limits and still
+    /// be considered feasible. Absorbs rounding errors of floating point sums.
+    pub tolerance: N,
+    pub labels: Labels,
+}
+
+impl<N: Number> Scenario<N> {
+    pub fn load(input_file: String) -> Result<Scenario<N>, ScenarioErrors> {
+        Scenario::load_with(input_file, &LoadOptions::default())
+    }
+
+    /// Loads scenario from a file in any of the `ScenarioFormat`s
+    ///
+    /// Columns of binary scenarios stay in the memory-mapped file, text
+    /// formats are parsed into owned columns.
+    pub fn load_with(
+        input_file: String,
+        options: &LoadOptions,
+    ) -> Result<Scenario<N>, ScenarioErrors> {
+        let path = input_file.as_str();
+        let io_error = |err| ScenarioError::new(path, Io(err));
+
+        let format = ScenarioFormat::of_file(path).map_err(io_error)?;
+        if format == ScenarioFormat::Binary {
+            return MappedScenario::open(path)?.to_scenario();
+        }
+
+        let content = fs::read_to_string(path).map_err(io_error)?;
+        let errors = Errors::new(path, options.collect_errors);
+
+        match format {
+            ScenarioFormat::Positional => load_positional(&content, errors),
+            ScenarioFormat::Table => table::load(&content, errors),
+            ScenarioFormat::Json => json::load(&content, errors),
+            ScenarioFormat::Binary => unreachable!(),
+        }
+    }
+
+    /// Saves scenario in the JSON format if `output_file` has the `.json`
+    /// extension, in the binary format if it has the `.bin` extension
+    /// or in the positional format otherwise
+    ///
+    /// The file is written under a temporary name and renamed once complete,
+    /// so an interrupted save never leaves a truncated scenario behind.
+    pub fn save(&self, output_file: &str) -> io::Result<()> {
+        let tmp_file = format!("{}.tmp", output_file);
+
+        let format = ScenarioFormat::detect(output_file, "");
+
+        match self.write_to(&tmp_file, format) {
+            Ok(()) => fs::rename(&tmp_file, output_file),
+            Err(err) => {
+                let _ = fs::remove_file(&tmp_file);
+                Err(err)
+            }
+        }
+    }
+
+    fn write_to(&self, path: &str, format: ScenarioFormat) -> io::Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+
+        match format {
+            ScenarioFormat::Json => {
+                json::write(self, &mut file)?;
+                return file.into_inner()?.sync_all();
+            }
+            ScenarioFormat::Binary => {
+                binary::write(self, &mut file)?;
+                return file.into_inner()?.sync_all();
+            }
+            _ => {}
+        }
+
+        writeln!(
+            file,
+            "{},{},{}",
+            self.number_of_objects, self.max_weight, self.max_size
+        )?;
+        for i in 0..self.number_of_objects {
+            writeln!(
+                file,
+                "{},{},{}",
+                self.weights[i], self.sizes[i], self.costs[i]
+            )?;
+        }
+
+        file.into_inner()?.sync_all()
+    }
+
+    /// Sets feasibility tolerance
+    ///
+    /// Fails if the weight or size limit raised by `tolerance` does not fit
+    /// into `N`.
+    pub fn with_tolerance(mut self, tolerance: N) -> Result<Scenario<N>, String> {
+        for (name, max) in [("weight", self.max_weight), ("size", self.max_size)].iter() {
+            if max.checked_add(tolerance).is_none() {
+                return Err(format!(
+                    "Tolerance {} makes the {} limit overflow {}",
+                    tolerance,
+                    name,
+                    N::NAME
+                ));
+            }
+        }
+
+        self.tolerance = tolerance;
+        Ok(self)
+    }
+
+    /// The largest total weight considered feasible
+    pub fn weight_limit(&self) -> N {
+        self.max_weight + self.tolerance
+    }
+
+    /// The largest total size considered feasible
+    pub fn size_limit(&self) -> N {
+        self.max_size + self.tolerance
+    }
+}
+
+const COLUMNS: [&str; 3] = ["weight", "size", "cost"];
+
+/// Loads scenario in the `ScenarioFormat::Positional` format
+fn load_positional<N: Number>(
+    content: &str,
+    mut errors: Errors,
+) -> Result<Scenario<N>, ScenarioErrors> {
+    let mut lines = content.lines();
+
+    let header = match lines.next() {
+        Some(line) => line,
+        None => return Err(ScenarioError::at(errors.path, 1, 1, NoHeader).into()),
+    };
+    let header = fields(header);
+    if header.len() != 3 {
+        let found = header.len();
+        return Err(ScenarioError::at(errors.path, 1, 1, HeaderLengthOtherThen3 { found }).into());
+    }
+
+    let at = |(column, _): (usize, &str)| Place::at(1, column);
+    let number_of_objects = errors.parse_usize(at(header[0]), header[0].1)?;
+    let max_weight = errors.parse_number::<N>(at(header[1]), header[1].1)?;
+    let max_size = errors.parse_number::<N>(at(header[2]), header[2].1)?;
+
+    let mut loaded = Loaded::new();
+    let mut actual = 0;
+
+    for (line_idx, line) in lines.enumerate() {
+        // header is the first line
+        let line_number = line_idx + 2;
+        actual += 1;
+
+        let line = fields(line);
+        if line.len() != 3 {
+            let found = line.len();
+            errors.push(Place::at(line_number, 1), LineLengthOtherThen3 { found })?;
+            continue;
+        }
+
+        let places = [0, 1, 2].map(|i| Place::at(line_number, line[i].0));
+        loaded.parse(&mut errors, [line[0].1, line[1].1, line[2].1], places)?;
+    }
+
+    if let Some(declared) = number_of_objects {
+        if declared != actual {
+            let kind = IncorrectNumberOfObjects { declared, actual };
+            errors.push(at(header[0]), kind)?;
+        }
+    }
+
+    loaded.finish(errors, max_weight, max_size, [at(header[1]), at(header[2])])
+}
+
+/// Position of a value in the scenario file
+#[derive(Debug, Clone, Default)]
+struct Place {
+    line: Option<usize>,
+    column: Option<usize>,
+    field: Option<String>,
+}
+
+impl Place {
+    fn at(line: usize, column: usize) -> Place {
+        Place {
+            line: Some(line),
+            column: Some(column),
+            field: None,
+        }
+    }
+
+    fn field(field: String) -> Place {
+        Place {
+            field: Some(field),
+            ..Place::default()
+        }
+    }
+
+    /// Named field in given line
+    fn cell(line: usize, field: &str) -> Place {
+        Place {
+            line: Some(line),
+            column: None,
+            field: Some(field.to_string()),
+        }
+    }
+}
+
+/// Objects loaded so far
+struct Loaded<N: Number> {
+    weights: Vec<N>,
+    sizes: Vec<N>,
+    costs: Vec<N>,
+    /// Running sums of the columns, `None` once they overflow
+    sums: [Option<N>; 3],
+    labels: Labels,
+    used_ids: HashSet<String>,
+}
+
+impl<N: Number> Loaded<N> {
+    fn new() -> Loaded<N> {
+        Loaded {
+            weights: Vec::new(),
+            sizes: Vec::new(),
+            costs: Vec::new(),
+            sums: [Some(N::zero()); 3],
+            labels: Labels::default(),
+            used_ids: HashSet::new(),
+        }
+    }
+
+    /// Parses and adds an object, `places` point at its weight, size and cost
+    ///
+    /// Returns `false` if the object is invalid and errors are collected.
+    fn parse(
+        &mut self,
+        errors: &mut Errors,
+        fields: [&str; 3],
+        places: [Place; 3],
+    ) -> Result<bool, ScenarioErrors> {
+        let mut values = [N::zero(); 3];
+        let mut valid = true;
+        for (i, (field, place)) in fields.iter().zip(places.iter()).enumerate() {
+            match errors.parse_number::<N>(place.clone(), field)? {
+                Some(value) => values[i] = value,
+                None => valid = false,
+            }
+        }
+
+        if !valid {
+            return Ok(false);
+        }
+
+        self.push(errors, values, places)?;
+        Ok(true)
+    }
+
+    /// Adds an object with already validated values
+    fn push(
+        &mut self,
+        errors: &mut Errors,
+        values: [N; 3],
+        places: [Place; 3],
+    ) -> Result<(), ScenarioErrors> {
+        for (i, (sum, value)) in self.sums.iter_mut().zip(values.iter()).enumerate() {
+            // report overflow of each column only once
+            if let Some(acc) = *sum {
+                *sum = acc.checked_add(*value);
+                if sum.is_none() {
+                    let overflow = SumOverflow {
+                        column: COLUMNS[i],
+                        number_type: N::NAME,
+                    };
+                    errors.push(places[i].clone(), overflow)?;
+                }
+            }
+        }
+
+        self.weights.push(values[0]);
+        self.sizes.push(values[1]);
+        self.costs.push(values[2]);
+
+        Ok(())
+    }
+
+    /// Whether an earlier object has id `id`
+    fn has_id(&self, id: &str) -> bool {
+        self.used_ids.contains(id)
+    }
+
+    /// Adds id and name of the last object
+    fn label(
+        &mut self,
+        errors: &mut Errors,
+        id: String,
+        name: Option<String>,
+        place: Place,
+    ) -> Result<(), ScenarioErrors> {
+        if id.is_empty() {
+            errors.push(place.clone(), EmptyId)?;
+        } else if !self.used_ids.insert(id.clone()) {
+            errors.push(place.clone(), DuplicateId { id: id.clone() })?;
+        }
+
+        self.labels.ids.push(id);
+        self.labels.names.push(name);
+        Ok(())
+    }
+
+    /// Checks totals against the capacities and builds the scenario
+    fn finish(
+        self,
+        mut errors: Errors,
+        max_weight: Option<N>,
+        max_size: Option<N>,
+        places: [Place; 2],
+    ) -> Result<Scenario<N>, ScenarioErrors> {
+        let [weight_place, size_place] = places;
+
+        // totals are only meaningful if every object was loaded
+        if errors.is_empty() {
+            let (total_weight, total_size) = (self.sums[0].unwrap(), self.sums[1].unwrap());
+            let (max_weight, max_size) = (max_weight.unwrap(), max_size.unwrap());
+
+            match max_weight.checked_add(max_weight) {
+                Some(minimal) if total_weight <= minimal => {
+                    let kind = TotalWeightToSmall {
+                        minimal: minimal.to_string(),
+                        total_weight: total_weight.to_string(),
+                    };
+                    errors.push(weight_place, kind)?;
+                }
+                Some(_) => {}
+                None => errors.push(weight_place, header_overflow::<N>("weight"))?,
+            }
+
+            match max_size.checked_add(max_size) {
+                Some(minimal) if total_size <= minimal => {
+                    let kind = TotalSizeToSmall {
+                        minimal: minimal.to_string(),
+                        total_size: total_size.to_string(),
+                    };
+                    errors.push(size_place, kind)?;
+                }
+                Some(_) => {}
+                None => errors.push(size_place, header_overflow::<N>("size"))?,
+            }
+        }
+
+        errors.finish()?;
+
+        Ok(Scenario {
+            number_of_objects: self.weights.len(),
+            max_weight: max_weight.unwrap(),
+            max_size: max_size.unwrap(),
+            weights: self.weights.into(),
+            sizes: self.sizes.into(),
+            costs: self.costs.into(),
+            tolerance: N::zero(),
+            labels: self.labels,
+        })
+    }
+}
+
+fn header_overflow<N: Number>(column: &'static str) -> ScenarioErrorKind {
+    SumOverflow {
+        column,
+        number_type: N::NAME,
+    }
+}
+
+/// Splits the line by commas
+///
+/// Returns trimmed fields together with 1-based columns at which they start.
+fn fields(line: &str) -> Vec<(usize, &str)> {
+    let mut start = 0;
+
+    line.split(',')
+        .map(|field| {
+            let trimmed = field.trim_start();
+            let offset = start + field.len() - trimmed.len();
+            start += field.len() + 1;
+
+            (line[..offset].chars().count() + 1, trimmed.trim_end())
+        })
+        .collect()
+}
+
+/// Returns `false` for negative numbers and NaN
+fn is_non_negative<N: Number>(value: N) -> bool {
+    match value.partial_cmp(&N::zero()) {
+        Some(Ordering::Greater) | Some(Ordering::Equal) => true,
+        Some(Ordering::Less) | None => false,
+    }
+}
+
+/// Errors found so far while loading a scenario
+struct Errors<'a> {
+    path: &'a str,
+    collect: bool,
+    errors: Vec<ScenarioError>,
+}
+
+impl<'a> Errors<'a> {
+    fn new(path: &'a str, collect: bool) -> Errors<'a> {
+        Errors {
+            path,
+            collect,
+            errors: Vec::new(),
+        }
+    }
+
+    /// Records the error
+    ///
+    /// If errors are not collected it returns the error right away
+    /// so it can be propagated with `?`.
+    fn push(&mut self, place: Place, kind: ScenarioErrorKind) -> Result<(), ScenarioErrors> {
+        let err = ScenarioError {
+            path: self.path.to_string(),
+            line: place.line,
+            column: place.column,
+            field: place.field,
+            kind,
+        };
+
+        if self.collect {
+            self.errors.push(err);
+            Ok(())
+        } else {
+            Err(err.into())
+        }
+    }
+
+    /// Parses number of objects
+    ///
+    /// Returns `None` if the field is invalid and errors are collected.
+    fn parse_usize(&mut self, place: Place, value: &str) -> Result<Option<usize>, ScenarioErrors> {
+        match value.parse::<usize>() {
+            Ok(value) => Ok(Some(value)),
+            Err(err) => {
+                let kind = InvalidNumber {
+                    value: value.to_string(),
+                    number_type: "usize",
+                    reason: err.to_string(),
+                };
+                self.push(place, kind).map(|_| None)
+            }
+        }
+    }
+
+    /// Parses non negative number
+    ///
+    /// Returns `None` if the field is invalid and errors are collected.
+    fn parse_number<N: Number>(
+        &mut self,
+        place: Place,
+        value: &str,
+    ) -> Result<Option<N>, ScenarioErrors> {
+        let kind = match N::parse(value) {
+            Ok(number) if is_non_negative(number) => return Ok(Some(number)),
+            Ok(_) => NegativeValue {
+                value: value.to_string(),
+            },
+            Err(err) => InvalidNumber {
+                value: value.to_string(),
+                number_type: N::NAME,
+                reason: err.to_string(),
+            },
+        };
+
+        self.push(place, kind).map(|_| None)
+    }
+
+    /// Records an error after which loading cannot continue and returns all errors
+    fn fail(&mut self, place: Place, kind: ScenarioErrorKind) -> ScenarioErrors {
+        match self.push(place, kind) {
+            Ok(()) => ScenarioErrors(self.errors.drain(..).collect()),
+            Err(err) => err,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    fn finish(self) -> Result<(), ScenarioErrors> {
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ScenarioErrors(self.errors))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env::temp_dir;
+    use std::path::Path;
+    use std::process;
+
+    fn write_scenario(name: &str, content: &str) -> String {
+        let path = temp_dir().join(format!("genet_{}_{}.txt", name, process::id()));
+        std::fs::write(&path, content).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn load_detects_overflowing_sums() {
+        let path = write_scenario(
+            "overflow",
+            "3,10,1\n2000000000,5,1\n2000000000,5,1\n2000000000,5,1\n",
+        );
+
+        assert!(Scenario::<i32>::load(path.clone()).is_err());
+        assert!(Scenario::<i64>::load(path.clone()).is_ok());
+        assert!(Scenario::<f64>::load(path).is_ok());
+    }
+
+    #[test]
+    fn tolerance_cannot_overflow_the_limits() {
+        let path = write_scenario("tolerance", "3,10,1\n8,5,1\n8,5,1\n8,5,1\n");
+        let load = || Scenario::<i32>::load(path.clone()).unwrap();
+
+        assert!(load().with_tolerance(i32::MAX - 10).is_ok());
+        assert!(load().with_tolerance(i32::MAX - 9).is_err());
+    }
+
+    #[test]
+    fn load_rejects_negative_values() {
+        let path = write_scenario("negative", "3,1,1\n2,2,1\n2,2,-1\n2,2,1\n");
+
+        assert!(Scenario::<i64>::load(path.clone()).is_err());
+        // and u64 cannot even parse it
+        assert!(Scenario::<u64>::load(path).is_err());
+    }
+
+    #[test]
+    fn load_parses_fractional_values() {
+        let path = write_scenario(
+            "fractional",
+            "3, 2.5, 1.25\r\n1.75, 0.5, 10.1\r\n2.25, 0.75, 3.3\r\n1.5, 1.5, 0.01\r\n",
+        );
+
+        let scenario = Scenario::<f64>::load(path.clone()).unwrap();
+        assert_eq!(scenario.max_weight, 2.5);
+        assert_eq!(scenario.weights.as_slice(), &[1.75, 2.25, 1.5]);
+        assert_eq!(scenario.costs.as_slice(), &[10.1, 3.3, 0.01]);
+
+        // fractional values are not valid integers
+        assert!(Scenario::<i64>::load(path).is_err());
+    }
+
+    #[test]
+    fn errors_point_at_the_invalid_field() {
+        let path = write_scenario("position", "3,10,1\n2,2,1\n2, x,1\n2,2,1\n");
+
+        let errors = Scenario::<i64>::load(path.clone()).unwrap_err();
+        assert_eq!(errors.0.len(), 1);
+
+        let err = &errors.0[0];
+        assert_eq!((err.line, err.column), (Some(3), Some(4)));
+        assert!(err
+            .to_string()
+            .starts_with(&format!("{}:3:4: `x` is not a valid i64", path)));
+    }
+
+    #[test]
+    fn load_collects_all_errors() {
+        let path = write_scenario("collect", "4,10,1\n2,2,1\n2,x,1\n-2,2,1,5\n1,1,y\n2,2,1\n");
+        let options = LoadOptions {
+            collect_errors: true,
+        };
+
+        let errors = Scenario::<i64>::load_with(path.clone(), &options).unwrap_err();
+        let positions = errors
+            .0
+            .iter()
+            .map(|err| (err.line.unwrap(), err.column.unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(positions, vec![(3, 3), (4, 1), (5, 5), (1, 1)]);
+
+        // without the switch loading stops at the first error
+        let errors = Scenario::<i64>::load(path).unwrap_err();
+        assert_eq!(errors.0.len(), 1);
+    }
+
+    #[test]
+    fn total_weight_error_points_at_max_weight() {
+        let path = write_scenario("total", "2,10,1\n2,2,1\n2,2,1\n");
+
+        let errors = Scenario::<i64>::load(path).unwrap_err();
+        let err = &errors.0[0];
+        assert_eq!((err.line, err.column), (Some(1), Some(3)));
+        assert!(matches!(err.kind, TotalWeightToSmall { .. }));
+    }
+
+    #[test]
+    fn load_rejects_nan() {
+        let path = write_scenario("nan", "3,1,1\n2,2,1\n2,NaN,1\n2,2,1\n");
+
+        assert!(Scenario::<f64>::load(path).is_err());
+    }
+
+    #[test]
+    fn saved_scenario_loads_back() {
+        let path = write_scenario("save", "");
+        let scenario = Scenario::<f64> {
+            weights: vec![4.5, 7.0].into(),
+            sizes: vec![1.0, 2.25].into(),
+            costs: vec![3.0, 8.0].into(),
+            number_of_objects: 2,
+            max_weight: 5.5,
+            max_size: 1.5,
+            tolerance: 0.0,
+            labels: Labels::default(),
+        };
+
+        scenario.save(&path).unwrap();
+        let loaded = Scenario::<f64>::load(path.clone()).unwrap();
+
+        assert_eq!(loaded.weights, scenario.weights);
+        assert_eq!(loaded.sizes, scenario.sizes);
+        assert_eq!(loaded.costs, scenario.costs);
+        assert_eq!(loaded.max_weight, 5.5);
+        assert!(!Path::new(&format!("{}.tmp", path)).exists());
+    }
+}
@@ -0,0 +1,301 @@
+use crate::number::Number;
+use crate::scenario::analysis::Objects;
+use crate::scenario::column::Column;
+use crate::scenario::Scenario;
+
+/// Scenario with the objects which can be decided upfront taken out
+///
+/// At least one optimal solution of the original scenario consists of
+/// the `chosen` objects and an optimal solution of the `core` scenario.
+#[derive(Debug)]
+pub struct Reduction<N: Number> {
+    /// Remaining objects with limits lowered by the `chosen` ones
+    pub core: Scenario<N>,
+    /// Original index of each object of the `core`
+    pub core_objects: Vec<usize>,
+    /// Objects which are part of every solution worth more then the greedy one
+    pub chosen: Vec<usize>,
+    /// Total cost of the `chosen` objects
+    pub chosen_cost: N,
+    /// Objects heavier or larger then the limits
+    pub never_fit: Vec<usize>,
+    /// Dominated objects which cannot fit together with all their dominators
+    pub dominated: Vec<usize>,
+    /// Objects which are not part of any solution worth more then the greedy one
+    pub excluded: Vec<usize>,
+}
+
+impl<N: Number> Reduction<N> {
+    /// Maps indices of objects of the `core` to the indices of the original
+    /// scenario and adds the `chosen` objects
+    pub fn expand(&self, core_solution: &[usize]) -> Vec<usize> {
+        let mut solution = core_solution
+            .iter()
+            .map(|i| self.core_objects[*i])
+            .chain(self.chosen.iter().cloned())
+            .collect::<Vec<_>>();
+
+        solution.sort_unstable();
+        solution
+    }
+}
+
+impl<N: Number> Scenario<N> {
+    /// Decides objects which do not need to be searched for
+    ///
+    /// 1. Objects which do not fit on their own are dropped.
+    /// 2. Dominated objects are dropped if they cannot fit together with all
+    ///    of their dominators. Any solution containing such an object misses
+    ///    one of its dominators which can take its place.
+    /// 3. Reduced costs of the LP relaxation tell how much the upper bound
+    ///    drops when an object is forced in or out. If it drops below the cost
+    ///    of the greedy solution the object's state is fixed.
+    pub fn reduce(&self) -> Reduction<N> {
+        let objects = Objects::new(self);
+
+        let never_fit = (0..objects.len())
+            .filter(|i| {
+                objects.weights[*i] > objects.max_weight || objects.sizes[*i] > objects.max_size
+            })
+            .collect::<Vec<_>>();
+        let dominated = removable_dominated(&objects)
+            .into_iter()
+            .filter(|i| never_fit.binary_search(i).is_err())
+            .collect::<Vec<_>>();
+
+        let mut dropped = vec![false; objects.len()];
+        for i in never_fit.iter().chain(dominated.iter()) {
+            dropped[*i] = true;
+        }
+        let remaining = (0..objects.len())
+            .filter(|i| !dropped[*i])
+            .collect::<Vec<_>>();
+
+        let (chosen, excluded) = fix_by_reduced_costs(&objects.subset(&remaining));
+        let chosen = chosen.iter().map(|i| remaining[*i]).collect::<Vec<_>>();
+        let excluded = excluded.iter().map(|i| remaining[*i]).collect::<Vec<_>>();
+
+        let (chosen, excluded) = match self.chosen_limits(&chosen) {
+            Some(_) => (chosen, excluded),
+            // rounding errors of f64 may cause too many objects to be chosen,
+            // keeping all of them in the core is always correct
+            None => (Vec::new(), Vec::new()),
+        };
+        let ((max_weight, weight_tolerance), (max_size, size_tolerance)) =
+            self.chosen_limits(&chosen).unwrap();
+
+        for i in chosen.iter().chain(excluded.iter()) {
+            dropped[*i] = true;
+        }
+        let core_objects = (0..objects.len())
+            .filter(|i| !dropped[*i])
+            .collect::<Vec<_>>();
+
+        let select = |values: &[N]| {
+            let values = core_objects.iter().map(|i| values[*i]).collect::<Vec<_>>();
+            Column::from(values)
+        };
+        let core = Scenario {
+            weights: select(&self.weights),
+            sizes: select(&self.sizes),
+            costs: select(&self.costs),
+            number_of_objects: core_objects.len(),
+            max_weight,
+            max_size,
+            // both limits share a single tolerance so it has to be the smaller one
+            tolerance: if weight_tolerance < size_tolerance {
+                weight_tolerance
+            } else {
+                size_tolerance
+            },
+            labels: self.labels.subset(&core_objects),
+        };
+
+        Reduction {
+            chosen_cost: sum(&self.costs, &chosen),
+            core,
+            core_objects,
+            chosen,
+            never_fit,
+            dominated,
+            excluded,
+        }
+    }
+
+    /// Limits and tolerances left after taking the `chosen` objects
+    ///
+    /// Returns `None` if they do not fit.
+    #[allow(clippy::type_complexity)]
+    fn chosen_limits(&self, chosen: &[usize]) -> Option<((N, N), (N, N))> {
+        let weight = remaining_limit(self.max_weight, self.tolerance, sum(&self.weights, chosen))?;
+        let size = remaining_limit(self.max_size, self.tolerance, sum(&self.sizes, chosen))?;
+
+        Some((weight, size))
+    }
+}
+
+/// Column sums fit into `N` so sums of their subsets do as well
+fn sum<N: Number>(values: &[N], indices: &[usize]) -> N {
+    indices.iter().fold(N::zero(), |acc, i| acc + values[*i])
+}
+
+/// Lowers `limit` by `used`, eating into the `tolerance` only when necessary
+fn remaining_limit<N: Number>(limit: N, tolerance: N, used: N) -> Option<(N, N)> {
+    if used <= limit {
+        Some((limit - used, tolerance))
+    } else if used <= limit + tolerance {
+        Some((N::zero(), limit + tolerance - used))
+    } else {
+        None
+    }
+}
+
+/// Dominated objects which can be dropped without losing all optimal solutions
+///
+/// Takes O(n) per dominated object as the dominators are summed up until
+/// they no longer fit together with the object.
+fn removable_dominated(objects: &Objects) -> Vec<usize> {
+    objects
+        .dominated()
+        .into_iter()
+        .filter(|i| {
+            let (i, mut weight, mut size) = (*i, objects.weights[*i], objects.sizes[*i]);
+
+            (0..objects.len()).any(|j| {
+                if objects.dominates(j, i) {
+                    weight += objects.weights[j];
+                    size += objects.sizes[j];
+                }
+                weight > objects.max_weight || size > objects.max_size
+            })
+        })
+        .collect()
+}
+
+/// Returns objects which have to be chosen and excluded according to their reduced costs
+fn fix_by_reduced_costs(objects: &Objects) -> (Vec<usize>, Vec<usize>) {
+    if objects.len() == 0 {
+        return (Vec::new(), Vec::new());
+    }
+
+    let lp = objects.lp_relaxation();
+    let greedy = objects.greedy();
+    // f64 sums are not exact, do not fix objects on the edge
+    let margin = 1e-9 * lp.bound.abs().max(1.0);
+
+    let mut chosen = Vec::new();
+    let mut excluded = Vec::new();
+    for i in 0..objects.len() {
+        let reduced_cost = objects.reduced_cost(&lp, i);
+
+        // forcing the object into the solution or out of it lowers
+        // the bound by the absolute value of its reduced cost
+        if lp.bound - reduced_cost.abs() < greedy - margin {
+            if reduced_cost > 0.0 {
+                chosen.push(i);
+            } else {
+                excluded.push(i);
+            }
+        }
+    }
+
+    (chosen, excluded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scenario::Labels;
+
+    fn scenario(objects: &[(i64, i64, i64)], max_weight: i64, max_size: i64) -> Scenario<i64> {
+        Scenario {
+            weights: objects.iter().map(|x| x.0).collect::<Vec<_>>().into(),
+            sizes: objects.iter().map(|x| x.1).collect::<Vec<_>>().into(),
+            costs: objects.iter().map(|x| x.2).collect::<Vec<_>>().into(),
+            number_of_objects: objects.len(),
+            max_weight,
+            max_size,
+            tolerance: 0,
+            labels: Labels::default(),
+        }
+    }
+
+    /// Cost of the optimal solution found by checking every subset
+    fn optimum(scenario: &Scenario<i64>) -> i64 {
+        (0..1u32 << scenario.number_of_objects)
+            .map(|set| {
+                let objects = (0..scenario.number_of_objects)
+                    .filter(|i| set & (1 << i) != 0)
+                    .collect::<Vec<_>>();
+                (
+                    sum(&scenario.weights, &objects),
+                    sum(&scenario.sizes, &objects),
+                    sum(&scenario.costs, &objects),
+                )
+            })
+            .filter(|(weight, size, _)| {
+                *weight <= scenario.weight_limit() && *size <= scenario.size_limit()
+            })
+            .map(|(_, _, cost)| cost)
+            .max()
+            .unwrap()
+    }
+
+    #[test]
+    fn dominated_object_is_kept_if_it_fits_with_its_dominator() {
+        // object 1 is dominated by object 0 but the optimum takes both
+        let scenario = scenario(&[(1, 1, 5), (2, 2, 4), (9, 9, 1)], 3, 3);
+        let reduction = scenario.reduce();
+
+        assert!(reduction.dominated.is_empty());
+        assert_eq!(reduction.never_fit, vec![2]);
+    }
+
+    #[test]
+    fn dominated_object_is_dropped_if_it_cannot_fit_with_its_dominator() {
+        let scenario = scenario(&[(2, 2, 5), (2, 3, 4), (1, 1, 1)], 4, 4);
+
+        assert_eq!(scenario.reduce().dominated, vec![1]);
+    }
+
+    #[test]
+    fn reduction_preserves_the_optimum() {
+        use rand::rngs::StdRng;
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..200 {
+            let objects = (0..12)
+                .map(|_| {
+                    let weight = rng.gen_range(1, 20);
+                    let size = rng.gen_range(1, 20);
+                    (weight, size, weight + size + rng.gen_range(0, 10))
+                })
+                .collect::<Vec<_>>();
+            let scenario = scenario(&objects, rng.gen_range(10, 60), rng.gen_range(10, 60));
+
+            let reduction = scenario.reduce();
+            let core_optimum = if reduction.core.number_of_objects == 0 {
+                0
+            } else {
+                optimum(&reduction.core)
+            };
+
+            assert_eq!(
+                core_optimum + reduction.chosen_cost,
+                optimum(&scenario),
+                "{:?}",
+                objects
+            );
+        }
+    }
+
+    #[test]
+    fn core_solution_maps_back_to_original_indices() {
+        let scenario = scenario(&[(1, 1, 5), (9, 9, 1), (1, 1, 5), (1, 1, 5)], 2, 2);
+        let reduction = scenario.reduce();
+
+        assert_eq!(reduction.core_objects, vec![0, 2, 3]);
+        assert_eq!(reduction.expand(&[0, 2]), vec![0, 3]);
+    }
+}
@@ -0,0 +1,517 @@
+use crate::number::Number;
+use crate::scenario::solver::{is_solver_solution, parse_solver_solution, SolverSolution};
+use crate::scenario::Scenario;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::io;
+use std::str::FromStr;
+
+/// How chosen objects are listed in a solution file
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SolutionFormat {
+    /// 0-based indices of the chosen objects
+    Indices,
+    /// Ids of the chosen objects as given by the scenario
+    Ids,
+    /// One 0 or 1 per object of the scenario
+    Bits,
+    /// Solution file of an external solver for an exported scenario
+    Solver,
+}
+
+impl SolutionFormat {
+    pub const VARIANTS: [&'static str; 4] = ["indices", "ids", "bits", "solver"];
+
+    const ALL: [SolutionFormat; 4] = [
+        SolutionFormat::Indices,
+        SolutionFormat::Ids,
+        SolutionFormat::Bits,
+        SolutionFormat::Solver,
+    ];
+
+    /// Bits if there is a 0 or 1 for every object, ids if the scenario has
+    /// ids and all values are among them, indices otherwise
+    fn detect<N: Number>(tokens: &[Token], scenario: &Scenario<N>) -> SolutionFormat {
+        let ids = &scenario.labels.ids;
+
+        if tokens.len() == scenario.number_of_objects
+            && tokens.iter().all(|x| x.value == "0" || x.value == "1")
+        {
+            SolutionFormat::Bits
+        } else if !ids.is_empty() && tokens.iter().all(|x| ids.iter().any(|id| *id == x.value)) {
+            SolutionFormat::Ids
+        } else {
+            SolutionFormat::Indices
+        }
+    }
+}
+
+impl FromStr for SolutionFormat {
+    type Err = UnknownSolutionFormat;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SolutionFormat::VARIANTS
+            .iter()
+            .position(|name| *name == s)
+            .map(|i| SolutionFormat::ALL[i])
+            .ok_or_else(|| UnknownSolutionFormat(s.to_string()))
+    }
+}
+
+#[derive(Debug)]
+pub struct UnknownSolutionFormat(String);
+
+impl Error for UnknownSolutionFormat {}
+
+impl Display for UnknownSolutionFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "unknown solution format {}, expected one of {}",
+            self.0,
+            SolutionFormat::VARIANTS.join(", ")
+        )
+    }
+}
+
+/// Value of a solution file with its 1-based line
+struct Token<'a> {
+    line: usize,
+    value: &'a str,
+}
+
+/// Reads indices of the chosen objects from a solution file
+///
+/// Values are separated by commas or whitespace, `#` starts a comment.
+/// The `Best objects` prefix printed by `train` is skipped so its output
+/// can be verified as is. The format is detected if not given.
+pub fn load_solution<N: Number>(
+    solution_file: &str,
+    scenario: &Scenario<N>,
+    format: Option<SolutionFormat>,
+) -> Result<Vec<usize>, SolutionError> {
+    let content = read(solution_file)?;
+
+    let is_solver = match format {
+        Some(format) => format == SolutionFormat::Solver,
+        None => is_solver_solution(&content),
+    };
+    let objects = if is_solver {
+        parse_solver_solution(&content, scenario.number_of_objects).map(|x| x.objects)
+    } else {
+        parse_solution(&content, scenario, format)
+    };
+
+    objects.map_err(|(line, kind)| SolutionError::new(solution_file, line, kind))
+}
+
+/// Reads solution file written by an external solver for a scenario exported
+/// with `Scenario::write_solver`
+pub fn load_solver_solution<N: Number>(
+    solution_file: &str,
+    scenario: &Scenario<N>,
+) -> Result<SolverSolution, SolutionError> {
+    let content = read(solution_file)?;
+
+    parse_solver_solution(&content, scenario.number_of_objects)
+        .map_err(|(line, kind)| SolutionError::new(solution_file, line, kind))
+}
+
+fn read(path: &str) -> Result<String, SolutionError> {
+    fs::read_to_string(path)
+        .map_err(|err| SolutionError::new(path, None, SolutionErrorKind::Io(err)))
+}
+
+fn parse_solution<N: Number>(
+    content: &str,
+    scenario: &Scenario<N>,
+    format: Option<SolutionFormat>,
+) -> Result<Vec<usize>, (Option<usize>, SolutionErrorKind)> {
+    let tokens = content
+        .lines()
+        .enumerate()
+        .flat_map(|(i, line)| {
+            let line_content = line.split('#').next().unwrap_or("").trim();
+            let line_content = line_content
+                .strip_prefix("Best objects")
+                .unwrap_or(line_content);
+
+            line_content
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|x| !x.is_empty())
+                .map(move |value| Token { line: i + 1, value })
+        })
+        .collect::<Vec<_>>();
+
+    let format = format.unwrap_or_else(|| SolutionFormat::detect(&tokens, scenario));
+    let n = scenario.number_of_objects;
+
+    let objects = match format {
+        SolutionFormat::Solver => unreachable!(),
+        SolutionFormat::Bits => {
+            if tokens.len() != n {
+                let kind = SolutionErrorKind::BitCount {
+                    found: tokens.len(),
+                    number_of_objects: n,
+                };
+                return Err((None, kind));
+            }
+
+            let mut objects = Vec::new();
+            for (i, token) in tokens.iter().enumerate() {
+                match token.value {
+                    "1" => objects.push(i),
+                    "0" => {}
+                    value => {
+                        let kind = SolutionErrorKind::InvalidBit {
+                            value: value.to_string(),
+                        };
+                        return Err((Some(token.line), kind));
+                    }
+                }
+            }
+            objects
+        }
+
+        SolutionFormat::Ids => {
+            let indices = scenario
+                .labels
+                .ids
+                .iter()
+                .enumerate()
+                .map(|(i, id)| (id.as_str(), i))
+                .collect::<HashMap<_, _>>();
+
+            tokens
+                .iter()
+                .map(|token| {
+                    indices.get(token.value).cloned().ok_or_else(|| {
+                        let kind = SolutionErrorKind::UnknownId {
+                            id: token.value.to_string(),
+                        };
+                        (Some(token.line), kind)
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?
+        }
+
+        SolutionFormat::Indices => tokens
+            .iter()
+            .map(|token| match token.value.parse::<usize>() {
+                Ok(index) if index < n => Ok(index),
+                Ok(index) => Err((
+                    Some(token.line),
+                    SolutionErrorKind::IndexOutOfRange {
+                        index,
+                        number_of_objects: n,
+                    },
+                )),
+                Err(err) => Err((
+                    Some(token.line),
+                    SolutionErrorKind::InvalidIndex {
+                        value: token.value.to_string(),
+                        reason: err.to_string(),
+                    },
+                )),
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+    };
+
+    // every object can be chosen at most once
+    let mut chosen = vec![false; n];
+    for (object, token) in objects.iter().zip(tokens.iter()) {
+        if chosen[*object] {
+            let kind = SolutionErrorKind::ChosenTwice {
+                object: scenario.labels.id(*object),
+            };
+            return Err((Some(token.line), kind));
+        }
+        chosen[*object] = true;
+    }
+
+    Ok(objects)
+}
+
+#[derive(Debug)]
+pub struct SolutionError {
+    pub path: String,
+    /// 1-based line, not set for errors which concern the whole file
+    pub line: Option<usize>,
+    pub kind: SolutionErrorKind,
+}
+
+#[derive(Debug)]
+pub enum SolutionErrorKind {
+    Io(io::Error),
+
+    InvalidIndex {
+        value: String,
+        reason: String,
+    },
+
+    IndexOutOfRange {
+        index: usize,
+        number_of_objects: usize,
+    },
+
+    UnknownId {
+        id: String,
+    },
+
+    InvalidBit {
+        value: String,
+    },
+
+    BitCount {
+        found: usize,
+        number_of_objects: usize,
+    },
+
+    ChosenTwice {
+        object: String,
+    },
+
+    /// Solver solution names a variable which is not an object of the scenario
+    UnknownVariable {
+        name: String,
+    },
+
+    NotBinary {
+        name: String,
+        value: String,
+    },
+}
+
+impl SolutionError {
+    fn new(path: &str, line: Option<usize>, kind: SolutionErrorKind) -> SolutionError {
+        SolutionError {
+            path: path.to_string(),
+            line,
+            kind,
+        }
+    }
+}
+
+impl Error for SolutionError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.kind {
+            SolutionErrorKind::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl Display for SolutionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.path)?;
+        if let Some(line) = self.line {
+            write!(f, ":{}", line)?;
+        }
+        write!(f, ": {}", self.kind)
+    }
+}
+
+impl Display for SolutionErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        use SolutionErrorKind::*;
+
+        match self {
+            Io(err) => write!(f, "{}", err),
+            InvalidIndex { value, reason } => {
+                write!(f, "`{}` is not a valid index: {}", value, reason)
+            }
+            IndexOutOfRange {
+                index,
+                number_of_objects,
+            } => write!(
+                f,
+                "index {} is out of range, scenario has {} objects",
+                index, number_of_objects
+            ),
+            UnknownId { id } => write!(f, "scenario has no object with id `{}`", id),
+            InvalidBit { value } => write!(f, "`{}` is not a valid bit, expected 0 or 1", value),
+            BitCount {
+                found,
+                number_of_objects,
+            } => write!(
+                f,
+                "solution has {} bits, expected one for each of {} objects",
+                found, number_of_objects
+            ),
+            ChosenTwice { object } => write!(f, "object {} is chosen more then once", object),
+            UnknownVariable { name } => write!(f, "scenario has no variable {}", name),
+            NotBinary { name, value } => {
+                write!(f, "value {} of variable {} is not binary", value, name)
+            }
+        }
+    }
+}
+
+/// Totals of a solution checked against the limits of the scenario
+#[derive(Debug)]
+pub struct Verification<N> {
+    pub number_of_objects: usize,
+    pub weight: N,
+    pub size: N,
+    pub cost: N,
+    pub max_weight: N,
+    pub max_size: N,
+    /// Every limit the solution exceeds
+    pub violations: Vec<Violation<N>>,
+}
+
+#[derive(Debug)]
+pub struct Violation<N> {
+    pub resource: &'static str,
+    pub total: N,
+    /// Capacity together with the tolerance
+    pub limit: N,
+    /// How much `total` exceeds `limit`
+    pub excess: N,
+}
+
+impl<N: Number> Verification<N> {
+    pub fn is_feasible(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+impl<N: Number> Scenario<N> {
+    /// Recomputes totals of the chosen objects
+    ///
+    /// Totals cannot overflow as they never exceed the sums of whole columns.
+    pub fn verify(&self, objects: &[usize]) -> Verification<N> {
+        let sum = |values: &[N]| objects.iter().fold(N::zero(), |acc, i| acc + values[*i]);
+        let (weight, size) = (sum(&self.weights), sum(&self.sizes));
+
+        let violations = [
+            ("weight", weight, self.weight_limit()),
+            ("size", size, self.size_limit()),
+        ]
+        .iter()
+        .filter(|(_, total, limit)| total > limit)
+        .map(|(resource, total, limit)| Violation {
+            resource,
+            total: *total,
+            limit: *limit,
+            excess: *total - *limit,
+        })
+        .collect();
+
+        Verification {
+            number_of_objects: objects.len(),
+            weight,
+            size,
+            cost: sum(&self.costs),
+            max_weight: self.max_weight,
+            max_size: self.max_size,
+            violations,
+        }
+    }
+}
+
+impl<N: Number> Display for Verification<N> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Chosen objects {}", self.number_of_objects)?;
+        writeln!(f, "Total weight {} of {}", self.weight, self.max_weight)?;
+        writeln!(f, "Total size {} of {}", self.size, self.max_size)?;
+        writeln!(f, "Total cost {}", self.cost)?;
+
+        for violation in self.violations.iter() {
+            writeln!(
+                f,
+                "Violated: total {} {} exceeds the limit {} by {}",
+                violation.resource, violation.total, violation.limit, violation.excess
+            )?;
+        }
+
+        if self.is_feasible() {
+            write!(f, "Feasible")
+        } else {
+            write!(f, "Infeasible")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scenario::Labels;
+
+    fn scenario() -> Scenario<i64> {
+        let mut scenario = Scenario {
+            weights: vec![2, 3, 4].into(),
+            sizes: vec![1, 2, 3].into(),
+            costs: vec![10, 12, 1].into(),
+            number_of_objects: 3,
+            max_weight: 4,
+            max_size: 2,
+            tolerance: 0,
+            labels: Labels::default(),
+        };
+        scenario.labels.ids = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        scenario
+    }
+
+    fn parse(content: &str) -> Vec<usize> {
+        parse_solution(content, &scenario(), None)
+            .map_err(|x| x.1)
+            .unwrap()
+    }
+
+    #[test]
+    fn solution_format_is_detected() {
+        assert_eq!(parse("1 0 1"), vec![0, 2]);
+        assert_eq!(parse("c,a"), vec![2, 0]);
+        assert_eq!(parse("# old run\n2\n1\n"), vec![2, 1]);
+        assert_eq!(parse("Best objects b,c"), vec![1, 2]);
+    }
+
+    #[test]
+    fn invalid_solutions_are_rejected() {
+        let error = |content, format| parse_solution(content, &scenario(), format).unwrap_err();
+
+        assert!(matches!(
+            error("0\n3", None),
+            (Some(2), SolutionErrorKind::IndexOutOfRange { index: 3, .. })
+        ));
+        assert!(matches!(
+            error("a,d", Some(SolutionFormat::Ids)),
+            (Some(1), SolutionErrorKind::UnknownId { .. })
+        ));
+        assert!(matches!(
+            error("1,1", None),
+            (Some(1), SolutionErrorKind::ChosenTwice { .. })
+        ));
+        assert!(matches!(
+            error("1 0", Some(SolutionFormat::Bits)),
+            (None, SolutionErrorKind::BitCount { found: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn every_violated_limit_is_reported() {
+        let scenario = scenario();
+
+        let verification = scenario.verify(&[0]);
+        assert!(verification.is_feasible());
+        assert_eq!(verification.cost, 10);
+
+        let verification = scenario.verify(&[0, 1]);
+        assert_eq!(
+            verification
+                .violations
+                .iter()
+                .map(|x| (x.resource, x.excess))
+                .collect::<Vec<_>>(),
+            vec![("weight", 1), ("size", 1)]
+        );
+        assert!(scenario
+            .with_tolerance(1)
+            .unwrap()
+            .verify(&[0, 1])
+            .is_feasible());
+    }
+}
@@ -0,0 +1,322 @@
+use crate::number::Number;
+use crate::scenario::solution::SolutionErrorKind;
+use crate::scenario::Scenario;
+use std::io::{self, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+/// Input format of external MIP solvers
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SolverFormat {
+    /// CPLEX LP format
+    Lp,
+    /// Free-format MPS
+    Mps,
+}
+
+impl SolverFormat {
+    pub const VARIANTS: [&'static str; 2] = ["lp", "mps"];
+
+    /// Takes the format from the extension of `path`
+    pub fn detect(path: &str) -> Option<SolverFormat> {
+        let extension = Path::new(path).extension()?.to_string_lossy();
+        extension.to_ascii_lowercase().parse().ok()
+    }
+}
+
+impl FromStr for SolverFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "lp" => Ok(SolverFormat::Lp),
+            "mps" => Ok(SolverFormat::Mps),
+            _ => Err(format!(
+                "unknown solver format {}, expected one of {}",
+                s,
+                SolverFormat::VARIANTS.join(", ")
+            )),
+        }
+    }
+}
+
+/// Objects are exported as binary variables `x0`, `x1`, ... named after their indices
+fn variable(i: usize) -> String {
+    format!("x{}", i)
+}
+
+fn variable_index(name: &str) -> Option<usize> {
+    name.strip_prefix('x')?.parse().ok()
+}
+
+/// CPLEX LP line length limit is 560 characters
+const TERMS_PER_LINE: usize = 8;
+
+impl<N: Number> Scenario<N> {
+    /// Writes scenario as a 0-1 program maximizing cost with one
+    /// constraint per resource
+    pub fn write_solver<W: Write>(&self, format: SolverFormat, writer: W) -> io::Result<()> {
+        match format {
+            SolverFormat::Lp => self.write_lp(writer),
+            SolverFormat::Mps => self.write_mps(writer),
+        }
+    }
+
+    fn write_lp<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let n = self.number_of_objects;
+
+        writeln!(writer, "\\ 2D knapsack problem with {} objects", n)?;
+        writeln!(writer, "Maximize")?;
+        self.write_lp_row(&mut writer, "cost", |i| self.costs[i])?;
+        writeln!(writer)?;
+
+        writeln!(writer, "Subject To")?;
+        self.write_lp_row(&mut writer, "weight", |i| self.weights[i])?;
+        writeln!(writer, " <= {}", self.weight_limit())?;
+        self.write_lp_row(&mut writer, "size", |i| self.sizes[i])?;
+        writeln!(writer, " <= {}", self.size_limit())?;
+
+        writeln!(writer, "Binary")?;
+        for line in (0..n).collect::<Vec<_>>().chunks(TERMS_PER_LINE) {
+            let names = line.iter().map(|i| variable(*i)).collect::<Vec<_>>();
+            writeln!(writer, " {}", names.join(" "))?;
+        }
+
+        writeln!(writer, "End")
+    }
+
+    /// Writes ` name: c0 x0 + c1 x1 ...` without the final line break
+    fn write_lp_row<W: Write>(
+        &self,
+        writer: &mut W,
+        name: &str,
+        coefficient: impl Fn(usize) -> N,
+    ) -> io::Result<()> {
+        write!(writer, " {}:", name)?;
+        for i in 0..self.number_of_objects {
+            if i > 0 {
+                write!(writer, " +")?;
+                if i % TERMS_PER_LINE == 0 {
+                    write!(writer, "\n  ")?;
+                }
+            }
+            write!(writer, " {} {}", coefficient(i), variable(i))?;
+        }
+        Ok(())
+    }
+
+    fn write_mps<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(writer, "NAME genet")?;
+        writeln!(writer, "OBJSENSE\n    MAX")?;
+        writeln!(writer, "ROWS\n N cost\n L weight\n L size")?;
+
+        writeln!(writer, "COLUMNS")?;
+        for i in 0..self.number_of_objects {
+            writeln!(
+                writer,
+                "    {} cost {} weight {}",
+                variable(i),
+                self.costs[i],
+                self.weights[i]
+            )?;
+            writeln!(writer, "    {} size {}", variable(i), self.sizes[i])?;
+        }
+
+        writeln!(writer, "RHS")?;
+        writeln!(
+            writer,
+            "    RHS weight {} size {}",
+            self.weight_limit(),
+            self.size_limit()
+        )?;
+
+        writeln!(writer, "BOUNDS")?;
+        for i in 0..self.number_of_objects {
+            writeln!(writer, " BV BND {}", variable(i))?;
+        }
+
+        writeln!(writer, "ENDATA")
+    }
+}
+
+/// Solution written by an external solver for an exported scenario
+#[derive(Debug)]
+pub struct SolverSolution {
+    /// Indices of the chosen objects
+    pub objects: Vec<usize>,
+    /// Objective value declared by the solver
+    pub objective: Option<f64>,
+}
+
+/// Values this far from 0 or 1 are not accepted as binary
+const INTEGRALITY_TOLERANCE: f64 = 1e-4;
+
+/// Returns true if `content` looks like a solution file of a solver
+pub(super) fn is_solver_solution(content: &str) -> bool {
+    content.lines().any(|line| {
+        line.contains("<variable ") || {
+            let tokens = line.split_whitespace().collect::<Vec<_>>();
+            tokens
+                .windows(2)
+                .any(|pair| variable_index(pair[0]).is_some() && pair[1].parse::<f64>().is_ok())
+        }
+    })
+}
+
+/// Reads solution files of common MIP solvers
+///
+/// Understands CPLEX XML `.sol` files and the `name value` lines written by
+/// Gurobi, SCIP, HiGHS and CBC, where CBC puts the column index before the
+/// name. Variables which are not listed are 0. Lines mentioning the objective
+/// hold its value as the last number, dual values are ignored.
+pub(super) fn parse_solver_solution(
+    content: &str,
+    number_of_objects: usize,
+) -> Result<SolverSolution, (Option<usize>, SolutionErrorKind)> {
+    let mut chosen = vec![false; number_of_objects];
+    let mut objective = None;
+
+    for (i, line) in content.lines().enumerate() {
+        let line_number = Some(i + 1);
+        let lowercase = line.to_ascii_lowercase();
+
+        if lowercase.starts_with("# dual") {
+            break;
+        }
+
+        let value = if line.contains("<variable ") {
+            attribute(line, "name").zip(attribute(line, "value"))
+        } else if lowercase.contains("objective") {
+            objective = attribute(line, "objectiveValue")
+                .or_else(|| line.split_whitespace().last())
+                .and_then(|x| x.parse::<f64>().ok())
+                .or(objective);
+            None
+        } else {
+            let tokens = line.split_whitespace().collect::<Vec<_>>();
+            tokens
+                .iter()
+                .position(|x| variable_index(x).is_some())
+                .and_then(|i| Some((tokens[i], *tokens.get(i + 1)?)))
+        };
+
+        let (name, value) = match value {
+            Some(value) => value,
+            None => continue,
+        };
+
+        let index = match variable_index(name) {
+            Some(index) if index < number_of_objects => index,
+            _ => {
+                let kind = SolutionErrorKind::UnknownVariable {
+                    name: name.to_string(),
+                };
+                return Err((line_number, kind));
+            }
+        };
+
+        let not_binary = || {
+            let kind = SolutionErrorKind::NotBinary {
+                name: name.to_string(),
+                value: value.to_string(),
+            };
+            (line_number, kind)
+        };
+        let value = value.parse::<f64>().map_err(|_| not_binary())?;
+        chosen[index] = if (value - 1.0).abs() <= INTEGRALITY_TOLERANCE {
+            true
+        } else if value.abs() <= INTEGRALITY_TOLERANCE {
+            false
+        } else {
+            return Err(not_binary());
+        };
+    }
+
+    Ok(SolverSolution {
+        objects: (0..number_of_objects).filter(|i| chosen[*i]).collect(),
+        objective,
+    })
+}
+
+/// Value of XML attribute `name="value"`
+fn attribute<'a>(line: &'a str, name: &str) -> Option<&'a str> {
+    let start = line.find(&format!(" {}=\"", name))? + name.len() + 3;
+    let length = line[start..].find('"')?;
+    Some(&line[start..start + length])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scenario::Labels;
+
+    fn scenario() -> Scenario<i64> {
+        Scenario {
+            weights: vec![2, 3, 4].into(),
+            sizes: vec![1, 2, 3].into(),
+            costs: vec![10, 12, 1].into(),
+            number_of_objects: 3,
+            max_weight: 4,
+            max_size: 2,
+            tolerance: 0,
+            labels: Labels::default(),
+        }
+    }
+
+    fn export(format: SolverFormat) -> String {
+        let mut content = Vec::new();
+        scenario().write_solver(format, &mut content).unwrap();
+        String::from_utf8(content).unwrap()
+    }
+
+    #[test]
+    fn lp_file_has_objective_constraints_and_binaries() {
+        let lp = export(SolverFormat::Lp);
+
+        assert!(lp.contains("Maximize\n cost: 10 x0 + 12 x1 + 1 x2\n"));
+        assert!(lp.contains("Subject To\n weight: 2 x0 + 3 x1 + 4 x2 <= 4\n"));
+        assert!(lp.contains(" size: 1 x0 + 2 x1 + 3 x2 <= 2\n"));
+        assert!(lp.ends_with("Binary\n x0 x1 x2\nEnd\n"));
+    }
+
+    #[test]
+    fn mps_file_declares_binary_columns() {
+        let mps = export(SolverFormat::Mps);
+
+        assert!(mps.contains("    x1 cost 12 weight 3\n    x1 size 2\n"));
+        assert!(mps.contains("    RHS weight 4 size 2\n"));
+        assert!(mps.contains(" BV BND x2\n"));
+    }
+
+    #[test]
+    fn solutions_of_common_solvers_are_read() {
+        let gurobi = "# Objective value = 10\nx0 1\nx1 0\nx2 -0\n";
+        let cplex = r#"<CPLEXSolution version="1.2">
+ <header objectiveValue="10" primalFeasible="1" dualFeasible="1"/>
+ <variables>
+  <variable name="x0" index="0" value="0.99999999"/>
+  <variable name="x2" index="2" value="0"/>
+ </variables>"#;
+        let cbc = "Optimal - objective value 10.00000000\n      0 x0   1   -10\n";
+        let highs = "Objective 10\n# Columns 3\nx0 1\n# Dual solution values\nx1 1\n";
+
+        for content in [gurobi, cplex, cbc, highs].iter() {
+            assert!(is_solver_solution(content));
+            let solution = parse_solver_solution(content, 3).unwrap();
+            assert_eq!(solution.objects, vec![0], "{}", content);
+            assert_eq!(solution.objective, Some(10.0), "{}", content);
+        }
+    }
+
+    #[test]
+    fn fractional_and_unknown_variables_are_rejected() {
+        assert!(matches!(
+            parse_solver_solution("x0 0.5\n", 3),
+            Err((Some(1), SolutionErrorKind::NotBinary { .. }))
+        ));
+        assert!(matches!(
+            parse_solver_solution("x0 1\nx3 1\n", 3),
+            Err((Some(2), SolutionErrorKind::UnknownVariable { .. }))
+        ));
+    }
+}
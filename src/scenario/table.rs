@@ -0,0 +1,211 @@
+use crate::number::Number;
+use crate::scenario::error::ScenarioErrorKind::{InvalidCsv, MissingCapacity, MissingColumn};
+use crate::scenario::error::ScenarioErrors;
+use crate::scenario::{Errors, Loaded, Place, Scenario};
+use csv::{ReaderBuilder, StringRecord, Trim};
+
+const MAX_WEIGHT: &str = "max_weight";
+const MAX_SIZE: &str = "max_size";
+
+/// Loads scenario in the `ScenarioFormat::Table` format
+pub(super) fn load<N: Number>(
+    content: &str,
+    mut errors: Errors,
+) -> Result<Scenario<N>, ScenarioErrors> {
+    // `# key = value` lines before the table
+    let mut preamble_lines = 0;
+    let mut table_start = 0;
+    let mut properties = Vec::new();
+    for line in content.split_inclusive('\n') {
+        let trimmed = line.trim();
+        if !trimmed.is_empty() && !trimmed.starts_with('#') {
+            break;
+        }
+
+        preamble_lines += 1;
+        table_start += line.len();
+
+        // lines without `=` are plain comments
+        if let Some((key, value)) = trimmed.trim_start_matches('#').split_once('=') {
+            let equals = line.find('=').unwrap();
+            let after = &line[equals + 1..];
+            let offset = equals + 1 + after.len() - after.trim_start().len();
+            let column = line[..offset].chars().count() + 1;
+            properties.push((preamble_lines, column, key.trim(), value.trim()));
+        }
+    }
+
+    let mut capacities = [None, None];
+    let mut capacity_places = [Place::default(), Place::default()];
+    let mut loaded = Loaded::<N>::new();
+
+    for (line, column, key, value) in properties {
+        match key {
+            MAX_WEIGHT | MAX_SIZE => {
+                let i = if key == MAX_WEIGHT { 0 } else { 1 };
+                capacity_places[i] = Place::at(line, column);
+                capacities[i] = errors.parse_number::<N>(Place::at(line, column), value)?;
+            }
+            _ => {
+                loaded
+                    .labels
+                    .metadata
+                    .insert(key.to_string(), value.to_string());
+            }
+        }
+    }
+
+    for (i, name) in [MAX_WEIGHT, MAX_SIZE].iter().enumerate() {
+        if capacity_places[i].line.is_none() {
+            errors.push(Place::default(), MissingCapacity { name })?;
+        }
+    }
+
+    let mut reader = ReaderBuilder::new()
+        .trim(Trim::All)
+        .comment(Some(b'#'))
+        .from_reader(&content.as_bytes()[table_start..]);
+    let header_line = preamble_lines + 1;
+
+    let header = reader.headers().map_err(|err| {
+        let kind = InvalidCsv {
+            reason: err.to_string(),
+        };
+        errors.fail(Place::at(header_line, 1), kind)
+    })?;
+    let columns = Columns::new(header);
+
+    let mut missing = false;
+    for (column, name) in columns.required.iter().zip(Columns::REQUIRED.iter()) {
+        if column.is_none() {
+            errors.push(Place::at(header_line, 1), MissingColumn { name })?;
+            missing = true;
+        }
+    }
+    if missing {
+        return Err(errors.finish().unwrap_err());
+    }
+
+    let mut record = StringRecord::new();
+    loop {
+        match reader.read_record(&mut record) {
+            Ok(true) => {}
+            Ok(false) => break,
+            Err(err) => {
+                let line = err
+                    .position()
+                    .map_or(header_line, |x| preamble_lines + x.line() as usize);
+                let kind = InvalidCsv {
+                    reason: err.to_string(),
+                };
+                errors.push(Place::at(line, 1), kind)?;
+                continue;
+            }
+        }
+
+        let line = preamble_lines + record.position().unwrap().line() as usize;
+        let [id, weight, size, cost] = columns.required.map(|i| &record[i.unwrap()]);
+        let places = ["weight", "size", "cost"].map(|name| Place::cell(line, name));
+
+        if loaded.parse(&mut errors, [weight, size, cost], places)? {
+            let name = columns
+                .name
+                .map(|i| &record[i])
+                .filter(|name| !name.is_empty())
+                .map(str::to_string);
+            loaded.label(&mut errors, id.to_string(), name, Place::cell(line, "id"))?;
+        }
+    }
+
+    let [max_weight, max_size] = capacities;
+    loaded.finish(errors, max_weight, max_size, capacity_places)
+}
+
+/// Indices of the known columns
+struct Columns {
+    /// `id`, `weight`, `size` and `cost` columns
+    required: [Option<usize>; 4],
+    name: Option<usize>,
+}
+
+impl Columns {
+    const REQUIRED: [&'static str; 4] = ["id", "weight", "size", "cost"];
+
+    /// Finds columns by their case insensitive names, other columns are ignored
+    fn new(header: &StringRecord) -> Columns {
+        let find = |name: &str| header.iter().position(|x| x.eq_ignore_ascii_case(name));
+
+        Columns {
+            required: Columns::REQUIRED.map(find),
+            name: find("name"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scenario::error::ScenarioErrorKind::{MissingCapacity, MissingColumn};
+    use crate::scenario::{LoadOptions, Scenario};
+    use std::env::temp_dir;
+    use std::process;
+
+    fn write_scenario(name: &str, content: &str) -> String {
+        let path = temp_dir().join(format!("genet_{}_{}.csv", name, process::id()));
+        std::fs::write(&path, content).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn load_reads_named_columns_in_any_order() {
+        let path = write_scenario(
+            "table",
+            "# Spring inventory\n# max_weight = 3\n# max_size = 2\n# source = warehouse\n\
+             cost,Weight,size,id,name,color\n\
+             10,2,1,SKU-1,\"Chair, oak\",red\n\
+             12,3,2,SKU-2,,blue\n\
+             1,4,3,SKU-3,Lamp,green\n",
+        );
+
+        let scenario = Scenario::<i64>::load(path).unwrap();
+        assert_eq!(scenario.weights.as_slice(), &[2, 3, 4]);
+        assert_eq!(scenario.costs.as_slice(), &[10, 12, 1]);
+        assert_eq!((scenario.max_weight, scenario.max_size), (3, 2));
+        assert_eq!(scenario.labels.ids, vec!["SKU-1", "SKU-2", "SKU-3"]);
+        assert_eq!(scenario.labels.names[0].as_deref(), Some("Chair, oak"));
+        assert_eq!(scenario.labels.names[1], None);
+        assert_eq!(scenario.labels.metadata["source"], "warehouse");
+    }
+
+    #[test]
+    fn errors_point_at_the_line_and_column_name() {
+        let path = write_scenario(
+            "table_invalid",
+            "# max_weight = 3\n# max_size = 2\nid,weight,size,cost\na,2,1,1\nb,x,2,1\n",
+        );
+
+        let err = &Scenario::<i64>::load(path.clone()).unwrap_err().0[0];
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "{}:5: weight: `x` is not a valid i64: invalid digit found in string",
+                path
+            )
+        );
+    }
+
+    #[test]
+    fn missing_capacities_and_columns_are_reported() {
+        let path = write_scenario("table_missing", "# max_size = 2\nid,weight,cost\na,2,1\n");
+        let options = LoadOptions {
+            collect_errors: true,
+        };
+
+        let errors = Scenario::<i64>::load_with(path, &options).unwrap_err();
+        assert!(matches!(
+            errors.0[0].kind,
+            MissingCapacity { name: "max_weight" }
+        ));
+        assert!(matches!(errors.0[1].kind, MissingColumn { name: "size" }));
+        assert_eq!(errors.0[1].line, Some(2));
+    }
+}
@@ -1,33 +1,115 @@
 use crate::consts::DynResult;
-use crate::consts::Number;
+use crate::number::Number;
+use crate::population::cellular::NeighbourhoodStats;
 use crate::population::config::Config as PopulationConfig;
-use crate::population::Population;
-use crate::scenario::Scenario;
+use crate::population::control::Rates;
+use crate::population::diversity::DiversityCounts;
+use crate::population::hall_of_fame::HallOfFame;
+use crate::population::island::{Islands, MigrationConfig};
+use crate::population::network::{NetworkConfig, Node};
+use crate::population::operators::OperatorStats;
+use crate::population::stats::GenerationStats;
+use crate::scenario::{LoadOptions, Scenario, ScenarioFormat};
+use rand::thread_rng;
 use std::fs::File;
-use std::io::Write;
+use std::io::{self, BufWriter, Write};
+use std::sync::atomic::Ordering;
 use std::time::{SystemTime, UNIX_EPOCH};
 
-pub fn train(
+/// Options of the training loop
+#[derive(Debug)]
+pub struct TrainConfig {
+    pub generation_limit: usize,
+    /// Stops training once this many individuals were scored
+    pub evaluation_limit: Option<usize>,
+    /// Stops training when changes between generations are smaller then epsilon
+    pub epsilon: Option<f64>,
+    /// Trains on the core problem left by `Scenario::reduce`
+    pub reduce: bool,
+    /// Number of niches whose best individuals are printed when the islands use niching
+    pub niches: usize,
+    pub migration: MigrationConfig,
+    /// CSV file statistics of each island and of all of them are written to every generation
+    pub stats_file: Option<String>,
+    /// CSV file statistics of the neighbourhoods of each cellular island are written to every generation
+    pub neighbourhood_stats_file: Option<String>,
+    /// CSV file usage and credit of each operator of each island are written to every generation
+    pub operator_stats_file: Option<String>,
+    /// Exchanges migrants with islands of other processes
+    pub network: Option<NetworkConfig>,
+    /// Number of the best distinct feasible solutions of the whole run kept in the hall of fame
+    pub hall_of_fame: Option<usize>,
+    /// Number of objects in which solutions of the hall of fame differ at least
+    pub hall_of_fame_distance: usize,
+    /// CSV file the hall of fame is written to at the end, it is printed otherwise
+    pub hall_of_fame_file: Option<String>,
+}
+
+pub fn train<N: Number>(
     input_file: String,
     result_file: Option<String>,
-    population_config: PopulationConfig,
-    generation_limit: usize,
-    epsilon: Option<f64>,
+    island_configs: Vec<PopulationConfig>,
+    train_config: &TrainConfig,
+    tolerance: N,
+    load_options: &LoadOptions,
 ) -> DynResult<()> {
+    let format = ScenarioFormat::of_file(&input_file)?;
+
     let ts = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .expect("Time went backwards");
 
-    let scenario = Scenario::load(input_file)?;
+    let scenario = Scenario::<N>::load_with(input_file, load_options)?.with_tolerance(tolerance)?;
 
     let tp = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .expect("Time went backwards");
-    println!("Scenario loading {:?}", tp - ts);
+    println!(
+        "Scenario loading {:?} ({} format, {} objects)",
+        tp - ts,
+        format.name(),
+        scenario.number_of_objects
+    );
 
-    println!("{:?}", &population_config);
+    let reduction = if train_config.reduce {
+        let reduction = scenario.reduce();
+        println!(
+            "Reduction: {} never fit, {} dominated, {} chosen, {} excluded, {} of {} objects left",
+            reduction.never_fit.len(),
+            reduction.dominated.len(),
+            reduction.chosen.len(),
+            reduction.excluded.len(),
+            reduction.core.number_of_objects,
+            scenario.number_of_objects
+        );
+        Some(reduction)
+    } else {
+        None
+    };
+    let (core, chosen_cost) = match &reduction {
+        Some(reduction) => (reduction.core.clone(), reduction.chosen_cost),
+        // the whole scenario is kept to report totals of the expanded solutions
+        None => (scenario.clone(), N::zero()),
+    };
 
-    let mut population = Population::new(scenario, population_config);
+    for config in island_configs.iter() {
+        println!("{:?}", config);
+    }
+
+    // everything was decided by the reduction, there is nothing to search for
+    let generation_limit = if core.number_of_objects == 0 {
+        0
+    } else {
+        train_config.generation_limit
+    };
+
+    let mut node = match &train_config.network {
+        Some(network) => Some(start_node(network, &core)?),
+        None => None,
+    };
+
+    let niching = island_configs[0].niching;
+    let mut islands = Islands::new(core, island_configs, train_config.migration.clone())?;
 
     let te = SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -36,44 +118,375 @@ pub fn train(
 
     let mut results = Vec::new();
 
-    let mut previous_best_individual = Number::max_value();
+    let mut stats_file = match &train_config.stats_file {
+        Some(stats_file) => {
+            let header = format!(
+                "generation,evaluations,island,{},{},{}",
+                GenerationStats::<N>::CSV_HEADER,
+                DiversityCounts::CSV_HEADER,
+                Rates::CSV_HEADER
+            );
+            Some(create_stats_file(stats_file, &header)?)
+        }
+        None => None,
+    };
+
+    let mut neighbourhood_stats_file = match &train_config.neighbourhood_stats_file {
+        Some(stats_file) => {
+            let header = format!("generation,island,{}", NeighbourhoodStats::CSV_HEADER);
+            Some(create_stats_file(stats_file, &header)?)
+        }
+        None => None,
+    };
+
+    let mut operator_stats_file = match &train_config.operator_stats_file {
+        Some(stats_file) => {
+            let header = format!("generation,island,{}", OperatorStats::CSV_HEADER);
+            Some(create_stats_file(stats_file, &header)?)
+        }
+        None => None,
+    };
+
+    let mut hall_of_fame = train_config
+        .hall_of_fame
+        .map(|size| HallOfFame::new(size, train_config.hall_of_fame_distance));
+    if let Some(hall_of_fame) = &mut hall_of_fame {
+        islands.update_hall_of_fame(hall_of_fame);
+    }
+
+    let mut previous_best_individual = f64::MAX;
 
     for _ in 0..generation_limit {
-        let best_individual = population.evolve();
+        // the best of each bred generation, the random one is not recorded
+        let mut best_individual = islands.evolve();
+
+        if let Some(node) = &mut node {
+            if islands.is_migration_generation() {
+                exchange_migrants(node, &mut islands);
+                // immigrants may be better than anything bred here
+                best_individual = islands.global_stats().best;
+            }
+        }
+        // scores of the core problem do not include the chosen objects
+        let best_individual = best_individual + chosen_cost;
+
+        if let Some(hall_of_fame) = &mut hall_of_fame {
+            islands.update_hall_of_fame(hall_of_fame);
+        }
 
-        results.push(best_individual);
+        // results are reported per evaluation count so that modes which score
+        // a different number of individuals per generation can be compared
+        results.push((islands.evaluations(), best_individual));
 
-        let delta =
-            ((best_individual - previous_best_individual) as f64).abs() / best_individual as f64;
+        if let Some(file) = &mut stats_file {
+            write_stats(file, results.len(), &islands, chosen_cost)?;
+        }
+
+        if let Some(file) = &mut neighbourhood_stats_file {
+            write_neighbourhood_stats(file, results.len(), &islands, chosen_cost)?;
+        }
+
+        if let Some(file) = &mut operator_stats_file {
+            write_operator_stats(file, results.len(), &islands)?;
+        }
+
+        // computed in f64 because unsigned types cannot represent negative difference
+        let best_individual = best_individual.to_f64();
+        let delta = (best_individual - previous_best_individual).abs() / best_individual;
 
         previous_best_individual = best_individual;
 
-        if let Some(epsilon) = epsilon {
+        if let Some(epsilon) = train_config.epsilon {
             if delta <= epsilon {
                 break;
             }
         }
+
+        if let Some(evaluation_limit) = train_config.evaluation_limit {
+            if islands.evaluations() >= evaluation_limit {
+                break;
+            }
+        }
+    }
+
+    if let Some(file) = stats_file {
+        file.into_inner()?.sync_all()?;
+    }
+    if let Some(file) = neighbourhood_stats_file {
+        file.into_inner()?.sync_all()?;
+    }
+    if let Some(file) = operator_stats_file {
+        file.into_inner()?.sync_all()?;
     }
 
     let tf = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .expect("Time went backwards");
     println!("Population evolution {:?}", tf - te);
+    println!("Evaluations {}", islands.evaluations());
+    let diversity = islands.diversity().total;
+    if diversity != DiversityCounts::default() {
+        println!(
+            "Duplicates removed {}, random immigrants {}",
+            diversity.duplicates, diversity.immigrants
+        );
+    }
 
+    let results = results
+        .iter()
+        .map(|(evaluations, best)| format!("{},{}", evaluations, best))
+        .collect::<Vec<String>>()
+        .join("\n");
     if let Some(result_file) = result_file {
-        let results = results
-            .iter()
-            .map(|x| x.to_string())
-            .collect::<Vec<String>>()
-            .join("\n");
-
         let mut file = File::create(&result_file)?;
-        file.write(results.as_bytes())?;
+        file.write_all(results.as_bytes())?;
     } else {
-        println!("{:?}", results);
+        println!("{}", results);
+    }
+
+    if islands.islands().len() > 1 {
+        print_islands(&islands, chosen_cost);
+    }
+
+    if let Some(node) = &node {
+        print_network_stats(node);
+    }
+
+    let original_objects = |objects: &[usize]| match &reduction {
+        Some(reduction) => reduction.expand(objects),
+        None => objects.to_vec(),
+    };
+    let expand = |objects: &[usize]| {
+        original_objects(objects)
+            .iter()
+            .map(|i| scenario.labels.id(*i))
+            .collect::<Vec<_>>()
+            .join(",")
+    };
+    println!("Result {}", islands.best_score() + chosen_cost);
+    println!("Best objects {}", expand(islands.best_objects()));
+
+    if let Some(niching) = niching {
+        print_niches(
+            &islands,
+            niching.radius,
+            train_config.niches,
+            chosen_cost,
+            expand,
+        );
+    }
+
+    if let Some(hall_of_fame) = &hall_of_fame {
+        report_hall_of_fame(
+            hall_of_fame,
+            train_config.hall_of_fame_file.as_deref(),
+            &scenario,
+            original_objects,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Starts listening for migrants of other processes training on `scenario`
+fn start_node<N: Number>(network: &NetworkConfig, scenario: &Scenario<N>) -> DynResult<Node<N>> {
+    let mut node = Node::listen(network.listen.as_str(), scenario, network.timeout)?;
+    for peer in network.peers.iter() {
+        node.add_peer(peer.clone());
+    }
+    println!("Listening for migrants on {}", node.local_address());
+    Ok(node)
+}
+
+/// Sends emigrants of the islands to the peers and lets the received ones in
+fn exchange_migrants<N: Number>(node: &mut Node<N>, islands: &mut Islands<N>) {
+    let mut rng = thread_rng();
+    node.send(&islands.emigrants(&mut rng));
+    islands.immigrate(node.receive(), &mut rng);
+}
+
+fn print_network_stats<N: Number>(node: &Node<N>) {
+    let stats = node.stats();
+    println!(
+        "Migrants sent {} received {}, rejected frames {}",
+        stats.sent.load(Ordering::SeqCst),
+        stats.received.load(Ordering::SeqCst),
+        stats.rejected_frames.load(Ordering::SeqCst)
+    );
+}
+
+/// Creates CSV file with statistics of each generation and writes its header
+fn create_stats_file(stats_file: &str, header: &str) -> io::Result<BufWriter<File>> {
+    let mut file = BufWriter::new(File::create(stats_file)?);
+    writeln!(file, "{}", header)?;
+    Ok(file)
+}
+
+/// Writes statistics of each island and of all of them
+///
+/// `offset` is added to every score, e.g. the cost of objects chosen by the reduction.
+fn write_stats<N: Number, W: Write>(
+    file: &mut W,
+    generation: usize,
+    islands: &Islands<N>,
+    offset: N,
+) -> io::Result<()> {
+    for (i, stats) in islands.stats().iter().enumerate() {
+        let stats = stats.with_offset(offset);
+        let island = &islands.islands()[i];
+        writeln!(
+            file,
+            "{},{},{},{},{},{}",
+            generation,
+            island.evaluations(),
+            i,
+            stats.to_csv(),
+            island.diversity().last.to_csv(),
+            island.rates().to_csv()
+        )?;
+    }
+    let stats = islands.global_stats().with_offset(offset);
+    writeln!(
+        file,
+        "{},{},all,{},{},{}",
+        generation,
+        islands.evaluations(),
+        stats.to_csv(),
+        islands.diversity().last.to_csv(),
+        islands.rates().to_csv()
+    )
+}
+
+/// Writes neighbourhood statistics of each cellular island
+fn write_neighbourhood_stats<N: Number, W: Write>(
+    file: &mut W,
+    generation: usize,
+    islands: &Islands<N>,
+    offset: N,
+) -> io::Result<()> {
+    for (i, island) in islands.islands().iter().enumerate() {
+        if let Some(stats) = island.neighbourhood_stats() {
+            let stats = stats.with_offset(offset.to_f64());
+            writeln!(file, "{},{},{}", generation, i, stats.to_csv())?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes usage and credit of each operator of each island
+fn write_operator_stats<N: Number, W: Write>(
+    file: &mut W,
+    generation: usize,
+    islands: &Islands<N>,
+) -> io::Result<()> {
+    for (i, island) in islands.islands().iter().enumerate() {
+        for stats in island.operator_stats().unwrap_or_default() {
+            writeln!(file, "{},{},{}", generation, i, stats.to_csv())?;
+        }
+    }
+    Ok(())
+}
+
+/// Prints the best individual of each island and statistics of its last generation
+fn print_islands<N: Number>(islands: &Islands<N>, offset: N) {
+    for (i, island) in islands.islands().iter().enumerate() {
+        let stats = GenerationStats::new(island.scores().iter()).with_offset(offset);
+        println!(
+            "Island {} best {} last generation best {} mean {:.2}",
+            i,
+            island.best_score() + offset,
+            stats.best,
+            stats.mean
+        );
+    }
+}
+
+/// Prints the number of niches and the best individual of the first `count` of them
+///
+/// `describe` turns indices of objects into the ids which are printed.
+fn print_niches<N: Number>(
+    islands: &Islands<N>,
+    radius: usize,
+    count: usize,
+    offset: N,
+    describe: impl Fn(&[usize]) -> String,
+) {
+    let niches = islands.niches(radius);
+    println!("Niches {}", niches.len());
+    for (i, niche) in niches.iter().take(count).enumerate() {
+        println!(
+            "Niche {} best {} members {} objects {}",
+            i,
+            niche.score + offset,
+            niche.members,
+            describe(&niche.objects)
+        );
     }
+}
 
-    println!("Result {}", results.last().unwrap());
+/// Writes the hall of fame into `file` as CSV, or prints it if there is no file
+///
+/// Solutions are verified against the whole `scenario`, `original_objects` maps
+/// their objects back into it.
+fn report_hall_of_fame<N: Number>(
+    hall_of_fame: &HallOfFame<N>,
+    file: Option<&str>,
+    scenario: &Scenario<N>,
+    original_objects: impl Fn(&[usize]) -> Vec<usize>,
+) -> DynResult<()> {
+    let entries = hall_of_fame.entries();
+    let mut file = match file {
+        Some(file) => Some(csv::Writer::from_path(file)?),
+        None => {
+            println!("Hall of fame {}", entries.len());
+            None
+        }
+    };
+    if let Some(file) = &mut file {
+        file.write_record([
+            "rank",
+            "cost",
+            "weight",
+            "max_weight",
+            "size",
+            "max_size",
+            "objects",
+        ])?;
+    }
 
+    for (i, entry) in entries.iter().enumerate() {
+        let objects = original_objects(&entry.objects);
+        let verification = scenario.verify(&objects);
+        let objects = objects
+            .iter()
+            .map(|i| scenario.labels.id(*i))
+            .collect::<Vec<_>>()
+            .join(",");
+        match &mut file {
+            Some(file) => file.write_record([
+                (i + 1).to_string(),
+                verification.cost.to_string(),
+                verification.weight.to_string(),
+                verification.max_weight.to_string(),
+                verification.size.to_string(),
+                verification.max_size.to_string(),
+                objects,
+            ])?,
+            None => println!(
+                "Hall of fame {} cost {} weight {} of {} size {} of {} objects {}",
+                i + 1,
+                verification.cost,
+                verification.weight,
+                verification.max_weight,
+                verification.size,
+                verification.max_size,
+                objects
+            ),
+        }
+    }
+
+    if let Some(file) = file {
+        file.into_inner()?.sync_all()?;
+    }
     Ok(())
 }